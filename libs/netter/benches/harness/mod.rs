@@ -0,0 +1,91 @@
+// harness is a small criterion-style benchmark harness, kept in the crate so
+// that the benchmarks need no dependencies: each benchmark is warmed up,
+// then timed over a number of samples, and the median and spread of the
+// time per input are reported along with the throughput. Every benchmark
+// takes the FILTER argument, which picks the benchmarks whose "group/name"
+// contains it.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// WARM_UP is how long each benchmark runs before it is measured, SAMPLES is
+// how many samples are taken and SAMPLE_TIME is roughly how long each takes.
+const WARM_UP: Duration = Duration::from_millis(300);
+const SAMPLES: usize = 30;
+const SAMPLE_TIME: Duration = Duration::from_millis(30);
+
+pub struct Bench {
+    filter: Option<String>,
+}
+
+impl Bench {
+    // from_args takes the filter from the command line.
+    pub fn from_args() -> Bench {
+        let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+        Bench { filter }
+    }
+
+    // run times f over every input, and reports the time per input.
+    pub fn run<T>(&mut self, group: &str, name: &str, inputs: &[T], mut f: impl FnMut(&T) -> bool) {
+        let id = format!("{}/{}", group, name);
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !id.contains(filter))
+        {
+            return;
+        }
+
+        let mut pass = || {
+            let mut ok = 0;
+            for input in inputs {
+                ok += f(black_box(input)) as usize;
+            }
+            black_box(ok);
+        };
+
+        // warm up, and work out how many passes fill a sample.
+        let start = Instant::now();
+        let mut passes = 0u32;
+        while start.elapsed() < WARM_UP {
+            pass();
+            passes += 1;
+        }
+        let per_pass = start.elapsed() / passes;
+        let iters = (SAMPLE_TIME.as_nanos() / per_pass.as_nanos().max(1)).max(1) as u32;
+
+        let mut samples: Vec<f64> = (0..SAMPLES)
+            .map(|_| {
+                let start = Instant::now();
+                for _ in 0..iters {
+                    pass();
+                }
+                let total = iters as usize * inputs.len();
+                start.elapsed().as_nanos() as f64 / total as f64
+            })
+            .collect();
+        samples.sort_by(f64::total_cmp);
+
+        let median = samples[SAMPLES / 2];
+        println!(
+            "{:<16} time: [{:>7.2} ns {:>7.2} ns {:>7.2} ns]  thrpt: {:>8.2} Melem/s",
+            id,
+            samples[0],
+            median,
+            samples[SAMPLES - 1],
+            1e3 / median,
+        );
+    }
+}
+
+// Rng is a xorshift generator, so that every run benchmarks the same inputs.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,63 @@
+// parse.rs benchmarks IPv4 parsing with each ipv4::Backend against
+// std::net::Ipv4Addr. Run it with
+//
+//   cargo bench --bench parse [-- FILTER]
+//
+// where FILTER picks the benchmarks whose "group/name" contains it. See
+// harness/mod.rs for how they are timed.
+
+mod harness;
+
+use std::net::Ipv4Addr;
+
+use netter::ipv4::{Backend, ParseOptions};
+
+use harness::{Bench, Rng};
+
+fn main() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    let short: Vec<String> = (0..1024)
+        .map(|_| {
+            let [a, b, c, d] = (rng.next() as u32).to_be_bytes();
+            format!("{}.{}.{}.{}", a % 10, b % 10, c % 10, d % 10)
+        })
+        .collect();
+    let long: Vec<String> = (0..1024)
+        .map(|_| {
+            let [a, b, c, d] = (rng.next() as u32).to_be_bytes();
+            format!("{}.{}.{}.{}", a | 0x80, b | 0x80, c | 0x80, d | 0x80)
+        })
+        .collect();
+    let mixed: Vec<String> = (0..1024)
+        .map(|_| Ipv4Addr::from(rng.next() as u32).to_string())
+        .collect();
+    let invalid: Vec<String> = mixed
+        .iter()
+        .enumerate()
+        .map(|(i, s)| match i % 4 {
+            0 => format!("{}.", s),
+            1 => s.replacen('.', "..", 1),
+            2 => format!("{}0", s),
+            _ => format!("0{}", s),
+        })
+        .collect();
+
+    let mut bench = Bench::from_args();
+    for (group, inputs) in [
+        ("short", &short),
+        ("long", &long),
+        ("mixed", &mixed),
+        ("invalid", &invalid),
+    ] {
+        for (name, backend) in [
+            ("scalar", Backend::Scalar),
+            ("swar", Backend::Swar),
+            ("simd", Backend::Simd),
+        ] {
+            let options = ParseOptions::default().backend(backend);
+            bench.run(group, name, inputs, |s| options.parse(s).is_ok());
+        }
+        bench.run(group, "std", inputs, |s| s.parse::<Ipv4Addr>().is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+// prefix_map.rs benchmarks PrefixMap lookups on a synthetic table the size
+// of the full Internet routing table: a million IPv4 prefixes and a quarter
+// of a million IPv6 ones, with lengths spread the way they are in the real
+// table. Run it with
+//
+//   cargo bench --bench prefix_map [-- FILTER]
+//
+// where FILTER picks the benchmarks whose "group/name" contains it. See
+// harness/mod.rs for how they are timed.
+
+mod harness;
+
+use std::time::Instant;
+
+use netter::{IpAddr, IpNet, PrefixMap, ipv4, ipv6};
+
+use harness::{Bench, Rng};
+
+// V4_LENS and V6_LENS are the prefix lengths of the table and how many in a
+// thousand prefixes have each.
+const V4_LENS: [(u8, u32); 12] = [
+    (8, 1),
+    (12, 2),
+    (14, 4),
+    (16, 13),
+    (17, 10),
+    (18, 17),
+    (19, 30),
+    (20, 50),
+    (21, 53),
+    (22, 120),
+    (23, 100),
+    (24, 600),
+];
+const V6_LENS: [(u8, u32); 9] = [
+    (29, 40),
+    (32, 150),
+    (36, 30),
+    (40, 60),
+    (44, 80),
+    (46, 30),
+    (47, 20),
+    (48, 580),
+    (64, 10),
+];
+
+fn main() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    // unicast IPv4 space is 1.0.0.0 to 223.255.255.255, and IPv6 routes
+    // come from 2000::/3.
+    let v4: Vec<IpNet> = (0..1_000_000)
+        .map(|_| {
+            let len = pick(&mut rng, &V4_LENS);
+            let bits = (1 + rng.next() % 223) << 24 | rng.next() & 0xff_ffff;
+            let addr = ipv4::Addr::from_bits(bits as u32);
+            IpNet::V4(ipv4::Prefix::new_masked(addr, len).unwrap())
+        })
+        .collect();
+    let v6: Vec<IpNet> = (0..250_000)
+        .map(|_| {
+            let len = pick(&mut rng, &V6_LENS);
+            let bits = 0x2000 << 112 | ((rng.next() as u128) << 64 | rng.next() as u128) >> 3;
+            let addr = ipv6::Addr::from_bits(bits);
+            IpNet::V6(ipv6::Prefix::new_masked(addr, len).unwrap())
+        })
+        .collect();
+
+    let start = Instant::now();
+    let map: PrefixMap<u32> = v4.iter().chain(&v6).copied().zip(0..).collect();
+    println!(
+        "built a table of {} prefixes in {:.0?}",
+        map.len(),
+        start.elapsed()
+    );
+
+    // lookups for addresses inside routes, which is what a router mostly
+    // sees, and for any address at all, which mostly miss the longer routes.
+    let inside = |nets: &[IpNet], rng: &mut Rng| -> Vec<IpAddr> {
+        (0..4096)
+            .map(|_| match nets[rng.next() as usize % nets.len()] {
+                IpNet::V4(p) => {
+                    let host = rng.next() as u32 & p.hostmask().to_bits();
+                    IpAddr::V4(ipv4::Addr::from_bits(p.network().to_bits() | host))
+                }
+                IpNet::V6(p) => {
+                    let host = rng.next() as u128 & p.hostmask().to_bits();
+                    IpAddr::V6(ipv6::Addr::from_bits(p.network().to_bits() | host))
+                }
+            })
+            .collect()
+    };
+    let v4_inside = inside(&v4, &mut rng);
+    let v6_inside = inside(&v6, &mut rng);
+    let v4_any: Vec<IpAddr> = (0..4096)
+        .map(|_| IpAddr::V4(ipv4::Addr::from_bits(rng.next() as u32)))
+        .collect();
+    let v6_any: Vec<IpAddr> = (0..4096)
+        .map(|_| {
+            let bits = 0x2000 << 112 | ((rng.next() as u128) << 64 | rng.next() as u128) >> 3;
+            IpAddr::V6(ipv6::Addr::from_bits(bits))
+        })
+        .collect();
+    let v4_nets: Vec<IpNet> = (0..4096).map(|i| v4[i * 241]).collect();
+    let v6_nets: Vec<IpNet> = (0..4096).map(|i| v6[i * 61]).collect();
+
+    let mut bench = Bench::from_args();
+    for (group, inside, any, nets) in [
+        ("v4", &v4_inside, &v4_any, &v4_nets),
+        ("v6", &v6_inside, &v6_any, &v6_nets),
+    ] {
+        bench.run(group, "lpm_inside", inside, |&a| {
+            map.longest_match(a).is_some()
+        });
+        bench.run(group, "lpm_any", any, |&a| map.longest_match(a).is_some());
+        bench.run(group, "matches", inside, |&a| map.matches(a).count() > 1);
+        bench.run(group, "get", nets, |&n| map.get(n).is_some());
+    }
+
+    // a route flapping: withdrawn and announced again.
+    let mut map = map;
+    let churn: Vec<(IpNet, u32)> = v4_nets.iter().chain(&v6_nets).copied().zip(0..).collect();
+    bench.run("both", "remove_insert", &churn, |&(n, v)| {
+        map.remove(n).is_some() && map.insert(n, v).is_none()
+    });
+}
+
+// pick returns a length from lens, weighted by how common it is.
+fn pick(rng: &mut Rng, lens: &[(u8, u32)]) -> u8 {
+    let total: u32 = lens.iter().map(|&(_, n)| n).sum();
+    let mut n = (rng.next() % total as u64) as u32;
+    for &(len, weight) in lens {
+        if n < weight {
+            return len;
+        }
+        n -= weight;
+    }
+    unreachable!()
+}
@@ -0,0 +1,221 @@
+// build.rs generates the special-purpose address tables from the vendored
+// copies of the IANA registries in data/. Each registry row becomes a
+// SpecialPurpose entry, and its name is mapped to a Classification variant
+// through the tables below. A name that is not in the table fails the build,
+// so that updating a registry forces a decision about any new entries. Where
+// one name covers blocks with different meanings, a "name block" key picks
+// the variant for a single block.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+// IPV4_CLASSES maps the names in the IPv4 Special-Purpose Address Registry to
+// ipv4::Classification variants.
+const IPV4_CLASSES: &[(&str, &str)] = &[
+    ("This network", "ThisNetwork"),
+    ("This host on this network", "ThisHost"),
+    ("Private-Use", "Private"),
+    ("Shared Address Space", "Shared"),
+    ("Loopback", "Loopback"),
+    ("Link Local", "LinkLocal"),
+    ("IETF Protocol Assignments", "ProtocolAssignments"),
+    ("IPv4 Service Continuity Prefix", "ServiceContinuity"),
+    ("IPv4 dummy address", "Dummy"),
+    ("Port Control Protocol Anycast", "PcpAnycast"),
+    ("Traversal Using Relays around NAT Anycast", "TurnAnycast"),
+    ("NAT64/DNS64 Discovery", "Nat64Discovery"),
+    ("Documentation (TEST-NET-1)", "Documentation"),
+    ("Documentation (TEST-NET-2)", "Documentation"),
+    ("Documentation (TEST-NET-3)", "Documentation"),
+    ("AS112-v4", "As112"),
+    ("Direct Delegation AS112 Service", "As112"),
+    ("AMT", "Amt"),
+    ("Deprecated (6to4 Relay Anycast)", "SixToFourRelayAnycast"),
+    ("6a44-relay anycast address", "SixA44Relay"),
+    ("Benchmarking", "Benchmarking"),
+    ("Reserved", "Reserved"),
+    ("Limited Broadcast", "Broadcast"),
+];
+
+// IPV6_CLASSES maps the names in the IPv6 Special-Purpose Address Registry to
+// ipv6::Classification variants.
+const IPV6_CLASSES: &[(&str, &str)] = &[
+    ("Loopback Address", "Loopback"),
+    ("Unspecified Address", "Unspecified"),
+    ("IPv4-mapped Address", "Ipv4Mapped"),
+    ("IPv4-IPv6 Translat. 64:ff9b::/96", "Nat64WellKnown"),
+    ("IPv4-IPv6 Translat. 64:ff9b:1::/48", "Nat64LocalUse"),
+    ("Discard-Only Address Block", "DiscardOnly"),
+    ("IETF Protocol Assignments", "ProtocolAssignments"),
+    ("TEREDO", "Teredo"),
+    ("Port Control Protocol Anycast", "PcpAnycast"),
+    ("Traversal Using Relays around NAT Anycast", "TurnAnycast"),
+    (
+        "DNS-SD Service Registration Protocol Anycast",
+        "DnsSdSrpAnycast",
+    ),
+    ("Benchmarking", "Benchmarking"),
+    ("AMT", "Amt"),
+    ("AS112-v6", "As112"),
+    ("Direct Delegation AS112 Service", "As112"),
+    ("Deprecated (previously ORCHID)", "Orchid"),
+    ("ORCHIDv2", "OrchidV2"),
+    (
+        "Drone Remote ID Protocol Entity Tags (DETs) Prefix",
+        "DroneRemoteId",
+    ),
+    ("Documentation", "Documentation"),
+    ("6to4", "SixToFour"),
+    ("Segment Routing (SRv6) SIDs", "Srv6Sids"),
+    ("Unique-Local", "UniqueLocal"),
+    ("Link-Local Unicast", "LinkLocal"),
+];
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    let ipv4 = generate(
+        "data/iana-ipv4-special-registry.csv",
+        IPV4_CLASSES,
+        |block| {
+            let (addr, len) = block
+                .split_once('/')
+                .expect("address block without a length");
+            let octets = addr.parse::<Ipv4Addr>().expect("invalid address").octets();
+            format!("{:?}, {}", octets, len)
+        },
+    );
+    fs::write(Path::new(&out_dir).join("ipv4_special.rs"), ipv4).unwrap();
+
+    let ipv6 = generate(
+        "data/iana-ipv6-special-registry.csv",
+        IPV6_CLASSES,
+        |block| {
+            let (addr, len) = block
+                .split_once('/')
+                .expect("address block without a length");
+            let octets = addr.parse::<Ipv6Addr>().expect("invalid address").octets();
+            format!("{:?}, {}", octets, len)
+        },
+    );
+    fs::write(Path::new(&out_dir).join("ipv6_special.rs"), ipv6).unwrap();
+}
+
+// generate turns a registry CSV into the source of a REGISTRY table. prefix
+// renders an address block as the arguments SpecialPurpose::entry expects
+// for it.
+fn generate(path: &str, classes: &[(&str, &str)], prefix: impl Fn(&str) -> String) -> String {
+    println!("cargo::rerun-if-changed={}", path);
+    let csv = fs::read_to_string(path).unwrap_or_else(|e| panic!("reading {}: {}", path, e));
+
+    let mut out = String::new();
+    writeln!(out, "// @generated by build.rs from {}.", path).unwrap();
+    writeln!(out, "static REGISTRY: &[SpecialPurpose] = &[").unwrap();
+
+    for (n, line) in csv.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv(line);
+        if fields.len() != 10 {
+            panic!(
+                "{}:{}: expected 10 fields, found {}",
+                path,
+                n + 1,
+                fields.len()
+            );
+        }
+
+        let name = fields[1].trim_matches('"');
+        let terminated = match fields[4].as_str() {
+            "N/A" | "" => "None".to_string(),
+            date => format!("Some({:?})", date),
+        };
+
+        // a row can list more than one block, and blocks and flags can carry
+        // footnote markers like "[1]", which are dropped.
+        for block in fields[0].split(',') {
+            let block = strip_footnote(block);
+            let class = [format!("{} {}", name, block), name.to_string()]
+                .iter()
+                .find_map(|key| {
+                    classes
+                        .iter()
+                        .find(|(registry_name, _)| registry_name == key)
+                })
+                .map(|(_, class)| class)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "{}:{}: no Classification for {:?}, add it to build.rs",
+                        path,
+                        n + 1,
+                        name
+                    )
+                });
+            writeln!(
+                out,
+                "    SpecialPurpose::entry({}, {:?}, {:?}, {:?}, {}, {}, {}, {}, {}, {}, Classification::{}),",
+                prefix(block),
+                name,
+                fields[2],
+                fields[3],
+                terminated,
+                flag(&fields[5]),
+                flag(&fields[6]),
+                flag(&fields[7]),
+                flag(&fields[8]),
+                flag(&fields[9]),
+                class,
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(out, "];").unwrap();
+    out
+}
+
+// split_csv splits a CSV line into fields, handling quoted fields and
+// doubled quotes inside them.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// strip_footnote removes a trailing footnote marker such as " [1]".
+fn strip_footnote(s: &str) -> &str {
+    let s = s.trim();
+    match s.rfind(" [") {
+        Some(i) if s.ends_with(']') => &s[..i],
+        _ => s,
+    }
+}
+
+// flag renders a True/False/N/A registry column as an Option<bool>.
+fn flag(s: &str) -> &'static str {
+    match strip_footnote(s) {
+        "True" => "Some(true)",
+        "False" => "Some(false)",
+        "N/A" | "" => "None",
+        other => panic!("unexpected flag value {:?}", other),
+    }
+}
@@ -0,0 +1,14 @@
+// ascii.rs has the byte string helpers the const parsers need in place of
+// the slice and str methods that are not const.
+
+// find returns the offset of the first b in s.
+pub(crate) const fn find(s: &[u8], b: u8) -> Option<usize> {
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
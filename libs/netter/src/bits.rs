@@ -0,0 +1,104 @@
+use crate::{IpNet, ipv4, ipv6};
+
+// Bits is the integer form of an address, which the collection types work
+// on so that one implementation serves both families: u32 for IPv4 and u128
+// for IPv6. Bits are counted from the most significant one, the way prefix
+// lengths count them.
+pub(crate) trait Bits: Copy + Ord + Default {
+    const BITS: u8;
+    const MIN: Self;
+    const MAX: Self;
+
+    // succ and pred return the next and previous address, if there is one.
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    // masked clears all but the first len bits.
+    fn masked(self, len: u8) -> Self;
+    // last sets all but the first len bits, giving the last address of the
+    // prefix of length len that self starts.
+    fn last(self, len: u8) -> Self;
+    // bit returns bit i.
+    fn bit(self, i: u8) -> usize;
+    // flip inverts bit i.
+    fn flip(self, i: u8) -> Self;
+    // common returns how many leading bits self and other have in common.
+    fn common(self, other: Self) -> u8;
+    // net makes the prefix of length len that starts at self.
+    fn net(self, len: u8) -> IpNet;
+}
+
+impl Bits for u32 {
+    const BITS: u8 = 32;
+    const MIN: u32 = 0;
+    const MAX: u32 = u32::MAX;
+
+    fn succ(self) -> Option<u32> {
+        self.checked_add(1)
+    }
+
+    fn pred(self) -> Option<u32> {
+        self.checked_sub(1)
+    }
+
+    fn masked(self, len: u8) -> u32 {
+        self & u32::MAX.checked_shl(32 - len as u32).unwrap_or(0)
+    }
+
+    fn last(self, len: u8) -> u32 {
+        self | u32::MAX.checked_shr(len as u32).unwrap_or(0)
+    }
+
+    fn bit(self, i: u8) -> usize {
+        (self >> (31 - i)) as usize & 1
+    }
+
+    fn flip(self, i: u8) -> u32 {
+        self ^ 1 << (31 - i)
+    }
+
+    fn common(self, other: u32) -> u8 {
+        (self ^ other).leading_zeros() as u8
+    }
+
+    fn net(self, len: u8) -> IpNet {
+        IpNet::V4(ipv4::Prefix::new(ipv4::Addr::from_bits(self), len).unwrap())
+    }
+}
+
+impl Bits for u128 {
+    const BITS: u8 = 128;
+    const MIN: u128 = 0;
+    const MAX: u128 = u128::MAX;
+
+    fn succ(self) -> Option<u128> {
+        self.checked_add(1)
+    }
+
+    fn pred(self) -> Option<u128> {
+        self.checked_sub(1)
+    }
+
+    fn masked(self, len: u8) -> u128 {
+        self & u128::MAX.checked_shl(128 - len as u32).unwrap_or(0)
+    }
+
+    fn last(self, len: u8) -> u128 {
+        self | u128::MAX.checked_shr(len as u32).unwrap_or(0)
+    }
+
+    fn bit(self, i: u8) -> usize {
+        (self >> (127 - i)) as usize & 1
+    }
+
+    fn flip(self, i: u8) -> u128 {
+        self ^ 1 << (127 - i)
+    }
+
+    fn common(self, other: u128) -> u8 {
+        (self ^ other).leading_zeros() as u8
+    }
+
+    fn net(self, len: u8) -> IpNet {
+        IpNet::V6(ipv6::Prefix::new(ipv6::Addr::from_bits(self), len).unwrap())
+    }
+}
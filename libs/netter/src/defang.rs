@@ -0,0 +1,308 @@
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::IpAddr;
+#[cfg(feature = "alloc")]
+use crate::{InvalidAddrErr, ParseOptions, ipv4, ipv6};
+
+// Defang is a fmt::Write that defangs text on its way to a Formatter: every
+// '.' is written as "[.]" and every ':' as "[:]", so that an indicator pasted
+// into a report or a chat is not turned into a link. It is how the Defanged
+// types of both families are written.
+pub(crate) struct Defang<'a, 'b>(pub(crate) &'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for Defang<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        while let Some(i) = rest.find(['.', ':']) {
+            self.0.write_str(&rest[..i])?;
+            self.0.write_str(if rest.as_bytes()[i] == b'.' {
+                "[.]"
+            } else {
+                "[:]"
+            })?;
+            rest = &rest[i + 1..];
+        }
+        self.0.write_str(rest)
+    }
+}
+
+// Defanged writes an address of either family in defanged form. See
+// IpAddr::defanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Defanged(IpAddr);
+
+impl fmt::Display for Defanged {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            IpAddr::V4(v4) => write!(f, "{}", v4.defanged()),
+            IpAddr::V6(v6) => write!(f, "{}", v6.defanged()),
+        }
+    }
+}
+
+impl IpAddr {
+    // defanged returns the address formatted the way threat-intel reports
+    // write indicators, with every '.' as "[.]" and every ':' as "[:]":
+    // "192[.]0[.]2[.]1" or "2001[:]db8[:][:]1". The result can be read back
+    // with ParseOptions::parse_defanged.
+    pub fn defanged(&self) -> Defanged {
+        Defanged(*self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ParseOptions {
+    // parse_defanged will parse a defanged address of either family into an
+    // IpAddr using these options. The family is picked from the host once
+    // the text is refanged, so "hxxp://192[.]0[.]2[.]1" is IPv4 despite the
+    // colon in the scheme, and a host with a '.' before its first ':' is
+    // IPv4 too. See ipv4::parse_defanged for the forms accepted.
+    pub fn parse_defanged(&self, s: &str) -> Result<IpAddr, InvalidAddrErr> {
+        let text = Refanged::new(s.as_bytes());
+        let url = text.url();
+        let host = &text.text()[url.host.clone()];
+        let v6 = match host.iter().position(|&b| b == b':') {
+            Some(i) => !host[..i].contains(&b'.'),
+            None => false,
+        };
+        if url.bracket.is_some() || v6 {
+            Ok(IpAddr::V6(ipv6::defang::scan_refanged(
+                &text,
+                &url,
+                &self.get_v6(),
+            )?))
+        } else {
+            Ok(IpAddr::V4(ipv4::defang::scan_refanged(
+                &text,
+                &url,
+                &self.get_v4(),
+            )?))
+        }
+    }
+}
+
+// SEPARATORS are the defanged spellings of the separators in an address or
+// URL, with what each stands for. Letters match in either case, so "[DOT]"
+// is a dot too.
+#[cfg(feature = "alloc")]
+const SEPARATORS: &[(&[u8], &[u8])] = &[
+    (b"[.]", b"."),
+    (b"(.)", b"."),
+    (b"{.}", b"."),
+    (b"[dot]", b"."),
+    (b"(dot)", b"."),
+    (b"{dot}", b"."),
+    (b"[:]", b":"),
+    (b"(:)", b":"),
+    (b"{:}", b":"),
+    (b"[://]", b"://"),
+];
+
+// Refanged is defanged text with its separators put back, along with where
+// each byte came from, so that errors found in the refanged text can point
+// at the input the caller has.
+#[cfg(feature = "alloc")]
+pub(crate) struct Refanged {
+    text: Vec<u8>,
+    // from holds the input offset of each byte of text, and the length of
+    // the input after the last one.
+    from: Vec<usize>,
+}
+
+// Url is where the address is in refanged text. Without a scheme the host
+// is the whole text, less the port of a dotted-quad host; after a scheme it
+// runs up to the port or path, and may be in square brackets.
+#[cfg(feature = "alloc")]
+pub(crate) struct Url {
+    pub(crate) host: Range<usize>,
+    // scheme is true if the host followed a scheme.
+    pub(crate) scheme: bool,
+    // bracket is the offset of the '[' before the host, if there was one,
+    // and closed is true if the matching ']' was found.
+    pub(crate) bracket: Option<usize>,
+    pub(crate) closed: bool,
+    // junk is the offset of the first byte after the host that is neither
+    // a port nor the start of a path, query or fragment.
+    pub(crate) junk: Option<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl Refanged {
+    // new refangs input, replacing each of the SEPARATORS with the
+    // separator it stands for.
+    pub(crate) fn new(input: &[u8]) -> Refanged {
+        let mut text = Vec::with_capacity(input.len());
+        let mut from = Vec::with_capacity(input.len() + 1);
+        let mut i = 0;
+        'input: while i < input.len() {
+            if matches!(input[i], b'[' | b'(' | b'{') {
+                for &(defanged, plain) in SEPARATORS {
+                    if input.len() - i >= defanged.len()
+                        && input[i..i + defanged.len()].eq_ignore_ascii_case(defanged)
+                    {
+                        text.extend_from_slice(plain);
+                        from.extend((0..plain.len()).map(|_| i));
+                        i += defanged.len();
+                        continue 'input;
+                    }
+                }
+            }
+            text.push(input[i]);
+            from.push(i);
+            i += 1;
+        }
+        from.push(input.len());
+        Refanged { text, from }
+    }
+
+    // text returns the refanged text.
+    pub(crate) fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    // offset returns the input offset of byte i of the text. The end of the
+    // text maps to the end of the input.
+    pub(crate) fn offset(&self, i: usize) -> usize {
+        self.from[i.min(self.text.len())]
+    }
+
+    // url finds the host in the text, skipping a scheme like "hxxp://" and
+    // any port and path after the host.
+    pub(crate) fn url(&self) -> Url {
+        let text = &self.text[..];
+        let Some(start) = scheme_len(text) else {
+            // a dotted-quad host may carry a port without a scheme, as in
+            // "192[.]0[.]2[.]1:443". An IPv6 host is left whole, since its
+            // first ':' comes before any '.'.
+            let end = match text.iter().position(|&b| b == b':') {
+                Some(i)
+                    if text[..i].contains(&b'.')
+                        && i + 1 < text.len()
+                        && text[i + 1..].iter().all(u8::is_ascii_digit) =>
+                {
+                    i
+                }
+                _ => text.len(),
+            };
+            return Url {
+                host: 0..end,
+                scheme: false,
+                bracket: None,
+                closed: false,
+                junk: None,
+            };
+        };
+
+        let (host, bracket, closed, mut end) = if text.get(start) == Some(&b'[') {
+            match text[start..].iter().position(|&b| b == b']') {
+                Some(i) => (start + 1..start + i, Some(start), true, start + i + 1),
+                None => (start + 1..text.len(), Some(start), false, text.len()),
+            }
+        } else {
+            let end = text[start..]
+                .iter()
+                .position(|b| matches!(b, b':' | b'/' | b'?' | b'#'))
+                .map_or(text.len(), |i| start + i);
+            (start..end, None, false, end)
+        };
+
+        // a port is a ':' and decimal digits.
+        if text.get(end) == Some(&b':') {
+            end += 1;
+            let digits = text[end..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            if digits == 0 {
+                return Url {
+                    host,
+                    scheme: true,
+                    bracket,
+                    closed,
+                    junk: Some(end),
+                };
+            }
+            end += digits;
+        }
+        let junk = (end < text.len() && !matches!(text[end], b'/' | b'?' | b'#')).then_some(end);
+        Url {
+            host,
+            scheme: true,
+            bracket,
+            closed,
+            junk,
+        }
+    }
+}
+
+// scheme_len returns the length of the RFC 3986 scheme and "://" at the start
+// of text, if there is one. Any scheme is allowed, since defanged ones like
+// "hxxp" and "fxp" are made up.
+#[cfg(feature = "alloc")]
+fn scheme_len(text: &[u8]) -> Option<usize> {
+    if !text.first()?.is_ascii_alphabetic() {
+        return None;
+    }
+    let len = text
+        .iter()
+        .position(|b| !(b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.')))?;
+    text[len..].starts_with(b"://").then_some(len + 3)
+}
+
+#[cfg(test)]
+mod defang_tests {
+    use crate::IpAddr;
+
+    #[test]
+    fn test_defanged() {
+        let cases = Vec::from([
+            ("192.0.2.1", "192[.]0[.]2[.]1"),
+            ("2001:db8::1", "2001[:]db8[:][:]1"),
+            ("::ffff:192.0.2.1", "[:][:]ffff[:]192[.]0[.]2[.]1"),
+        ]);
+
+        for (s, want) in cases {
+            let addr: IpAddr = s.parse().unwrap();
+            assert_eq!(addr.defanged().to_string(), want, "{}", s);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_defanged() {
+        use crate::ParseOptions;
+
+        let cases = Vec::from([
+            ("192[.]168[.]1[.]1", Ok("192.168.1.1")),
+            ("10(.)0(.)0(.)1", Ok("10.0.0.1")),
+            ("hxxp://1.2.3.4", Ok("1.2.3.4")),
+            ("hxxps[://]1[.]2[.]3[.]4:8443/gate.php", Ok("1.2.3.4")),
+            ("2001[:]db8[:][:]1", Ok("2001:db8::1")),
+            ("hxxp://[2001:db8::1]:8080/", Ok("2001:db8::1")),
+            ("fe80[:][:]1%eth0", Ok("fe80::1%eth0")),
+            ("hxxp://1.2.3.4x", Err(14)),
+            ("45[.]9[.]148[.]108:443", Ok("45.9.148.108")),
+            ("1[.]2[.]3[.]4:80", Ok("1.2.3.4")),
+            ("[:][:]ffff[:]1[.]2[.]3[.]4", Ok("::ffff:1.2.3.4")),
+            ("1.2.3.4:http", Err(7)),
+            ("hxxp://[2001:db8::1", Err(19)),
+            ("2001:db8[://]", Err(8)),
+            ("1[://]ff[://]1", Err(1)),
+            ("2001[:]db8[:][:]1[:]", Err(20)),
+        ]);
+
+        let options = ParseOptions::permissive();
+        for (s, want) in cases {
+            let got = options
+                .parse_defanged(s)
+                .map(|addr| addr.to_string())
+                .map_err(|err| err.offset());
+            assert_eq!(got, want.map(String::from), "{}", s);
+        }
+    }
+}
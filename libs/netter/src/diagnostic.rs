@@ -0,0 +1,30 @@
+use core::fmt;
+
+// write_snippet writes the shared part of a rustc-style diagnostic for an
+// error at offset in input: the header, the input and the carets under
+// width characters starting at offset. The caller writes the label that
+// goes after the carets.
+pub(crate) fn write_snippet(
+    f: &mut fmt::Formatter,
+    what: &str,
+    reason: &str,
+    input: &str,
+    offset: usize,
+    width: usize,
+) -> fmt::Result {
+    let offset = offset.min(input.len());
+
+    // the caret is placed by character, not by byte, so that it lines up
+    // with the input even if the offending character is not ASCII. An
+    // offset inside a character puts it under that character.
+    let column = input
+        .char_indices()
+        .take_while(|&(i, c)| i + c.len_utf8() <= offset)
+        .count();
+
+    writeln!(f, "error: invalid {}: {}", what, reason)?;
+    writeln!(f, " --> input:1:{}", column + 1)?;
+    writeln!(f, "  |")?;
+    writeln!(f, "1 | {}", input)?;
+    write!(f, "  | {:column$}{:^<width$} ", "", "")
+}
@@ -0,0 +1,458 @@
+use core::ops::Range;
+
+use crate::{IpAddr, IpNet, ParseOptions};
+
+// extract.rs finds addresses and prefixes in free text, such as log lines
+// and mail bodies. Every candidate is read by the same scanners as the
+// parse functions, through parse_ascii_partial, so anything extracted is
+// exactly what parsing its text with the same options gives, and anything
+// the parser rejects is skipped rather than half matched.
+//
+// What the scanners cannot know is where the text around an address ends,
+// and that is what the word boundary rules here are for. A match has to
+//
+//   - start at the beginning of the input or after a byte that is not a
+//     letter, digit, '_' or '.', so "v1.2.3.4" and the "2.3.4.5" inside
+//     "1.2.3.4.5" are skipped; an IPv6 address may not follow a ':' either.
+//   - end at the end of the input or before a byte that is not a letter,
+//     digit or '_', and not before a '.' that is followed by one, so
+//     "1.2.3.4.5" and "10.0.0.1.example.com" are skipped but "at 10.0.0.1."
+//     is found. An IPv6 address may not be followed by a ':' and a letter or
+//     digit either, while an IPv4 address may, as in "10.0.0.1:8080".
+//
+// An address followed by '/' and a length is a prefix if the prefix parser
+// accepts it. If it does not, say because of host bits with options that
+// reject them, the address alone is the match, as it is in a URL like
+// "http://10.0.0.1/index.html". The length has to end a word like an
+// address does, or there is no match at all, so "10.0.0.0/8.5" is skipped
+// rather than read as 10.0.0.0. A bare "::" is never a match: in text it is
+// much more often punctuation than the unspecified address.
+
+// MAX_MATCH bounds the length of a match, which only an IPv6 zone can make
+// long. LOOKAHEAD is how many bytes after an address the rules above need to
+// see: a '/', a length of up to three digits, and a '.' and the byte after
+// it. Together they are how much of a stream an Extractor has to hold back.
+const MAX_MATCH: usize = 255;
+#[cfg(feature = "alloc")]
+const LOOKAHEAD: usize = 6;
+
+// Found is what was extracted: an address, or a prefix in CIDR notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Found {
+    Addr(IpAddr),
+    Net(IpNet),
+}
+
+impl ParseOptions {
+    // extract returns an iterator over the addresses and prefixes in input,
+    // parsed with these options. See the extract function.
+    pub fn extract<'a>(&self, input: &'a [u8]) -> Extract<'a> {
+        Extract {
+            input,
+            pos: 0,
+            options: *self,
+        }
+    }
+}
+
+// extract returns an iterator over the addresses and prefixes in input, and
+// the byte ranges they were found at, in order:
+//
+//   let line = b"Jan 12 sshd: Failed password from 203.0.113.7 port 51234";
+//   let (span, found) = extract(line).next().unwrap();
+//   // span is 34..45, found is Found::Addr(203.0.113.7)
+//
+// Input does not have to be UTF-8. It uses the default ParseOptions; see
+// ParseOptions::extract to pick a different profile, e.g. to accept zones.
+pub fn extract(input: &[u8]) -> Extract<'_> {
+    ParseOptions::default().extract(input)
+}
+
+// Extract iterates over the addresses and prefixes in a byte slice. See
+// extract.
+#[derive(Debug, Clone)]
+pub struct Extract<'a> {
+    input: &'a [u8],
+    pos: usize,
+    options: ParseOptions,
+}
+
+impl Iterator for Extract<'_> {
+    type Item = (Range<usize>, Found);
+
+    fn next(&mut self) -> Option<(Range<usize>, Found)> {
+        while self.pos < self.input.len() {
+            let start = self.pos;
+            if let Some((end, found)) = self.match_at(start) {
+                self.pos = end;
+                return Some((start..end, found));
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+impl Extract<'_> {
+    // match_at returns the end of the match that starts at start, and what
+    // it is, if there is one.
+    fn match_at(&self, start: usize) -> Option<(usize, Found)> {
+        let input = self.input;
+        let first = input[start];
+        if !first.is_ascii_hexdigit() && first != b':' {
+            return None;
+        }
+        let before = start.checked_sub(1).map(|i| input[i]);
+        if before.is_some_and(|b| is_word(b) || b == b'.') {
+            return None;
+        }
+
+        let window = &input[start..input.len().min(start + MAX_MATCH)];
+        let (addr, len) = if looks_like_ipv6(window) {
+            if before == Some(b':') {
+                return None;
+            }
+            let (addr, len) = self.options.get_v6().parse_ascii_partial(window).ok()?;
+            if !window[..len].iter().any(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            (IpAddr::V6(addr), len)
+        } else if first.is_ascii_digit() {
+            let (addr, len) = self.options.get_v4().parse_ascii_partial(window).ok()?;
+            (IpAddr::V4(addr), len)
+        } else {
+            return None;
+        };
+        if len == MAX_MATCH {
+            return None;
+        }
+
+        let end = start + len;
+        if let Some(net_end) = self.net_end(end) {
+            // a length that runs on into a word, as in "10.0.0.0/8.5", makes
+            // the whole thing something else, such as a version number.
+            if !ends_word(input, net_end, addr.is_ipv6()) {
+                return None;
+            }
+            // the whole match is ASCII, so it is also a str.
+            let text = core::str::from_utf8(&input[start..net_end]).ok()?;
+            if let Ok(net) = self.options.parse_net(text) {
+                return Some((net_end, Found::Net(net)));
+            }
+        }
+        ends_word(input, end, addr.is_ipv6()).then_some((end, Found::Addr(addr)))
+    }
+
+    // net_end returns where the prefix ends if the address that ends at end
+    // is followed by a '/' and a length of one to three digits.
+    fn net_end(&self, end: usize) -> Option<usize> {
+        if self.input.get(end) != Some(&b'/') {
+            return None;
+        }
+        let digits = self.input[end + 1..]
+            .iter()
+            .take(4)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        let net_end = end + 1 + digits;
+        (1..=3).contains(&digits).then_some(net_end)
+    }
+}
+
+// is_word reports whether b can be part of a word around an address.
+fn is_word(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+// ends_word reports whether an address or prefix can end at end.
+fn ends_word(input: &[u8], end: usize, ipv6: bool) -> bool {
+    let after = input.get(end + 1).copied();
+    match input.get(end) {
+        None => true,
+        Some(&b) if is_word(b) => false,
+        Some(b'.') => !after.is_some_and(is_word),
+        Some(b':') if ipv6 => !after.is_some_and(is_word),
+        Some(_) => true,
+    }
+}
+
+// looks_like_ipv6 reports whether the text at the start of input is an
+// IPv6 address if it is an address at all: its first group, of up to four
+// hex digits, is followed by a ':'.
+fn looks_like_ipv6(input: &[u8]) -> bool {
+    input
+        .iter()
+        .take(5)
+        .find(|b| !b.is_ascii_hexdigit())
+        .is_some_and(|&b| b == b':')
+}
+
+// Extractor finds the addresses and prefixes in a stream that arrives in
+// chunks, such as a socket or a file read a buffer at a time, with the same
+// rules as extract. A match can span two chunks, so the last few hundred
+// bytes of each chunk are held back until the next one shows how they end.
+// Spans are byte offsets in the whole stream.
+//
+//   let mut extractor = Extractor::new();
+//   let mut found = Vec::new();
+//   while let Some(chunk) = next_chunk() {
+//       extractor.push(&chunk, &mut found);
+//       for (span, value) in found.drain(..) { ... }
+//   }
+//   extractor.finish(&mut found);
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default)]
+pub struct Extractor {
+    options: ParseOptions,
+    // buf holds the bytes that are not decided yet, after one byte of the
+    // ones that are for context. offset is where buf starts in the stream,
+    // and next is the first position in buf a match can start at.
+    buf: alloc::vec::Vec<u8>,
+    offset: u64,
+    next: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Extractor {
+    // new returns an Extractor that uses the default ParseOptions.
+    pub fn new() -> Extractor {
+        Extractor::default()
+    }
+
+    // options sets the options that addresses and prefixes are parsed with.
+    pub fn options(mut self, options: ParseOptions) -> Extractor {
+        self.options = options;
+        self
+    }
+
+    // push adds the next chunk of the stream, and appends what can be
+    // extracted so far to found.
+    pub fn push(&mut self, chunk: &[u8], found: &mut alloc::vec::Vec<(Range<u64>, Found)>) {
+        self.buf.extend_from_slice(chunk);
+        self.drain(false, found);
+    }
+
+    // finish ends the stream, and appends what is left to found. The
+    // Extractor can then be used for a new stream.
+    pub fn finish(&mut self, found: &mut alloc::vec::Vec<(Range<u64>, Found)>) {
+        self.drain(true, found);
+        *self = Extractor::new().options(self.options);
+    }
+
+    // drain extracts the matches that start before the part of buf that
+    // more input could still change, which is all of it at the end.
+    fn drain(&mut self, last: bool, found: &mut alloc::vec::Vec<(Range<u64>, Found)>) {
+        let decided = match last {
+            true => self.buf.len(),
+            false => self.buf.len().saturating_sub(MAX_MATCH + LOOKAHEAD),
+        };
+        let mut it = Extract {
+            input: &self.buf,
+            pos: self.next,
+            options: self.options,
+        };
+        let mut next = decided.max(self.next);
+        while it.pos < decided {
+            let Some((span, value)) = it.next() else {
+                break;
+            };
+            if span.start >= decided {
+                break;
+            }
+            next = next.max(span.end);
+            let start = self.offset + span.start as u64;
+            found.push((start..start + span.len() as u64, value));
+        }
+
+        // keep the byte before next, so that the next match still sees
+        // what comes before it.
+        let keep = next.saturating_sub(1);
+        self.buf.drain(..keep);
+        self.offset += keep as u64;
+        self.next = next - keep;
+    }
+}
+
+#[cfg(test)]
+mod extract_tests {
+    use super::{Found, extract};
+    use crate::rng::Rng;
+    use crate::{IpAddr, ParseOptions};
+
+    fn found(input: &str) -> Vec<(&str, String)> {
+        extract(input.as_bytes())
+            .map(|(span, found)| {
+                let value = match found {
+                    Found::Addr(addr) => addr.to_string(),
+                    Found::Net(net) => net.to_string(),
+                };
+                (&input[span], value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_extract() {
+        let cases = Vec::from([
+            (
+                "from 10.0.0.1 to 10.0.0.2",
+                Vec::from(["10.0.0.1", "10.0.0.2"]),
+            ),
+            ("10.0.0.1", Vec::from(["10.0.0.1"])),
+            ("connected to 10.0.0.1.", Vec::from(["10.0.0.1"])),
+            (
+                "(10.0.0.1), [2001:db8::1]",
+                Vec::from(["10.0.0.1", "2001:db8::1"]),
+            ),
+            ("peer=10.0.0.1:8080", Vec::from(["10.0.0.1"])),
+            (
+                "src:10.0.0.1 dst:10.0.0.2",
+                Vec::from(["10.0.0.1", "10.0.0.2"]),
+            ),
+            ("http://10.0.0.1/index.html", Vec::from(["10.0.0.1"])),
+            ("10.0.0.5-10.0.0.9", Vec::from(["10.0.0.5", "10.0.0.9"])),
+            (
+                "route 10.0.0.0/8 via fe80::1",
+                Vec::from(["10.0.0.0/8", "fe80::1"]),
+            ),
+            ("net 2001:db8::/32.", Vec::from(["2001:db8::/32"])),
+            (
+                "mapped ::ffff:192.0.2.1 ok",
+                Vec::from(["::ffff:192.0.2.1"]),
+            ),
+            ("loopback ::1", Vec::from(["::1"])),
+            // host bits, and a length out of range, leave the address.
+            ("addr 10.0.0.1/24", Vec::from(["10.0.0.1"])),
+            ("addr 10.0.0.0/33", Vec::from(["10.0.0.0"])),
+            ("addr 10.0.0.0/8080", Vec::from(["10.0.0.0"])),
+            // version numbers, hostnames and timestamps.
+            ("version 1.2.3.4.5", Vec::new()),
+            ("10.0.0.0/8.5", Vec::new()),
+            ("2001:db8::/32a", Vec::new()),
+            ("v1.2.3.4", Vec::new()),
+            ("10.0.0.1.example.com", Vec::new()),
+            ("host-10.0.0.1a", Vec::new()),
+            ("2024-01-15T10:20:30.123Z", Vec::new()),
+            ("at 12:30:45 today", Vec::new()),
+            ("mac 00:1a:2b:3c:4d:5e", Vec::new()),
+            ("std::vec::Vec and a :: b", Vec::new()),
+            ("1.2.3", Vec::new()),
+            ("010.0.0.1 256.0.0.1", Vec::new()),
+            ("1:2:3:4:5:6:7:8:9", Vec::new()),
+            ("fe80::1%eth0", Vec::new()),
+            ("", Vec::new()),
+        ]);
+        for (input, want) in cases {
+            let got: Vec<&str> = found(input).into_iter().map(|(text, _)| text).collect();
+            assert_eq!(got, want, "extract({:?})", input);
+        }
+
+        let line = b"Jan 12 sshd: Failed password from 203.0.113.7 port 51234";
+        let (span, value) = extract(line).next().unwrap();
+        assert_eq!(span, 34..45);
+        assert_eq!(value, Found::Addr("203.0.113.7".parse().unwrap()));
+
+        // not UTF-8 around the address.
+        let got: Vec<_> = extract(b"\xff\xfe10.0.0.1\xff").collect();
+        assert_eq!(got, [(2..10, Found::Addr("10.0.0.1".parse().unwrap()))]);
+
+        // other options: zones, and prefixes with host bits masked.
+        let opts = ParseOptions::permissive();
+        let got: Vec<_> = opts.extract(b"via fe80::1%eth0 net 10.0.0.1/24").collect();
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].0, 4..16);
+        assert_eq!(got[1], (21..32, Found::Net("10.0.0.0/24".parse().unwrap())));
+    }
+
+    #[test]
+    fn test_agrees_with_parser() {
+        // whatever is extracted from random text parses to the same value,
+        // and every address written into the text with separators around it
+        // is found.
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        let pieces = [
+            "10.0.0.1",
+            "1.2.3.4.5",
+            "::1",
+            "2001:db8::",
+            ":",
+            ".",
+            "/",
+            "/24",
+            "/8",
+            "a",
+            "0",
+            "255",
+            "-",
+            " ",
+            "ffff",
+            "1:2",
+            "::ffff:1.2.3.4",
+            "%",
+            "_",
+            "192.168.001.1",
+        ];
+        for _ in 0..2000 {
+            let mut text = String::new();
+            let mut planted = Vec::new();
+            for _ in 0..8 {
+                if rng.next().is_multiple_of(4) {
+                    let addr = IpAddr::V4((rng.next() as u32).into());
+                    text.push(' ');
+                    planted.push(addr);
+                    text += &addr.to_string();
+                    text.push(' ');
+                } else {
+                    text += pieces[rng.next() as usize % pieces.len()];
+                }
+            }
+
+            let options = ParseOptions::default();
+            let mut got = Vec::new();
+            for (span, value) in extract(text.as_bytes()) {
+                let s = &text[span];
+                match value {
+                    Found::Addr(addr) => assert_eq!(options.parse(s), Ok(addr), "{:?}", text),
+                    Found::Net(net) => assert_eq!(options.parse_net(s), Ok(net), "{:?}", text),
+                }
+                if let Found::Addr(addr) = value {
+                    got.push(addr);
+                }
+            }
+            for addr in planted {
+                assert!(got.contains(&addr), "{} not found in {:?}", addr, text);
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_extractor() {
+        use super::Extractor;
+        use crate::IpNet;
+
+        let mut text = String::new();
+        for i in 1..=200u32 {
+            let v4 = IpAddr::V4(i.into());
+            let v6 = IpNet::from(IpAddr::V6((i as u128).into()));
+            text += &format!("{} [{}] v1.2.3.{} 10.{}.0.0/16 ", v4, v6, i, i % 256);
+            text += &"x".repeat(i as usize % 7);
+            text.push('\n');
+        }
+        let want: Vec<_> = extract(text.as_bytes())
+            .map(|(span, value)| (span.start as u64..span.end as u64, value))
+            .collect();
+        assert_eq!(want.len(), 600);
+
+        // every chunk size, including ones that split every match.
+        for size in [1, 2, 3, 7, 64, 255, 261, 1000, text.len()] {
+            let mut extractor = Extractor::new();
+            let mut got = Vec::new();
+            for chunk in text.as_bytes().chunks(size) {
+                extractor.push(chunk, &mut got);
+            }
+            extractor.finish(&mut got);
+            assert_eq!(got, want, "chunks of {}", size);
+        }
+    }
+}
@@ -0,0 +1,802 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::ascii::find;
+use crate::{ipv4, ipv6};
+
+// InvalidAddrErr describes why a string is not a valid IP address of either
+// family. It wraps the error of the family the string was parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidAddrErr {
+    V4(ipv4::InvalidAddrErr),
+    V6(ipv6::InvalidAddrErr),
+}
+
+impl InvalidAddrErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match self {
+            InvalidAddrErr::V4(err) => err.offset(),
+            InvalidAddrErr::V6(err) => err.offset(),
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidAddrErr::V4(err) => err.reason(),
+            InvalidAddrErr::V6(err) => err.reason(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidAddrErr::V4(err) => write!(f, "{}", err),
+            InvalidAddrErr::V6(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for InvalidAddrErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidAddrErr::V4(err) => Some(err),
+            InvalidAddrErr::V6(err) => Some(err),
+        }
+    }
+}
+
+impl From<ipv4::InvalidAddrErr> for InvalidAddrErr {
+    fn from(err: ipv4::InvalidAddrErr) -> InvalidAddrErr {
+        InvalidAddrErr::V4(err)
+    }
+}
+
+impl From<ipv6::InvalidAddrErr> for InvalidAddrErr {
+    fn from(err: ipv6::InvalidAddrErr) -> InvalidAddrErr {
+        InvalidAddrErr::V6(err)
+    }
+}
+
+// InvalidPrefixErr describes why a string is not a valid prefix of either
+// family. It wraps the error of the family the string was parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidPrefixErr {
+    V4(ipv4::InvalidPrefixErr),
+    V6(ipv6::InvalidPrefixErr),
+}
+
+impl InvalidPrefixErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match self {
+            InvalidPrefixErr::V4(err) => err.offset(),
+            InvalidPrefixErr::V6(err) => err.offset(),
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidPrefixErr::V4(err) => err.reason(),
+            InvalidPrefixErr::V6(err) => err.reason(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidPrefixErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidPrefixErr::V4(err) => write!(f, "{}", err),
+            InvalidPrefixErr::V6(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for InvalidPrefixErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidPrefixErr::V4(err) => Some(err),
+            InvalidPrefixErr::V6(err) => Some(err),
+        }
+    }
+}
+
+impl From<ipv4::InvalidPrefixErr> for InvalidPrefixErr {
+    fn from(err: ipv4::InvalidPrefixErr) -> InvalidPrefixErr {
+        InvalidPrefixErr::V4(err)
+    }
+}
+
+impl From<ipv6::InvalidPrefixErr> for InvalidPrefixErr {
+    fn from(err: ipv6::InvalidPrefixErr) -> InvalidPrefixErr {
+        InvalidPrefixErr::V6(err)
+    }
+}
+
+// ParseOptions pairs the ParseOptions of the two families, for parsing
+// strings whose family is not known in advance. The named profiles use the
+// profile of the same name on both sides:
+//
+//   let opts = ParseOptions::strict().v6(ipv6::ParseOptions::strict().zones(ipv6::Zones::Scoped));
+//   let addr = opts.parse("fe80::1%eth0")?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ParseOptions {
+    v4: ipv4::ParseOptions,
+    v6: ipv6::ParseOptions,
+}
+
+impl ParseOptions {
+    // strict uses ipv4::ParseOptions::strict and ipv6::ParseOptions::strict.
+    pub const fn strict() -> ParseOptions {
+        ParseOptions {
+            v4: ipv4::ParseOptions::strict(),
+            v6: ipv6::ParseOptions::strict(),
+        }
+    }
+
+    // std_compat uses ipv4::ParseOptions::std_compat and
+    // ipv6::ParseOptions::std_compat, so parse accepts exactly what
+    // std::net::IpAddr::from_str accepts.
+    pub const fn std_compat() -> ParseOptions {
+        ParseOptions {
+            v4: ipv4::ParseOptions::std_compat(),
+            v6: ipv6::ParseOptions::std_compat(),
+        }
+    }
+
+    // permissive uses ipv4::ParseOptions::permissive and
+    // ipv6::ParseOptions::permissive.
+    pub const fn permissive() -> ParseOptions {
+        ParseOptions {
+            v4: ipv4::ParseOptions::permissive(),
+            v6: ipv6::ParseOptions::permissive(),
+        }
+    }
+
+    // v4 sets the options used for IPv4 strings.
+    pub const fn v4(mut self, v4: ipv4::ParseOptions) -> ParseOptions {
+        self.v4 = v4;
+        self
+    }
+
+    // get_v4 returns the options used for IPv4 strings.
+    pub const fn get_v4(&self) -> ipv4::ParseOptions {
+        self.v4
+    }
+
+    // v6 sets the options used for IPv6 strings.
+    pub const fn v6(mut self, v6: ipv6::ParseOptions) -> ParseOptions {
+        self.v6 = v6;
+        self
+    }
+
+    // get_v6 returns the options used for IPv6 strings.
+    pub const fn get_v6(&self) -> ipv6::ParseOptions {
+        self.v6
+    }
+
+    // parse will parse a string of either family into an IpAddr using these
+    // options. See the parse_addr function.
+    pub fn parse(&self, s: &str) -> Result<IpAddr, InvalidAddrErr> {
+        if is_ipv6(s) {
+            Ok(IpAddr::V6(self.v6.parse(s)?))
+        } else {
+            Ok(IpAddr::V4(self.v4.parse(s)?))
+        }
+    }
+
+    // parse_const will parse a string of either family into an IpAddr
+    // using these options, in a const context.
+    pub const fn parse_const(&self, s: &str) -> Result<IpAddr, InvalidAddrErr> {
+        if is_ipv6(s) {
+            match self.v6.parse_const(s) {
+                Ok(addr) => Ok(IpAddr::V6(addr)),
+                Err(err) => Err(InvalidAddrErr::V6(err)),
+            }
+        } else {
+            match self.v4.parse_const(s) {
+                Ok(addr) => Ok(IpAddr::V4(addr)),
+                Err(err) => Err(InvalidAddrErr::V4(err)),
+            }
+        }
+    }
+
+    // parse_net will parse a prefix of either family into an IpNet using
+    // these options. See the parse_net function.
+    pub fn parse_net(&self, s: &str) -> Result<IpNet, InvalidPrefixErr> {
+        if is_ipv6(s) {
+            Ok(IpNet::V6(self.v6.parse_prefix(s)?))
+        } else {
+            Ok(IpNet::V4(self.v4.parse_prefix(s)?))
+        }
+    }
+
+    // parse_net_const will parse a prefix of either family into an IpNet
+    // using these options, in a const context.
+    pub const fn parse_net_const(&self, s: &str) -> Result<IpNet, InvalidPrefixErr> {
+        if is_ipv6(s) {
+            match self.v6.parse_prefix_const(s) {
+                Ok(p) => Ok(IpNet::V6(p)),
+                Err(err) => Err(InvalidPrefixErr::V6(err)),
+            }
+        } else {
+            match self.v4.parse_prefix_const(s) {
+                Ok(p) => Ok(IpNet::V4(p)),
+                Err(err) => Err(InvalidPrefixErr::V4(err)),
+            }
+        }
+    }
+}
+
+// is_ipv6 decides which family a string is parsed as. Every IPv6 address
+// has a colon and no IPv4 address does, so the error for a string that is
+// neither comes from the family it looks most like.
+pub(crate) const fn is_ipv6(s: &str) -> bool {
+    find(s.as_bytes(), b':').is_some()
+}
+
+// parse_addr will parse an IPv4 or IPv6 address into an IpAddr, picking the
+// family from the string: anything with a ':' is IPv6. It uses the default
+// ParseOptions, which accept what std::net::IpAddr::from_str accepts.
+pub fn parse_addr(s: &str) -> Result<IpAddr, InvalidAddrErr> {
+    ParseOptions::default().parse(s)
+}
+
+// parse_net will parse an IPv4 or IPv6 prefix in CIDR notation into an
+// IpNet, picking the family the same way as parse_addr.
+pub fn parse_net(s: &str) -> Result<IpNet, InvalidPrefixErr> {
+    ParseOptions::default().parse_net(s)
+}
+
+// parse_addr_const will parse an IPv4 or IPv6 address into an IpAddr in a
+// const context, like parse_addr. It is what the ip! macro expands to.
+pub const fn parse_addr_const(s: &str) -> Result<IpAddr, InvalidAddrErr> {
+    ParseOptions::std_compat().parse_const(s)
+}
+
+// parse_net_const will parse an IPv4 or IPv6 prefix into an IpNet in a const
+// context, like parse_net. It is what the prefix! macro expands to.
+pub const fn parse_net_const(s: &str) -> Result<IpNet, InvalidPrefixErr> {
+    ParseOptions::std_compat().parse_net_const(s)
+}
+
+// IpAddr is an address of either family. The derived ordering puts every
+// IPv4 address before every IPv6 address, and orders addresses of the same
+// family numerically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IpAddr {
+    V4(ipv4::Addr),
+    V6(ipv6::Addr),
+}
+
+// Classification is the special-purpose classification of an address of
+// either family. See ipv4::Classification and ipv6::Classification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Classification {
+    V4(ipv4::Classification),
+    V6(ipv6::Classification),
+}
+
+impl IpAddr {
+    // is_ipv4 returns true for an IPv4 address.
+    pub const fn is_ipv4(&self) -> bool {
+        matches!(self, IpAddr::V4(_))
+    }
+
+    // is_ipv6 returns true for an IPv6 address, including an IPv4-mapped one.
+    pub const fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddr::V6(_))
+    }
+
+    // max_prefix_len returns the number of bits in an address of this
+    // family: 32 or 128.
+    pub const fn max_prefix_len(&self) -> u8 {
+        match self {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    // to_canonical returns the IPv4 address of an IPv4-mapped IPv6 address,
+    // and any other address unchanged, like std::net::IpAddr::to_canonical.
+    // Use it before comparing addresses that may come from a dual-stack
+    // socket.
+    pub const fn to_canonical(&self) -> IpAddr {
+        match self {
+            IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => IpAddr::V4(v4),
+                None => *self,
+            },
+            IpAddr::V4(_) => *self,
+        }
+    }
+
+    // classification returns what kind of address this is.
+    pub fn classification(&self) -> Classification {
+        match self {
+            IpAddr::V4(v4) => Classification::V4(v4.classification()),
+            IpAddr::V6(v6) => Classification::V6(v6.classification()),
+        }
+    }
+
+    // is_global returns true if the address is globally reachable. See
+    // ipv4::Addr::is_global and ipv6::Addr::is_global.
+    pub fn is_global(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_global(),
+            IpAddr::V6(v6) => v6.is_global(),
+        }
+    }
+
+    // is_unspecified returns true for 0.0.0.0 and ::.
+    pub const fn is_unspecified(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_unspecified(),
+            IpAddr::V6(v6) => v6.is_unspecified(),
+        }
+    }
+
+    // is_loopback returns true for 127.0.0.0/8 and ::1.
+    pub const fn is_loopback(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_loopback(),
+            IpAddr::V6(v6) => v6.is_loopback(),
+        }
+    }
+
+    // is_private returns true for the RFC 1918 private-use blocks and for
+    // IPv6 unique local addresses, fc00::/7.
+    pub fn is_private(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_private(),
+            IpAddr::V6(v6) => v6.is_unique_local(),
+        }
+    }
+
+    // is_link_local returns true for 169.254.0.0/16 and fe80::/10.
+    pub const fn is_link_local(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_link_local(),
+            IpAddr::V6(v6) => v6.is_link_local(),
+        }
+    }
+
+    // is_documentation returns true for the documentation blocks of either
+    // family.
+    pub fn is_documentation(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_documentation(),
+            IpAddr::V6(v6) => v6.is_documentation(),
+        }
+    }
+
+    // is_multicast returns true for 224.0.0.0/4 and ff00::/8.
+    pub const fn is_multicast(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_multicast(),
+            IpAddr::V6(v6) => v6.is_multicast(),
+        }
+    }
+}
+
+impl fmt::Display for IpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpAddr::V4(v4) => write!(f, "{}", v4),
+            IpAddr::V6(v6) => write!(f, "{}", v6),
+        }
+    }
+}
+
+impl FromStr for IpAddr {
+    type Err = InvalidAddrErr;
+
+    fn from_str(s: &str) -> Result<IpAddr, InvalidAddrErr> {
+        parse_addr(s)
+    }
+}
+
+impl From<ipv4::Addr> for IpAddr {
+    fn from(addr: ipv4::Addr) -> IpAddr {
+        IpAddr::V4(addr)
+    }
+}
+
+impl From<ipv6::Addr> for IpAddr {
+    fn from(addr: ipv6::Addr) -> IpAddr {
+        IpAddr::V6(addr)
+    }
+}
+
+impl From<core::net::IpAddr> for IpAddr {
+    fn from(addr: core::net::IpAddr) -> IpAddr {
+        match addr {
+            core::net::IpAddr::V4(v4) => IpAddr::V4(v4.into()),
+            core::net::IpAddr::V6(v6) => IpAddr::V6(v6.into()),
+        }
+    }
+}
+
+impl From<IpAddr> for core::net::IpAddr {
+    // from converts to a std address. std addresses have no zone, so the
+    // zone of an IPv6 address is dropped.
+    fn from(addr: IpAddr) -> core::net::IpAddr {
+        match addr {
+            IpAddr::V4(v4) => core::net::IpAddr::V4(v4.into()),
+            IpAddr::V6(v6) => core::net::IpAddr::V6(v6.into()),
+        }
+    }
+}
+
+// IpNet is a prefix of either family. Like IpAddr, the derived ordering puts
+// every IPv4 prefix before every IPv6 prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IpNet {
+    V4(ipv4::Prefix),
+    V6(ipv6::Prefix),
+}
+
+impl IpNet {
+    // is_ipv4 returns true for an IPv4 prefix.
+    pub const fn is_ipv4(&self) -> bool {
+        matches!(self, IpNet::V4(_))
+    }
+
+    // is_ipv6 returns true for an IPv6 prefix.
+    pub const fn is_ipv6(&self) -> bool {
+        matches!(self, IpNet::V6(_))
+    }
+
+    // network returns the first address in the prefix.
+    pub const fn network(&self) -> IpAddr {
+        match self {
+            IpNet::V4(p) => IpAddr::V4(p.network()),
+            IpNet::V6(p) => IpAddr::V6(p.network()),
+        }
+    }
+
+    // prefix_len returns the length of the prefix in bits.
+    pub const fn prefix_len(&self) -> u8 {
+        match self {
+            IpNet::V4(p) => p.prefix_len(),
+            IpNet::V6(p) => p.prefix_len(),
+        }
+    }
+
+    // max_prefix_len returns the number of bits in an address of this
+    // family: 32 or 128.
+    pub const fn max_prefix_len(&self) -> u8 {
+        self.network().max_prefix_len()
+    }
+
+    // contains returns true if addr is in the prefix. An address of the
+    // other family is never in the prefix, so an IPv4-mapped address is not
+    // in an IPv4 prefix; call to_canonical on it first to treat it as IPv4.
+    pub const fn contains(&self, addr: IpAddr) -> bool {
+        match (self, addr) {
+            (IpNet::V4(p), IpAddr::V4(a)) => p.contains(a),
+            (IpNet::V6(p), IpAddr::V6(a)) => p.contains(a),
+            _ => false,
+        }
+    }
+
+    // contains_net returns true if every address in other is also in this
+    // prefix. Prefixes of different families never contain each other.
+    pub const fn contains_net(&self, other: &IpNet) -> bool {
+        match (self, other) {
+            (IpNet::V4(p), IpNet::V4(o)) => p.contains_prefix(o),
+            (IpNet::V6(p), IpNet::V6(o)) => p.contains_prefix(o),
+            _ => false,
+        }
+    }
+
+    // overlaps returns true if the two prefixes have any address in common.
+    // Prefixes of different families never overlap.
+    pub const fn overlaps(&self, other: &IpNet) -> bool {
+        match (self, other) {
+            (IpNet::V4(p), IpNet::V4(o)) => p.overlaps(o),
+            (IpNet::V6(p), IpNet::V6(o)) => p.overlaps(o),
+            _ => false,
+        }
+    }
+
+    // to_canonical returns the IPv4 prefix of an IPv6 prefix inside
+    // ::ffff:0:0/96, e.g. 10.0.0.0/8 for ::ffff:10.0.0.0/104, and any other
+    // prefix unchanged.
+    pub const fn to_canonical(&self) -> IpNet {
+        let IpNet::V6(p) = self else {
+            return *self;
+        };
+        let Some(v4) = p.network().to_ipv4_mapped() else {
+            return *self;
+        };
+        match p.prefix_len().checked_sub(96) {
+            Some(len) => match ipv4::Prefix::new(v4, len) {
+                Some(p) => IpNet::V4(p),
+                None => *self,
+            },
+            None => *self,
+        }
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpNet::V4(p) => write!(f, "{}", p),
+            IpNet::V6(p) => write!(f, "{}", p),
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = InvalidPrefixErr;
+
+    fn from_str(s: &str) -> Result<IpNet, InvalidPrefixErr> {
+        parse_net(s)
+    }
+}
+
+impl From<ipv4::Prefix> for IpNet {
+    fn from(p: ipv4::Prefix) -> IpNet {
+        IpNet::V4(p)
+    }
+}
+
+impl From<ipv6::Prefix> for IpNet {
+    fn from(p: ipv6::Prefix) -> IpNet {
+        IpNet::V6(p)
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    // an address on its own is the /32 or /128 prefix that contains only it.
+    fn from(addr: IpAddr) -> IpNet {
+        match addr {
+            IpAddr::V4(a) => IpNet::V4(a.into()),
+            IpAddr::V6(a) => IpNet::V6(a.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ip_tests {
+    use super::{
+        Classification, InvalidAddrErr, InvalidPrefixErr, IpAddr, IpNet, ParseOptions, parse_addr,
+        parse_net,
+    };
+    use crate::testing::{addr, net};
+    use crate::{ipv4, ipv6};
+
+    #[test]
+    fn test_parse_addr() {
+        let cases = Vec::from([
+            "0.0.0.0",
+            "10.1.2.3",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "2001:db8::1",
+            "::ffff:192.0.2.1",
+            "1.2.3",
+            "10.0.0.01",
+            "::1%eth0",
+            "[::1]",
+            "1:2",
+            "",
+        ]);
+
+        // the default options accept exactly what std does, in either family.
+        for s in cases {
+            let std = s.parse::<std::net::IpAddr>().ok().map(IpAddr::from);
+            assert_eq!(parse_addr(s).ok(), std, "parse_addr of {:?}", s);
+            if let Ok(addr) = parse_addr(s) {
+                assert_eq!(addr.to_string(), std::net::IpAddr::from(addr).to_string());
+            }
+        }
+
+        assert_eq!(addr("10.1.2.3"), IpAddr::V4(ipv4::Addr::new(10, 1, 2, 3)));
+        assert_eq!(
+            addr("2001:db8::1"),
+            IpAddr::V6(ipv6::Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))
+        );
+
+        // errors come from the family the string looks like.
+        assert_eq!(
+            parse_addr("10.0.0.256"),
+            Err(InvalidAddrErr::V4(ipv4::InvalidAddrErr::OctetOutOfRange {
+                offset: 7,
+                octet: 3,
+            }))
+        );
+        assert_eq!(
+            parse_addr("1::2::3"),
+            Err(InvalidAddrErr::V6(
+                ipv6::InvalidAddrErr::MultipleCompressions {
+                    offset: 4,
+                    group: 2,
+                }
+            ))
+        );
+        assert_eq!(parse_addr("1::2::3").unwrap_err().offset(), 4);
+
+        let opts = ParseOptions::permissive();
+        assert_eq!(opts.parse("10.0.0.01"), Ok(addr("10.0.0.1")));
+        assert!(opts.parse("fe80::1%eth0").unwrap().is_ipv6());
+        let opts = ParseOptions::default().v4(ipv4::ParseOptions::inet_aton());
+        assert_eq!(opts.parse("010.0.0.1"), Ok(addr("8.0.0.1")));
+        assert_eq!(opts.get_v6(), ipv6::ParseOptions::default());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut addrs = Vec::from([
+            addr("::1"),
+            addr("10.0.0.1"),
+            addr("::"),
+            addr("255.255.255.255"),
+            addr("0.0.0.0"),
+            addr("::ffff:0.0.0.1"),
+        ]);
+        addrs.sort();
+        assert_eq!(
+            addrs,
+            [
+                addr("0.0.0.0"),
+                addr("10.0.0.1"),
+                addr("255.255.255.255"),
+                addr("::"),
+                addr("::1"),
+                addr("::ffff:0.0.0.1"),
+            ]
+        );
+
+        let mut nets = Vec::from([
+            net("::/0"),
+            net("10.0.0.0/8"),
+            net("0.0.0.0/0"),
+            net("10.0.0.0/16"),
+        ]);
+        nets.sort();
+        assert_eq!(
+            nets,
+            [
+                net("0.0.0.0/0"),
+                net("10.0.0.0/8"),
+                net("10.0.0.0/16"),
+                net("::/0")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_canonical() {
+        assert_eq!(addr("::ffff:10.0.0.1").to_canonical(), addr("10.0.0.1"));
+        assert_eq!(addr("::10.0.0.1").to_canonical(), addr("::10.0.0.1"));
+        assert_eq!(addr("10.0.0.1").to_canonical(), addr("10.0.0.1"));
+        assert_eq!(addr("2001:db8::1").to_canonical(), addr("2001:db8::1"));
+
+        let cases = Vec::from([
+            ("::ffff:10.0.0.0/104", "10.0.0.0/8"),
+            ("::ffff:0.0.0.0/96", "0.0.0.0/0"),
+            ("::ffff:10.1.2.3/128", "10.1.2.3/32"),
+            ("::/0", "::/0"),
+            ("::ffff:0:0/95", "::fffe:0:0/95"),
+            ("10.0.0.0/8", "10.0.0.0/8"),
+        ]);
+        for (s, want) in cases {
+            assert_eq!(
+                ParseOptions::permissive()
+                    .parse_net(s)
+                    .unwrap()
+                    .to_canonical(),
+                ParseOptions::permissive().parse_net(want).unwrap(),
+                "to_canonical of {}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_family_agnostic() {
+        let cases = Vec::from([
+            // (addr, global, loopback, private, link-local, documentation, multicast)
+            ("8.8.8.8", true, false, false, false, false, false),
+            (
+                "2001:4860:4860::8888",
+                true,
+                false,
+                false,
+                false,
+                false,
+                false,
+            ),
+            ("127.0.0.1", false, true, false, false, false, false),
+            ("::1", false, true, false, false, false, false),
+            ("192.168.1.1", false, false, true, false, false, false),
+            ("fd00::1", false, false, true, false, false, false),
+            ("169.254.1.1", false, false, false, true, false, false),
+            ("fe80::1", false, false, false, true, false, false),
+            ("192.0.2.1", false, false, false, false, true, false),
+            ("2001:db8::1", false, false, false, false, true, false),
+            ("224.0.0.1", false, false, false, false, false, true),
+            ("ff02::1", false, false, false, false, false, true),
+        ]);
+
+        for (s, global, loopback, private, link_local, documentation, multicast) in cases {
+            let a = addr(s);
+            assert_eq!(a.is_global(), global, "is_global of {}", s);
+            assert_eq!(a.is_loopback(), loopback, "is_loopback of {}", s);
+            assert_eq!(a.is_private(), private, "is_private of {}", s);
+            assert_eq!(a.is_link_local(), link_local, "is_link_local of {}", s);
+            assert_eq!(
+                a.is_documentation(),
+                documentation,
+                "is_documentation of {}",
+                s
+            );
+            assert_eq!(a.is_multicast(), multicast, "is_multicast of {}", s);
+        }
+
+        assert!(addr("0.0.0.0").is_unspecified());
+        assert!(addr("::").is_unspecified());
+        assert_eq!(
+            addr("10.0.0.1").classification(),
+            Classification::V4(ipv4::Classification::Private)
+        );
+        assert_eq!(
+            addr("fc00::1").classification(),
+            Classification::V6(ipv6::Classification::UniqueLocal)
+        );
+        assert_eq!(addr("10.0.0.1").max_prefix_len(), 32);
+        assert_eq!(addr("::").max_prefix_len(), 128);
+    }
+
+    #[test]
+    fn test_ip_net() {
+        let v4 = net("10.0.0.0/8");
+        let v6 = net("2001:db8::/32");
+        assert!(v4.is_ipv4() && v6.is_ipv6());
+        assert_eq!(v4.prefix_len(), 8);
+        assert_eq!(v6.prefix_len(), 32);
+        assert_eq!(v4.max_prefix_len(), 32);
+        assert_eq!(v6.max_prefix_len(), 128);
+        assert_eq!(v4.network(), addr("10.0.0.0"));
+        assert_eq!(v6.network(), addr("2001:db8::"));
+
+        assert!(v4.contains(addr("10.1.2.3")));
+        assert!(!v4.contains(addr("::ffff:10.1.2.3")));
+        assert!(v4.contains(addr("::ffff:10.1.2.3").to_canonical()));
+        assert!(v6.contains(addr("2001:db8::1")));
+        assert!(!v6.contains(addr("10.0.0.1")));
+
+        assert!(v4.contains_net(&net("10.1.0.0/16")));
+        assert!(!v4.contains_net(&net("::ffff:10.0.0.0/104")));
+        assert!(v6.overlaps(&net("2001:db8:1::/48")));
+        assert!(!v6.overlaps(&v4));
+        assert!(!net("0.0.0.0/0").overlaps(&net("::/0")));
+
+        assert_eq!(IpNet::from(addr("10.0.0.1")), net("10.0.0.1/32"));
+        assert_eq!(IpNet::from(addr("::1")), net("::1/128"));
+        assert_eq!(v6.to_string(), "2001:db8::/32");
+
+        assert_eq!(
+            parse_net("10.0.0.1/8"),
+            Err(InvalidPrefixErr::V4(ipv4::InvalidPrefixErr::HostBitsSet {
+                offset: 7
+            }))
+        );
+        assert_eq!(
+            parse_net("2001:db8::/129"),
+            Err(InvalidPrefixErr::V6(
+                ipv6::InvalidPrefixErr::LengthOutOfRange { offset: 11 }
+            ))
+        );
+        assert_eq!(ParseOptions::permissive().parse_net("10.1.2.3/8"), Ok(v4));
+    }
+}
@@ -0,0 +1,662 @@
+use alloc::collections::{BTreeSet, btree_set};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::bits::Bits;
+use crate::{InvalidPrefixErr, IpNet, parse_net};
+
+// AllocErr describes why an Ipam could not do what it was asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AllocErr {
+    // the prefix length asked for is longer than an address.
+    InvalidLen,
+    // there is no free prefix of the length asked for.
+    NoSpace,
+    // the prefix is not inside a pool.
+    NotInPool,
+    // some of the prefix is allocated already.
+    InUse,
+    // the prefix to free was not allocated, or was allocated with another
+    // length.
+    NotAllocated,
+    // the new pool overlaps a pool that is there already.
+    PoolOverlap,
+}
+
+impl AllocErr {
+    // reason returns a short description of the error.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            AllocErr::InvalidLen => "prefix length out of range",
+            AllocErr::NoSpace => "no free prefix of that length",
+            AllocErr::NotInPool => "prefix not in a pool",
+            AllocErr::InUse => "prefix already allocated",
+            AllocErr::NotAllocated => "prefix not allocated",
+            AllocErr::PoolOverlap => "pool overlaps another pool",
+        }
+    }
+}
+
+impl fmt::Display for AllocErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason())
+    }
+}
+
+impl core::error::Error for AllocErr {}
+
+// InvalidCheckpointErr describes why a string is not a valid Ipam
+// checkpoint. Every variant carries the line where the problem was found,
+// counting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidCheckpointErr {
+    // the line is not one of the kinds a checkpoint has.
+    Syntax { line: usize },
+    // the prefix on the line is invalid.
+    Prefix { line: usize, err: InvalidPrefixErr },
+    // the pool or allocation on the line conflicts with an earlier one.
+    Alloc { line: usize, err: AllocErr },
+}
+
+impl InvalidCheckpointErr {
+    // line returns the line at which the error was found.
+    pub const fn line(&self) -> usize {
+        match *self {
+            InvalidCheckpointErr::Syntax { line }
+            | InvalidCheckpointErr::Prefix { line, .. }
+            | InvalidCheckpointErr::Alloc { line, .. } => line,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidCheckpointErr::Syntax { .. } => "unknown line",
+            InvalidCheckpointErr::Prefix { err, .. } => err.reason(),
+            InvalidCheckpointErr::Alloc { err, .. } => err.reason(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidCheckpointErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid ipam checkpoint: {} at line {}",
+            self.reason(),
+            self.line()
+        )
+    }
+}
+
+impl core::error::Error for InvalidCheckpointErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidCheckpointErr::Syntax { .. } => None,
+            InvalidCheckpointErr::Prefix { err, .. } => Some(err),
+            InvalidCheckpointErr::Alloc { err, .. } => Some(err),
+        }
+    }
+}
+
+// Policy is how an Ipam picks the free block to carve a new prefix from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    // FirstFit takes the lowest free prefix that is long enough, which
+    // packs allocations towards the start of the pools.
+    #[default]
+    FirstFit,
+    // BestFit takes the smallest free block that is big enough, lowest
+    // first, which leaves the big blocks whole for big requests.
+    BestFit,
+}
+
+// Ipam hands out prefixes from pools of address space, as a provisioning
+// service carves /24s or /64s out of the supernets it owns.
+//
+// It is a buddy allocator. Free space is kept as a set of free prefixes,
+// one list per length. To allocate, a free prefix is split in halves until
+// one half has the length asked for, and the other halves stay free; when a
+// prefix is freed and its buddy, the other half of the prefix one bit
+// shorter, is free too, the two merge back, as far up as the pool. So the
+// free space of a pool is always described by the fewest prefixes, and a
+// fragmented pool heals as allocations are returned. Allocating, reserving
+// and freeing take O(W log n) for W the address width and n the number of
+// free blocks.
+//
+// Its state is the pools and the prefixes allocated from them, and Display
+// writes that out as a checkpoint that FromStr reads back:
+//
+//   policy first-fit
+//   pool 10.0.0.0/8
+//   pool 2001:db8::/32
+//   allocated 10.0.0.0/24
+//   allocated 2001:db8::/64
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ipam {
+    policy: Policy,
+    v4: Buddy<u32>,
+    v6: Buddy<u128>,
+}
+
+impl Ipam {
+    // new returns an Ipam without pools that allocates first-fit.
+    pub fn new() -> Ipam {
+        Ipam::default()
+    }
+
+    // policy sets how free blocks are picked.
+    pub fn policy(mut self, policy: Policy) -> Ipam {
+        self.policy = policy;
+        self
+    }
+
+    // get_policy returns how free blocks are picked.
+    pub fn get_policy(&self) -> Policy {
+        self.policy
+    }
+
+    // add_pool adds a prefix to allocate from. Pools cannot overlap.
+    pub fn add_pool(&mut self, pool: IpNet) -> Result<(), AllocErr> {
+        match pool {
+            IpNet::V4(p) => self.v4.add_pool(p.network().to_bits(), p.prefix_len()),
+            IpNet::V6(p) => self.v6.add_pool(p.network().to_bits(), p.prefix_len()),
+        }
+    }
+
+    // allocate returns the next free prefix of length len inside within,
+    // and marks it allocated. within can be a pool, part of one, or a
+    // prefix that covers several, such as 0.0.0.0/0 to allocate from any
+    // IPv4 pool; its family is the family of the result.
+    pub fn allocate(&mut self, within: IpNet, len: u8) -> Result<IpNet, AllocErr> {
+        let policy = self.policy;
+        match within {
+            IpNet::V4(p) => self
+                .v4
+                .allocate(p.network().to_bits(), p.prefix_len(), len, policy),
+            IpNet::V6(p) => self
+                .v6
+                .allocate(p.network().to_bits(), p.prefix_len(), len, policy),
+        }
+    }
+
+    // reserve marks a specific prefix allocated, e.g. a gateway subnet that
+    // must sit at a known place in the pool.
+    pub fn reserve(&mut self, net: IpNet) -> Result<(), AllocErr> {
+        match net {
+            IpNet::V4(p) => self.v4.reserve(p.network().to_bits(), p.prefix_len()),
+            IpNet::V6(p) => self.v6.reserve(p.network().to_bits(), p.prefix_len()),
+        }
+    }
+
+    // free returns an allocated prefix to its pool. It has to be freed
+    // whole, as it was allocated or reserved.
+    pub fn free(&mut self, net: IpNet) -> Result<(), AllocErr> {
+        match net {
+            IpNet::V4(p) => self.v4.free(p.network().to_bits(), p.prefix_len()),
+            IpNet::V6(p) => self.v6.free(p.network().to_bits(), p.prefix_len()),
+        }
+    }
+
+    // is_free returns true if the whole of net is inside a pool and none
+    // of it is allocated.
+    pub fn is_free(&self, net: IpNet) -> bool {
+        match net {
+            IpNet::V4(p) => self
+                .v4
+                .free_block(p.network().to_bits(), p.prefix_len())
+                .is_some(),
+            IpNet::V6(p) => self
+                .v6
+                .free_block(p.network().to_bits(), p.prefix_len())
+                .is_some(),
+        }
+    }
+
+    // pools returns the pools, in order.
+    pub fn pools(&self) -> Blocks<'_> {
+        Blocks {
+            v4: self.v4.pools.iter(),
+            v6: self.v6.pools.iter(),
+        }
+    }
+
+    // allocated returns the allocated prefixes, in order.
+    pub fn allocated(&self) -> Blocks<'_> {
+        Blocks {
+            v4: self.v4.allocated.iter(),
+            v6: self.v6.allocated.iter(),
+        }
+    }
+}
+
+impl fmt::Display for Ipam {
+    // fmt writes the checkpoint of the Ipam. See Ipam.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.policy {
+            Policy::FirstFit => writeln!(f, "policy first-fit")?,
+            Policy::BestFit => writeln!(f, "policy best-fit")?,
+        }
+        for pool in self.pools() {
+            writeln!(f, "pool {}", pool)?;
+        }
+        for net in self.allocated() {
+            writeln!(f, "allocated {}", net)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Ipam {
+    type Err = InvalidCheckpointErr;
+
+    // from_str restores an Ipam from its checkpoint. Blank lines are
+    // skipped.
+    fn from_str(s: &str) -> Result<Ipam, InvalidCheckpointErr> {
+        let mut ipam = Ipam::new();
+        for (i, text) in s.lines().enumerate() {
+            let line = i + 1;
+            let (kind, arg) = text.trim().split_once(' ').unwrap_or((text.trim(), ""));
+            let net = || parse_net(arg).map_err(|err| InvalidCheckpointErr::Prefix { line, err });
+            let alloc = |err| InvalidCheckpointErr::Alloc { line, err };
+            match (kind, arg) {
+                ("", "") => {}
+                ("policy", "first-fit") => ipam.policy = Policy::FirstFit,
+                ("policy", "best-fit") => ipam.policy = Policy::BestFit,
+                ("pool", _) => ipam.add_pool(net()?).map_err(alloc)?,
+                ("allocated", _) => ipam.reserve(net()?).map_err(alloc)?,
+                _ => return Err(InvalidCheckpointErr::Syntax { line }),
+            }
+        }
+        Ok(ipam)
+    }
+}
+
+// Blocks iterates over the pools or the allocated prefixes of an Ipam. See
+// Ipam::pools and Ipam::allocated.
+#[derive(Debug, Clone)]
+pub struct Blocks<'a> {
+    v4: btree_set::Iter<'a, (u32, u8)>,
+    v6: btree_set::Iter<'a, (u128, u8)>,
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = IpNet;
+
+    fn next(&mut self) -> Option<IpNet> {
+        match self.v4.next() {
+            Some(&(bits, len)) => Some(bits.net(len)),
+            None => self.v6.next().map(|&(bits, len)| bits.net(len)),
+        }
+    }
+}
+
+// Buddy is the allocator for one family.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Buddy<K> {
+    // pools and allocated hold prefixes as their start and length.
+    pools: BTreeSet<(K, u8)>,
+    // free[len] has the starts of the free prefixes of that length.
+    free: Vec<BTreeSet<K>>,
+    allocated: BTreeSet<(K, u8)>,
+}
+
+impl<K: Bits> Default for Buddy<K> {
+    fn default() -> Buddy<K> {
+        Buddy {
+            pools: BTreeSet::new(),
+            free: (0..=K::BITS).map(|_| BTreeSet::new()).collect(),
+            allocated: BTreeSet::new(),
+        }
+    }
+}
+
+impl<K: Bits> Buddy<K> {
+    // pool returns the length of the pool that contains the prefix, if any.
+    fn pool(&self, bits: K, len: u8) -> Option<u8> {
+        let &(start, pool_len) = self.pools.range(..=(bits, u8::MAX)).next_back()?;
+        (pool_len <= len && bits.masked(pool_len) == start).then_some(pool_len)
+    }
+
+    fn add_pool(&mut self, bits: K, len: u8) -> Result<(), AllocErr> {
+        // pools are disjoint, so only the last one that starts inside or
+        // before the new pool can overlap it.
+        let last = bits.last(len);
+        if let Some(&(start, pool_len)) = self.pools.range(..=(last, u8::MAX)).next_back()
+            && start.last(pool_len) >= bits
+        {
+            return Err(AllocErr::PoolOverlap);
+        }
+        self.pools.insert((bits, len));
+        self.free[len as usize].insert(bits);
+        Ok(())
+    }
+
+    // free_block returns the free prefix that contains the prefix, if any.
+    fn free_block(&self, bits: K, len: u8) -> Option<(K, u8)> {
+        let pool_len = self.pool(bits, len)?;
+        (pool_len..=len)
+            .map(|l| (bits.masked(l), l))
+            .find(|&(start, l)| self.free[l as usize].contains(&start))
+    }
+
+    fn allocate(
+        &mut self,
+        bits: K,
+        within: u8,
+        len: u8,
+        policy: Policy,
+    ) -> Result<IpNet, AllocErr> {
+        if len > K::BITS {
+            return Err(AllocErr::InvalidLen);
+        }
+        if len < within {
+            return Err(AllocErr::NoSpace);
+        }
+        // if all of within is free, the result is its first prefix.
+        if let Some(block) = self.free_block(bits, within) {
+            self.carve(block, bits, len);
+            return Ok(bits.net(len));
+        }
+
+        // otherwise it comes from one of the free blocks inside within,
+        // which are the ones of length within to len that start there.
+        let last = bits.last(within);
+        let mut found: Option<(K, u8)> = None;
+        for l in within..=len {
+            let Some(&start) = self.free[l as usize].range(bits..=last).next() else {
+                continue;
+            };
+            match policy {
+                Policy::FirstFit if found.is_some_and(|(s, _)| s < start) => {}
+                Policy::FirstFit | Policy::BestFit => found = Some((start, l)),
+            }
+        }
+        let block = found.ok_or(AllocErr::NoSpace)?;
+        self.carve(block, block.0, len);
+        Ok(block.0.net(len))
+    }
+
+    // carve allocates the prefix, which is inside the free block, splitting
+    // the block down to it and freeing the halves on either side.
+    fn carve(&mut self, block: (K, u8), bits: K, len: u8) {
+        let (mut start, mut l) = block;
+        self.free[l as usize].remove(&start);
+        while l < len {
+            start = bits.masked(l + 1);
+            self.free[l as usize + 1].insert(start.flip(l));
+            l += 1;
+        }
+        self.allocated.insert((bits, len));
+    }
+
+    fn reserve(&mut self, bits: K, len: u8) -> Result<(), AllocErr> {
+        self.pool(bits, len).ok_or(AllocErr::NotInPool)?;
+        let block = self.free_block(bits, len).ok_or(AllocErr::InUse)?;
+        self.carve(block, bits, len);
+        Ok(())
+    }
+
+    fn free(&mut self, bits: K, len: u8) -> Result<(), AllocErr> {
+        if !self.allocated.remove(&(bits, len)) {
+            return Err(AllocErr::NotAllocated);
+        }
+        // merge with the buddy as long as it is free, up to the pool.
+        let pool_len = self.pool(bits, len).unwrap();
+        let (mut start, mut l) = (bits, len);
+        while l > pool_len && self.free[l as usize].remove(&start.flip(l - 1)) {
+            l -= 1;
+            start = start.masked(l);
+        }
+        self.free[l as usize].insert(start);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ipam_tests {
+    use super::{AllocErr, InvalidCheckpointErr, Ipam, Policy};
+    use crate::rng::Rng;
+    use crate::testing::net;
+    use crate::{InvalidPrefixErr, IpNet, ipv4};
+
+    fn ipam(pools: &[&str]) -> Ipam {
+        let mut ipam = Ipam::new();
+        for pool in pools {
+            ipam.add_pool(net(pool)).unwrap();
+        }
+        ipam
+    }
+
+    #[test]
+    fn test_allocate() {
+        let mut ipam = ipam(&["10.0.0.0/22", "2001:db8::/48"]);
+        let pool = net("10.0.0.0/22");
+        let cases = Vec::from([
+            (24, Ok("10.0.0.0/24")),
+            (25, Ok("10.0.1.0/25")),
+            (24, Ok("10.0.2.0/24")),
+            (26, Ok("10.0.1.128/26")),
+            (23, Err(AllocErr::NoSpace)),
+            (33, Err(AllocErr::InvalidLen)),
+            (21, Err(AllocErr::NoSpace)),
+            (24, Ok("10.0.3.0/24")),
+            (24, Err(AllocErr::NoSpace)),
+        ]);
+        for (len, want) in cases {
+            assert_eq!(ipam.allocate(pool, len), want.map(net), "allocate /{}", len);
+        }
+
+        // /64s from the IPv6 pool, and from anywhere in IPv6.
+        let pool = net("2001:db8::/48");
+        assert_eq!(ipam.allocate(pool, 64), Ok(net("2001:db8::/64")));
+        assert_eq!(ipam.allocate(pool, 64), Ok(net("2001:db8:0:1::/64")));
+        assert_eq!(ipam.allocate(net("::/0"), 64), Ok(net("2001:db8:0:2::/64")));
+        assert_eq!(ipam.allocate(net("fd00::/8"), 64), Err(AllocErr::NoSpace));
+    }
+
+    #[test]
+    fn test_allocate_within() {
+        // from part of a pool.
+        let mut ipam = ipam(&["10.0.0.0/16"]);
+        assert_eq!(
+            ipam.allocate(net("10.0.128.0/17"), 24),
+            Ok(net("10.0.128.0/24"))
+        );
+        assert_eq!(
+            ipam.allocate(net("10.0.128.0/17"), 24),
+            Ok(net("10.0.129.0/24"))
+        );
+        assert_eq!(
+            ipam.allocate(net("10.0.0.0/16"), 24),
+            Ok(net("10.0.0.0/24"))
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        // a /26 hole at the start and a /25 one after it: first-fit takes
+        // a /27 from the first and best-fit from the smallest.
+        for (policy, want) in [
+            (Policy::FirstFit, "10.0.0.0/27"),
+            (Policy::BestFit, "10.0.0.128/27"),
+        ] {
+            let mut ipam = ipam(&["10.0.0.0/24"]).policy(policy);
+            assert_eq!(ipam.get_policy(), policy);
+            ipam.reserve(net("10.0.0.64/26")).unwrap();
+            ipam.reserve(net("10.0.0.192/26")).unwrap();
+            ipam.free(net("10.0.0.192/26")).unwrap();
+            ipam.reserve(net("10.0.0.160/27")).unwrap();
+            ipam.reserve(net("10.0.0.192/26")).unwrap();
+            assert_eq!(
+                ipam.allocate(net("10.0.0.0/24"), 27),
+                Ok(net(want)),
+                "{:?}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn test_reserve_and_free() {
+        let empty = ipam(&["10.0.0.0/16"]);
+        let mut ipam = empty.clone();
+        assert_eq!(ipam.reserve(net("10.0.5.0/24")), Ok(()));
+        assert_eq!(ipam.reserve(net("10.0.5.128/25")), Err(AllocErr::InUse));
+        assert_eq!(ipam.reserve(net("10.0.4.0/23")), Err(AllocErr::InUse));
+        assert_eq!(ipam.reserve(net("10.1.0.0/24")), Err(AllocErr::NotInPool));
+        assert_eq!(ipam.reserve(net("10.0.0.0/15")), Err(AllocErr::NotInPool));
+        assert!(ipam.is_free(net("10.0.4.0/24")));
+        assert!(!ipam.is_free(net("10.0.4.0/23")));
+        assert!(!ipam.is_free(net("10.1.0.0/24")));
+
+        // first-fit goes around the reservation.
+        assert_eq!(
+            ipam.allocate(net("10.0.0.0/16"), 22),
+            Ok(net("10.0.0.0/22"))
+        );
+        assert_eq!(
+            ipam.allocate(net("10.0.0.0/16"), 23),
+            Ok(net("10.0.6.0/23"))
+        );
+        assert_eq!(
+            ipam.allocate(net("10.0.0.0/16"), 24),
+            Ok(net("10.0.4.0/24"))
+        );
+
+        assert_eq!(ipam.free(net("10.0.5.0/25")), Err(AllocErr::NotAllocated));
+        assert_eq!(ipam.free(net("10.0.5.0/24")), Ok(()));
+        assert_eq!(ipam.free(net("10.0.5.0/24")), Err(AllocErr::NotAllocated));
+
+        // freeing everything merges the pool back into one block.
+        let nets: Vec<IpNet> = ipam.allocated().collect();
+        for n in nets {
+            ipam.free(n).unwrap();
+        }
+        assert!(ipam.is_free(net("10.0.0.0/16")));
+        assert_eq!(ipam, empty);
+    }
+
+    #[test]
+    fn test_pools() {
+        let mut ipam = ipam(&["10.0.0.0/16", "10.2.0.0/16"]);
+        let cases = Vec::from([
+            ("10.0.0.0/8", Err(AllocErr::PoolOverlap)),
+            ("10.0.1.0/24", Err(AllocErr::PoolOverlap)),
+            ("10.2.255.0/24", Err(AllocErr::PoolOverlap)),
+            ("10.1.0.0/16", Ok(())),
+            ("::/0", Ok(())),
+        ]);
+        for (s, want) in cases {
+            assert_eq!(ipam.add_pool(net(s)), want, "add_pool({})", s);
+        }
+        let pools: Vec<IpNet> = ipam.pools().collect();
+        assert_eq!(
+            pools,
+            ["10.0.0.0/16", "10.1.0.0/16", "10.2.0.0/16", "::/0"].map(net)
+        );
+
+        // adjacent pools never merge: a /15 cannot span two of them.
+        assert_eq!(ipam.allocate(net("10.0.0.0/8"), 15), Err(AllocErr::NoSpace));
+        assert_eq!(ipam.allocate(net("10.0.0.0/8"), 16), Ok(net("10.0.0.0/16")));
+        ipam.free(net("10.0.0.0/16")).unwrap();
+        assert!(ipam.is_free(net("10.0.0.0/16")));
+        assert!(!ipam.is_free(net("10.0.0.0/15")));
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut ipam = ipam(&["10.0.0.0/8", "2001:db8::/32"]).policy(Policy::BestFit);
+        ipam.allocate(net("10.0.0.0/8"), 24).unwrap();
+        ipam.reserve(net("10.1.0.0/16")).unwrap();
+        ipam.allocate(net("2001:db8::/32"), 64).unwrap();
+
+        let checkpoint = ipam.to_string();
+        assert_eq!(
+            checkpoint,
+            "policy best-fit\n\
+             pool 10.0.0.0/8\n\
+             pool 2001:db8::/32\n\
+             allocated 10.0.0.0/24\n\
+             allocated 10.1.0.0/16\n\
+             allocated 2001:db8::/64\n"
+        );
+        let restored: Ipam = checkpoint.parse().unwrap();
+        assert_eq!(restored, ipam);
+
+        let cases = Vec::from([
+            ("pool 10.0.0.0/8\n\nallocated 10.0.0.0/24", Ok(())),
+            (
+                "policy worst-fit",
+                Err(InvalidCheckpointErr::Syntax { line: 1 }),
+            ),
+            (
+                "pool 10.0.0.0/8\nfree 10.0.0.0/24",
+                Err(InvalidCheckpointErr::Syntax { line: 2 }),
+            ),
+            (
+                "pool 10.0.0.1/8",
+                Err(InvalidCheckpointErr::Prefix {
+                    line: 1,
+                    err: InvalidPrefixErr::V4(ipv4::InvalidPrefixErr::HostBitsSet { offset: 7 }),
+                }),
+            ),
+            (
+                "pool 10.0.0.0/8\nallocated 10.0.0.0/24\nallocated 10.0.0.0/25",
+                Err(InvalidCheckpointErr::Alloc {
+                    line: 3,
+                    err: AllocErr::InUse,
+                }),
+            ),
+        ]);
+        for (s, want) in cases {
+            assert_eq!(s.parse::<Ipam>().map(|_| ()), want, "parse({:?})", s);
+        }
+        let err = "pool 10.0.0.0/8\npool 10.0.0.0/9"
+            .parse::<Ipam>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid ipam checkpoint: pool overlaps another pool at line 2"
+        );
+    }
+
+    #[test]
+    fn test_random() {
+        // random allocations and frees never hand out overlapping prefixes,
+        // and freeing everything leaves the pool as it started.
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        for policy in [Policy::FirstFit, Policy::BestFit] {
+            let pool = net("10.0.0.0/16");
+            let empty = ipam(&["10.0.0.0/16"]).policy(policy);
+            let mut ipam = empty.clone();
+            let mut live: Vec<IpNet> = Vec::new();
+            for _ in 0..3000 {
+                if rng.next().is_multiple_of(3) && !live.is_empty() {
+                    let n = live.swap_remove(rng.next() as usize % live.len());
+                    assert_eq!(ipam.free(n), Ok(()), "free({})", n);
+                    continue;
+                }
+                let len = 18 + (rng.next() % 15) as u8;
+                let Ok(n) = ipam.allocate(pool, len) else {
+                    continue;
+                };
+                assert_eq!(n.prefix_len(), len);
+                assert!(pool.contains_net(&n), "{} outside the pool", n);
+                for other in &live {
+                    assert!(!n.overlaps(other), "{} overlaps {}", n, other);
+                }
+                live.push(n);
+            }
+            let restored: Ipam = ipam.to_string().parse().unwrap();
+            assert_eq!(restored, ipam, "{:?}", policy);
+            for n in live {
+                ipam.free(n).unwrap();
+            }
+            assert_eq!(ipam, empty);
+        }
+    }
+}
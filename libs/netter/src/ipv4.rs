@@ -0,0 +1,1496 @@
+use core::fmt;
+use core::net::Ipv4Addr;
+use core::str::FromStr;
+
+mod arith;
+pub(crate) mod defang;
+mod mask;
+mod prefix;
+mod range;
+mod reverse;
+mod simd;
+mod special;
+mod swar;
+mod whatwg;
+
+pub use defang::Defanged;
+#[cfg(feature = "alloc")]
+pub use defang::parse_defanged;
+pub use mask::{
+    Netmask, NetmaskNotation, Wildcard, parse_netmask, parse_netmask_prefix, parse_wildcard,
+};
+pub use prefix::{
+    HostBits, Hosts, InvalidPrefixErr, Prefix, Subnets, Supernets, parse_prefix, parse_prefix_const,
+};
+#[cfg(feature = "alloc")]
+pub use range::collapse_prefixes;
+pub use range::{AddrRange, Addrs, InvalidRangeErr, Prefixes, parse_range};
+pub use reverse::{
+    InvalidReverseNameErr, ReverseName, ReverseZone, parse_reverse_name, parse_reverse_zone,
+};
+pub use special::{Classification, MulticastScope, SpecialPurpose, special_purpose_registry};
+pub use whatwg::{Form, Notation, ends_in_a_number, parse_whatwg};
+
+type Result<T> = core::result::Result<T, InvalidAddrErr>;
+
+// InvalidAddrErr describes why a string is not a valid IPv4 address. Every
+// variant carries the byte offset in the input where the problem was found
+// and the index (0-3) of the octet that was being read at the time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidAddrErr {
+    // a character other than an ASCII digit or a dot was found.
+    InvalidChar { offset: usize, octet: usize },
+    // an octet had no digits, e.g. "10..0.1" or ".10.0.0.1".
+    EmptyOctet { offset: usize, octet: usize },
+    // an octet had more than three digits, e.g. "10.1000.0.1".
+    OctetTooLong { offset: usize, octet: usize },
+    // an octet started with a zero and the ParseOptions reject leading
+    // zeros, e.g. "10.01.0.1".
+    LeadingZero { offset: usize, octet: usize },
+    // an octet read as octal had an 8 or a 9 in it, e.g. "10.08.0.1".
+    InvalidOctalDigit { offset: usize, octet: usize },
+    // an octet was greater than 255, e.g. "10.256.0.1".
+    OctetOutOfRange { offset: usize, octet: usize },
+    // a dot was found after the fourth octet, e.g. "10.0.0.1.".
+    TooManyOctets { offset: usize, octet: usize },
+    // the input ended before four octets were read, e.g. "10.0.1".
+    TooFewOctets { offset: usize, octet: usize },
+}
+
+impl InvalidAddrErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidAddrErr::InvalidChar { offset, .. }
+            | InvalidAddrErr::EmptyOctet { offset, .. }
+            | InvalidAddrErr::OctetTooLong { offset, .. }
+            | InvalidAddrErr::LeadingZero { offset, .. }
+            | InvalidAddrErr::InvalidOctalDigit { offset, .. }
+            | InvalidAddrErr::OctetOutOfRange { offset, .. }
+            | InvalidAddrErr::TooManyOctets { offset, .. }
+            | InvalidAddrErr::TooFewOctets { offset, .. } => offset,
+        }
+    }
+
+    // octet returns the index (0-3) of the octet that was being read when
+    // the error was found.
+    pub const fn octet(&self) -> usize {
+        match *self {
+            InvalidAddrErr::InvalidChar { octet, .. }
+            | InvalidAddrErr::EmptyOctet { octet, .. }
+            | InvalidAddrErr::OctetTooLong { octet, .. }
+            | InvalidAddrErr::LeadingZero { octet, .. }
+            | InvalidAddrErr::InvalidOctalDigit { octet, .. }
+            | InvalidAddrErr::OctetOutOfRange { octet, .. }
+            | InvalidAddrErr::TooManyOctets { octet, .. }
+            | InvalidAddrErr::TooFewOctets { octet, .. } => octet,
+        }
+    }
+
+    // shifted returns the error with its offset moved by start, for errors
+    // found in a substring that starts at start in the original input.
+    pub(crate) const fn shifted(self, start: usize) -> InvalidAddrErr {
+        self.with_offset(self.offset() + start)
+    }
+
+    // with_offset returns the error with its offset replaced by offset, for
+    // errors whose position in the original input is not a fixed distance
+    // from where they were found, such as in refanged text.
+    pub(crate) const fn with_offset(self, offset: usize) -> InvalidAddrErr {
+        use InvalidAddrErr::*;
+
+        match self {
+            InvalidChar { octet, .. } => InvalidChar { offset, octet },
+            EmptyOctet { octet, .. } => EmptyOctet { offset, octet },
+            OctetTooLong { octet, .. } => OctetTooLong { offset, octet },
+            LeadingZero { octet, .. } => LeadingZero { offset, octet },
+            InvalidOctalDigit { octet, .. } => InvalidOctalDigit { offset, octet },
+            OctetOutOfRange { octet, .. } => OctetOutOfRange { offset, octet },
+            TooManyOctets { octet, .. } => TooManyOctets { offset, octet },
+            TooFewOctets { octet, .. } => TooFewOctets { offset, octet },
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidAddrErr::InvalidChar { .. } => "invalid character",
+            InvalidAddrErr::EmptyOctet { .. } => "empty octet",
+            InvalidAddrErr::OctetTooLong { .. } => "octet has more than three digits",
+            InvalidAddrErr::LeadingZero { .. } => "octet has a leading zero",
+            InvalidAddrErr::InvalidOctalDigit { .. } => "invalid octal digit",
+            InvalidAddrErr::OctetOutOfRange { .. } => "octet out of range",
+            InvalidAddrErr::TooManyOctets { .. } => "too many octets",
+            InvalidAddrErr::TooFewOctets { .. } => "too few octets",
+        }
+    }
+
+    // diagnostic returns a rustc-style rendering of the error against the
+    // input it was produced from, with a caret under the offending part:
+    //
+    //   error: invalid ipv4 address: octet out of range
+    //    --> input:1:4
+    //     |
+    //   1 | 10.256.0.1
+    //     |    ^^^ octet 1 must be between 0 and 255
+    pub fn diagnostic<'a>(&self, input: &'a str) -> Diagnostic<'a> {
+        Diagnostic { err: *self, input }
+    }
+
+    // caret_width returns how many characters of input a diagnostic
+    // underlines. Range, length and leading zero errors underline the whole
+    // run of digits of the octet, everything else gets a single caret.
+    pub(crate) fn caret_width(&self, input: &str) -> usize {
+        match self {
+            InvalidAddrErr::OctetTooLong { .. }
+            | InvalidAddrErr::OctetOutOfRange { .. }
+            | InvalidAddrErr::LeadingZero { .. } => input
+                .get(self.offset()..)
+                .unwrap_or("")
+                .bytes()
+                .take_while(|b| b.is_ascii_digit())
+                .count()
+                .max(1),
+            _ => 1,
+        }
+    }
+
+    // write_label writes the message printed next to the caret in a diagnostic.
+    pub(crate) fn write_label(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidAddrErr::InvalidChar { .. } => write!(f, "expected a digit or '.'"),
+            InvalidAddrErr::EmptyOctet { octet, .. } => write!(f, "octet {} has no digits", octet),
+            InvalidAddrErr::OctetTooLong { octet, .. } => {
+                write!(f, "octet {} must be at most three digits", octet)
+            }
+            InvalidAddrErr::OctetOutOfRange { octet, .. } => {
+                write!(f, "octet {} must be between 0 and 255", octet)
+            }
+            InvalidAddrErr::LeadingZero { octet, .. } => {
+                write!(f, "octet {} must not start with a zero", octet)
+            }
+            InvalidAddrErr::InvalidOctalDigit { octet, .. } => {
+                write!(f, "octet {} is octal and only allows digits 0-7", octet)
+            }
+            InvalidAddrErr::TooManyOctets { .. } => write!(f, "expected the end of the address"),
+            InvalidAddrErr::TooFewOctets { octet, .. } => {
+                write!(f, "expected 4 octets, found {}", octet + 1)
+            }
+        }
+    }
+}
+
+impl fmt::Display for InvalidAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid ipv4 address string: {} at byte {} (octet {})",
+            self.reason(),
+            self.offset(),
+            self.octet()
+        )
+    }
+}
+
+impl core::error::Error for InvalidAddrErr {}
+
+// Diagnostic renders an InvalidAddrErr against its input. See
+// InvalidAddrErr::diagnostic.
+pub struct Diagnostic<'a> {
+    err: InvalidAddrErr,
+    input: &'a str,
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.err.caret_width(self.input);
+        crate::diagnostic::write_snippet(
+            f,
+            "ipv4 address",
+            self.err.reason(),
+            self.input,
+            self.err.offset(),
+            width,
+        )?;
+        self.err.write_label(f)
+    }
+}
+
+// Addr is an owned IPv4 address. It is stored as four octets in network
+// (big-endian) order, so the derived ordering sorts addresses numerically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Addr([u8; 4]);
+
+impl Addr {
+    // UNSPECIFIED is the address 0.0.0.0.
+    pub const UNSPECIFIED: Addr = Addr([0, 0, 0, 0]);
+    // LOCALHOST is the loopback address 127.0.0.1.
+    pub const LOCALHOST: Addr = Addr([127, 0, 0, 1]);
+    // BROADCAST is the limited broadcast address 255.255.255.255.
+    pub const BROADCAST: Addr = Addr([255, 255, 255, 255]);
+
+    // new builds an address from its four octets, e.g. Addr::new(10, 0, 0, 1).
+    pub const fn new(a: u8, b: u8, c: u8, d: u8) -> Addr {
+        Addr([a, b, c, d])
+    }
+
+    // octets returns the four octets of the address in network order.
+    pub const fn octets(&self) -> [u8; 4] {
+        self.0
+    }
+
+    // from_bits builds an address from its big-endian u32 representation.
+    pub const fn from_bits(bits: u32) -> Addr {
+        Addr(bits.to_be_bytes())
+    }
+
+    // to_bits returns the address as a big-endian u32.
+    pub const fn to_bits(&self) -> u32 {
+        u32::from_be_bytes(self.0)
+    }
+
+    // to_ipv6_mapped returns the IPv4-mapped IPv6 address of the address,
+    // ::ffff:a.b.c.d. See ipv6::Addr::from_ipv4_mapped.
+    pub const fn to_ipv6_mapped(&self) -> crate::ipv6::Addr {
+        crate::ipv6::Addr::from_ipv4_mapped(*self)
+    }
+
+    // to_ipv6_compatible returns the deprecated IPv4-compatible IPv6 address
+    // of the address, ::a.b.c.d. See ipv6::Addr::from_ipv4_compatible.
+    pub const fn to_ipv6_compatible(&self) -> crate::ipv6::Addr {
+        crate::ipv6::Addr::from_ipv4_compatible(*self)
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d] = self.0;
+        write!(f, "{}.{}.{}.{}", a, b, c, d)
+    }
+}
+
+impl FromStr for Addr {
+    type Err = InvalidAddrErr;
+
+    fn from_str(s: &str) -> Result<Addr> {
+        parse(s)
+    }
+}
+
+impl From<[u8; 4]> for Addr {
+    fn from(octets: [u8; 4]) -> Addr {
+        Addr(octets)
+    }
+}
+
+impl From<Addr> for [u8; 4] {
+    fn from(addr: Addr) -> [u8; 4] {
+        addr.0
+    }
+}
+
+impl From<u32> for Addr {
+    fn from(bits: u32) -> Addr {
+        Addr::from_bits(bits)
+    }
+}
+
+impl From<Addr> for u32 {
+    fn from(addr: Addr) -> u32 {
+        addr.to_bits()
+    }
+}
+
+impl From<Ipv4Addr> for Addr {
+    fn from(addr: Ipv4Addr) -> Addr {
+        Addr(addr.octets())
+    }
+}
+
+impl From<Addr> for Ipv4Addr {
+    fn from(addr: Addr) -> Ipv4Addr {
+        Ipv4Addr::from(addr.0)
+    }
+}
+
+// LeadingZeros decides what the scanner does with an octet that starts with
+// a zero and has more digits after it, like the "010" in "010.1.1.1".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeadingZeros {
+    // Reject fails the parse with InvalidAddrErr::LeadingZero. This is what
+    // RFC 3986 and std::net do, and it is the only safe choice when the
+    // address is later handed to something that might read it as octal
+    // (CVE-2021-29921).
+    Reject,
+    // Decimal ignores the zeros, so "010" is ten. Octets are still limited to
+    // three digits.
+    Decimal,
+    // Octal reads the octet in base 8 like inet_aton(3) does, so "010" is
+    // eight. Any number of leading zeros is allowed.
+    Octal,
+}
+
+// Backend picks the code that parses a dotted quad. Every backend gives
+// exactly the same result as Backend::Scalar for every input and every
+// ParseOptions: the fast paths only take on four plain decimal octets and
+// leave anything else, including every error, to the scalar scanner. The
+// choice only matters for speed, and for comparing the backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    // Scalar is the byte-at-a-time scanner.
+    Scalar,
+    // Swar checks and converts the octets eight bytes at a time in u64
+    // words.
+    Swar,
+    // Simd uses SSE4.1 or AVX2 when the CPU has them, and Swar otherwise.
+    Simd,
+}
+
+// ParseOptions controls how lenient the scanner is. Options are built from
+// one of the named profiles and can then be adjusted one setting at a time:
+//
+//   let opts = ParseOptions::strict().leading_zeros(LeadingZeros::Decimal);
+//   let addr = opts.parse("010.0.0.1")?;
+//
+// Every parsing entry point in the crate either takes a ParseOptions or
+// uses ParseOptions::default(), which is ParseOptions::std_compat().
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    leading_zeros: LeadingZeros,
+    host_bits: HostBits,
+    backend: Backend,
+}
+
+impl ParseOptions {
+    // strict accepts only the RFC 791 dotted-quad form as restricted by the
+    // dec-octet rule of RFC 3986: no leading zeros. Prefixes must not have
+    // host bits set. std::net follows the same rule, so strict is another
+    // name for std_compat and compares equal to it.
+    pub const fn strict() -> ParseOptions {
+        ParseOptions::std_compat()
+    }
+
+    // std_compat accepts exactly what std::net::Ipv4Addr::from_str accepts.
+    // std has no prefix type, so prefixes are treated as in strict.
+    pub const fn std_compat() -> ParseOptions {
+        ParseOptions {
+            leading_zeros: LeadingZeros::Reject,
+            host_bits: HostBits::Reject,
+            backend: Backend::Simd,
+        }
+    }
+
+    // permissive reads leading zeros as decimal. This is how the scanner
+    // behaved before ParseOptions existed. Host bits in prefixes are masked.
+    pub const fn permissive() -> ParseOptions {
+        ParseOptions {
+            leading_zeros: LeadingZeros::Decimal,
+            host_bits: HostBits::Mask,
+            backend: Backend::Simd,
+        }
+    }
+
+    // inet_aton reads leading zeros as octal, the way inet_aton(3) and most
+    // libc resolvers do. It still requires a dotted quad of decimal or octal
+    // octets; use parse_whatwg for the hex and 1 to 3 part forms. Host bits
+    // in prefixes are masked.
+    pub const fn inet_aton() -> ParseOptions {
+        ParseOptions {
+            leading_zeros: LeadingZeros::Octal,
+            host_bits: HostBits::Mask,
+            backend: Backend::Simd,
+        }
+    }
+
+    // leading_zeros sets how octets with leading zeros are handled.
+    pub const fn leading_zeros(mut self, leading_zeros: LeadingZeros) -> ParseOptions {
+        self.leading_zeros = leading_zeros;
+        self
+    }
+
+    // get_leading_zeros returns how octets with leading zeros are handled.
+    pub const fn get_leading_zeros(&self) -> LeadingZeros {
+        self.leading_zeros
+    }
+
+    // host_bits sets how prefixes with host bits set are handled.
+    pub const fn host_bits(mut self, host_bits: HostBits) -> ParseOptions {
+        self.host_bits = host_bits;
+        self
+    }
+
+    // get_host_bits returns how prefixes with host bits set are handled.
+    pub const fn get_host_bits(&self) -> HostBits {
+        self.host_bits
+    }
+
+    // backend sets the code used to parse dotted quads. All the profiles use
+    // Backend::Simd.
+    pub const fn backend(mut self, backend: Backend) -> ParseOptions {
+        self.backend = backend;
+        self
+    }
+
+    // get_backend returns the code used to parse dotted quads.
+    pub const fn get_backend(&self) -> Backend {
+        self.backend
+    }
+
+    // parse will parse a string into an Addr using these options.
+    pub fn parse(&self, ipstr: &str) -> Result<Addr> {
+        self.parse_ascii(ipstr.as_bytes())
+    }
+
+    // parse_ascii will parse a byte slice into an Addr using these options.
+    // See the parse_ascii function.
+    pub fn parse_ascii(&self, input: &[u8]) -> Result<Addr> {
+        let fast = match self.backend {
+            Backend::Scalar => None,
+            Backend::Swar => swar::parse(input),
+            Backend::Simd => simd::parse(input),
+        };
+        if let Some(octets) = fast {
+            return Ok(Addr(octets));
+        }
+        scan(input, self, false).map(|(octets, _)| Addr(octets))
+    }
+
+    // parse_ascii_partial will parse an address at the start of a byte
+    // slice using these options. See the parse_ascii_partial function.
+    pub fn parse_ascii_partial(&self, input: &[u8]) -> Result<(Addr, usize)> {
+        scan(input, self, true).map(|(octets, end)| (Addr(octets), end))
+    }
+
+    // parse_const will parse a string into an Addr using these options, in
+    // a const context. It gives the same result as parse but always uses the
+    // scalar scanner. See the parse_const function.
+    pub const fn parse_const(&self, ipstr: &str) -> Result<Addr> {
+        match scan(ipstr.as_bytes(), self, false) {
+            Ok((octets, _)) => Ok(Addr(octets)),
+            Err(err) => Err(err),
+        }
+    }
+
+    // parse_defanged will parse a defanged address, as in threat-intel
+    // reports, into an Addr using these options. See the parse_defanged
+    // function.
+    #[cfg(feature = "alloc")]
+    pub fn parse_defanged(&self, s: &str) -> Result<Addr> {
+        defang::scan_defanged(s, self)
+    }
+
+    // parse_prefix will parse a string in CIDR notation into a Prefix using
+    // these options.
+    pub fn parse_prefix(&self, s: &str) -> core::result::Result<Prefix, InvalidPrefixErr> {
+        prefix::scan_prefix(s.as_bytes(), self)
+    }
+
+    // parse_prefix_const will parse a string in CIDR notation into a Prefix
+    // using these options, in a const context.
+    pub const fn parse_prefix_const(
+        &self,
+        s: &str,
+    ) -> core::result::Result<Prefix, InvalidPrefixErr> {
+        prefix::scan_prefix(s.as_bytes(), self)
+    }
+
+    // parse_range will parse a range of addresses into an AddrRange using
+    // these options. See the parse_range function.
+    pub fn parse_range(&self, s: &str) -> core::result::Result<AddrRange, InvalidRangeErr> {
+        range::scan_range(s, self)
+    }
+
+    // parse_netmask will parse a dotted netmask into a Netmask using these
+    // options.
+    pub fn parse_netmask(&self, s: &str) -> core::result::Result<Netmask, InvalidPrefixErr> {
+        mask::scan_netmask(s, 0, self)
+    }
+
+    // parse_netmask_prefix will parse an address and dotted netmask into a
+    // Prefix using these options. See the parse_netmask_prefix function.
+    pub fn parse_netmask_prefix(&self, s: &str) -> core::result::Result<Prefix, InvalidPrefixErr> {
+        mask::scan_netmask_prefix(s, self)
+    }
+
+    // parse_wildcard will parse an address and wildcard mask into a Wildcard
+    // using these options. See the parse_wildcard function.
+    pub fn parse_wildcard(&self, s: &str) -> core::result::Result<Wildcard, InvalidPrefixErr> {
+        mask::scan_wildcard(s, self)
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions::std_compat()
+    }
+}
+
+// parse will parse a string into an Addr using the same single-pass scanner
+// as valid_ipv4, so validating an address also yields its value. It uses the
+// default ParseOptions; see ParseOptions::parse to pick a different profile.
+pub fn parse(ipstr: &str) -> Result<Addr> {
+    ParseOptions::default().parse(ipstr)
+}
+
+// parse_const will parse a string into an Addr in a const context, using
+// ParseOptions::std_compat, the default. It is what the ipv4! macro expands
+// to, and can be used directly where a Result is wanted instead of a
+// compile error:
+//
+//   const GATEWAY: Addr = match parse_const("192.0.2.1") {
+//       Ok(addr) => addr,
+//       Err(_) => panic!("bad gateway address"),
+//   };
+pub const fn parse_const(ipstr: &str) -> Result<Addr> {
+    ParseOptions::std_compat().parse_const(ipstr)
+}
+
+// valid_ipv4 will parse a string and return a Result indicating if
+// the string is a valid RFC 791 IPv4 address. If the address is valid
+// the bool will be true. If it is not valid, an Err will be returned.
+pub fn valid_ipv4(ipstr: &str) -> Result<bool> {
+    ParseOptions::default()
+        .parse_ascii(ipstr.as_bytes())
+        .map(|_| true)
+}
+
+// parse_ascii will parse a byte slice into an Addr, like parse does for a
+// string. An address is all ASCII, so bytes read straight from a socket or
+// a log buffer can be parsed without checking that they are UTF-8 first;
+// any byte that is not ASCII is an InvalidChar like any other.
+pub fn parse_ascii(input: &[u8]) -> Result<Addr> {
+    ParseOptions::default().parse_ascii(input)
+}
+
+// parse_ascii_partial will parse the address at the start of input and
+// return it with the number of bytes it took up, leaving the rest of the
+// input alone. The address ends at the first byte that is neither an ASCII
+// digit nor a dot, or at a dot after the fourth octet, so
+//
+//   parse_ascii_partial(b"10.0.0.1:8080") == Ok((Addr::new(10, 0, 0, 1), 8))
+//   parse_ascii_partial(b"10.0.0.1.")     == Ok((Addr::new(10, 0, 0, 1), 8))
+//
+// What may follow the address is up to the caller. Everything before the
+// end must still be a valid address: "10.0.1 " is TooFewOctets at byte 6.
+pub fn parse_ascii_partial(input: &[u8]) -> Result<(Addr, usize)> {
+    ParseOptions::default().parse_ascii_partial(input)
+}
+
+// scan is the single-pass scanner behind all the parse functions. It returns
+// the four octets of the address and the offset at which it ends if the
+// input is valid under options. If partial is false the address has to take
+// up the whole input; if it is true the address ends as parse_ascii_partial
+// describes. It is a const fn, like everything it calls, so that addresses
+// can also be parsed at compile time.
+pub(crate) const fn scan(
+    input: &[u8],
+    options: &ParseOptions,
+    partial: bool,
+) -> Result<([u8; 4], usize)> {
+    // This algorithm runs in O(N) time where N is the number of bytes in input. We are
+    // looking for exactly 4 "blocks", where a block is a run of 1 to 3 digits delineated on
+    // at least one end by a separator character, the "dot" (.). We will iterate through the
+    // bytes in the input and check each one as it comes, ensuring that this byte
+    // does not invalidate the address string. A block is only turned into an octet once it is
+    // complete, i.e. when we see the dot after it or reach the end of the string, and both of
+    // those go through finish_octet so that every octet -- including the last one -- is
+    // validated the same way.
+    //
+    // There is no up-front length check: the per-block digit limit and the block count
+    // already bound the length of a valid address, and scanning lets us report the actual
+    // reason and position of a failure instead of just "too long" or "too short".
+    let mut octets = [0u8; 4];
+
+    // octet is the index of the block currently being read, digits is the number of digits
+    // seen in it so far and value is its running value in radix, which is 10 unless the block
+    // turns out to be octal. start is the byte offset of the first character of the block,
+    // which is where errors about the block as a whole point to.
+    let mut octet = 0;
+    let mut digits = 0;
+    let mut value: u16 = 0;
+    let mut radix = 10;
+    let mut start = 0;
+
+    // end is where the address stops, which is the end of the input unless a partial scan
+    // finds an earlier byte that cannot be part of it.
+    let mut end = input.len();
+
+    // iterate byte by byte through the input. If any invalidations are found, return
+    // immediately. A character that is not ASCII starts with a byte that is not a digit or a
+    // dot, so it is reported at its first byte just as a str scanner would.
+    let mut offset = 0;
+    while offset < input.len() {
+        let b = input[offset];
+        match b {
+            b'0'..=b'9' => {
+                let digit = (b - b'0') as u16;
+
+                // if the block so far is a single zero, this digit makes it a leading zero,
+                // and the options decide what that means.
+                if digits == 1 && value == 0 {
+                    match options.leading_zeros {
+                        LeadingZeros::Reject => {
+                            return Err(InvalidAddrErr::LeadingZero {
+                                offset: start,
+                                octet,
+                            });
+                        }
+                        LeadingZeros::Decimal => {}
+                        LeadingZeros::Octal => radix = 8,
+                    }
+                }
+
+                if radix == 8 {
+                    if digit > 7 {
+                        return Err(InvalidAddrErr::InvalidOctalDigit { offset, octet });
+                    }
+
+                    // octal blocks have no digit limit because of the leading zeros, so the
+                    // range is checked as we go to keep value from overflowing.
+                    value = value * 8 + digit;
+                    if value > 255 {
+                        return Err(InvalidAddrErr::OctetOutOfRange {
+                            offset: start,
+                            octet,
+                        });
+                    }
+                } else {
+                    // a decimal block can have at most three digits. Three digits can never
+                    // overflow value, so the range check can wait until the block is finished.
+                    if digits == 3 {
+                        return Err(InvalidAddrErr::OctetTooLong {
+                            offset: start,
+                            octet,
+                        });
+                    }
+
+                    value = value * 10 + digit;
+                }
+                digits += 1;
+            }
+            // dots ('.') represent a seperator character in the address string. The block
+            // before the dot is finished, and if there is room for another one we start it.
+            b'.' => {
+                // a partial scan ends at a dot after the fourth block instead.
+                if partial && octet == 3 {
+                    end = offset;
+                    break;
+                }
+
+                octets[octet] = match finish_octet(value, digits, start, offset, octet) {
+                    Ok(value) => value,
+                    Err(err) => return Err(err),
+                };
+
+                // if we have a dot and we already have seen 4 blocks, the address is invalid.
+                if octet == 3 {
+                    return Err(InvalidAddrErr::TooManyOctets { offset, octet });
+                }
+
+                octet += 1;
+                digits = 0;
+                value = 0;
+                radix = 10;
+                start = offset + 1;
+            }
+            // if the byte is not a digit or a dot, a partial scan has found the end of the
+            // address and a full scan has found an invalid address.
+            _ if partial => {
+                end = offset;
+                break;
+            }
+            _ => return Err(InvalidAddrErr::InvalidChar { offset, octet }),
+        }
+        offset += 1;
+    }
+
+    // the final block is not followed by a dot, so it is finished here. After that we must
+    // have seen exactly four blocks.
+    octets[octet] = match finish_octet(value, digits, start, end, octet) {
+        Ok(value) => value,
+        Err(err) => return Err(err),
+    };
+    if octet != 3 {
+        return Err(InvalidAddrErr::TooFewOctets { offset: end, octet });
+    }
+
+    Ok((octets, end))
+}
+
+// finish_octet validates a completed block and returns its value as an octet. offset is
+// where the block ended, i.e. the offset of the dot after it or the end of the string.
+const fn finish_octet(
+    value: u16,
+    digits: usize,
+    start: usize,
+    offset: usize,
+    octet: usize,
+) -> Result<u8> {
+    // a block without any digits means we saw two dots in a row, a leading or trailing dot,
+    // or an empty string.
+    if digits == 0 {
+        return Err(InvalidAddrErr::EmptyOctet { offset, octet });
+    }
+
+    if value > 255 {
+        return Err(InvalidAddrErr::OctetOutOfRange {
+            offset: start,
+            octet,
+        });
+    }
+
+    Ok(value as u8)
+}
+
+#[cfg(test)]
+mod net_tests {
+    use super::{
+        Addr, Backend, HostBits, InvalidAddrErr, LeadingZeros, ParseOptions, parse, parse_ascii,
+        parse_ascii_partial, valid_ipv4,
+    };
+    use crate::rng::Rng;
+    use std::net::Ipv4Addr;
+
+    // CONFORMANCE is the table of RFC 791 dotted-quad cases the scanner must
+    // agree with, and the exact error it must report for each invalid input.
+    const CONFORMANCE: &[(&str, Result<[u8; 4], InvalidAddrErr>)] = &[
+        ("0.0.0.0", Ok([0, 0, 0, 0])),
+        ("1.1.1.1", Ok([1, 1, 1, 1])),
+        ("127.0.0.1", Ok([127, 0, 0, 1])),
+        ("255.255.255.255", Ok([255, 255, 255, 255])),
+        ("199.200.249.250", Ok([199, 200, 249, 250])),
+        ("100.100.100.101", Ok([100, 100, 100, 101])),
+        // every octet is range checked, including the last one.
+        (
+            "256.1.1.1",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            "1.256.1.1",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 2,
+                octet: 1,
+            }),
+        ),
+        (
+            "1.1.256.1",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 4,
+                octet: 2,
+            }),
+        ),
+        (
+            "1.1.1.256",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        (
+            "1.1.1.999",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        (
+            "1.1.1.260",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        (
+            "1.1.1.300",
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        // exactly four blocks are required.
+        (
+            "100.100.100",
+            Err(InvalidAddrErr::TooFewOctets {
+                offset: 11,
+                octet: 2,
+            }),
+        ),
+        (
+            "1.1",
+            Err(InvalidAddrErr::TooFewOctets {
+                offset: 3,
+                octet: 1,
+            }),
+        ),
+        (
+            "1",
+            Err(InvalidAddrErr::TooFewOctets {
+                offset: 1,
+                octet: 0,
+            }),
+        ),
+        (
+            "1.1.1.1.1",
+            Err(InvalidAddrErr::TooManyOctets {
+                offset: 7,
+                octet: 3,
+            }),
+        ),
+        (
+            "1.1.1.1.",
+            Err(InvalidAddrErr::TooManyOctets {
+                offset: 7,
+                octet: 3,
+            }),
+        ),
+        // empty blocks anywhere.
+        (
+            "",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            ".",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            "...",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            ".1.1.1",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            "1..1.1",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 2,
+                octet: 1,
+            }),
+        ),
+        (
+            "1.1.1.",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        (
+            "1.1.1..",
+            Err(InvalidAddrErr::EmptyOctet {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        // at most three digits per block.
+        (
+            "1000.1.1.1",
+            Err(InvalidAddrErr::OctetTooLong {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            "1.1.1.1000",
+            Err(InvalidAddrErr::OctetTooLong {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        // no leading zeros.
+        (
+            "1.1.1.0000",
+            Err(InvalidAddrErr::LeadingZero {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        (
+            "01.1.1.1",
+            Err(InvalidAddrErr::LeadingZero {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            "1.1.1.00",
+            Err(InvalidAddrErr::LeadingZero {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+        // only ASCII digits and dots.
+        (
+            "1.1.1.1 ",
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 7,
+                octet: 3,
+            }),
+        ),
+        (
+            "+1.1.1.1",
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 0,
+                octet: 0,
+            }),
+        ),
+        (
+            "1.-1.1.1",
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 2,
+                octet: 1,
+            }),
+        ),
+        (
+            "0x1.1.1.1",
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 1,
+                octet: 0,
+            }),
+        ),
+        (
+            "1.1.1.1/8",
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 7,
+                octet: 3,
+            }),
+        ),
+        (
+            "1.1.1.\u{0661}",
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 6,
+                octet: 3,
+            }),
+        ),
+    ];
+
+    // check_against_std parses s with the scanner under each profile and with
+    // std::net and panics if they disagree. The default, strict and std_compat
+    // profiles must match std exactly. The permissive profile may also accept
+    // octets with leading zeros, which it reads as decimal: in that case its
+    // value must match std's parse of the same address with the zeros stripped.
+    fn check_against_std(s: &str) {
+        let ours = parse(s);
+        assert_eq!(
+            valid_ipv4(s).is_ok(),
+            ours.is_ok(),
+            "valid_ipv4 and parse disagree on {:?}",
+            s
+        );
+        assert_eq!(ParseOptions::strict().parse(s), ours, "strict on {:?}", s);
+        assert_eq!(
+            ParseOptions::std_compat().parse(s),
+            ours,
+            "std_compat on {:?}",
+            s
+        );
+        // every backend agrees with the scalar scanner, errors included.
+        for options in [
+            ParseOptions::strict(),
+            ParseOptions::permissive(),
+            ParseOptions::inet_aton(),
+        ] {
+            let scalar = options.backend(Backend::Scalar).parse(s);
+            for backend in [Backend::Swar, Backend::Simd] {
+                assert_eq!(
+                    options.backend(backend).parse(s),
+                    scalar,
+                    "{:?} with {:?} on {:?}",
+                    backend,
+                    options,
+                    s
+                );
+            }
+        }
+        // a valid address is also the whole of itself as a partial parse.
+        if let Ok(addr) = ours {
+            assert_eq!(
+                parse_ascii_partial(s.as_bytes()),
+                Ok((addr, s.len())),
+                "parse_ascii_partial of {:?}",
+                s
+            );
+        }
+
+        match (ours, s.parse::<Ipv4Addr>()) {
+            (Ok(a), Ok(b)) => assert_eq!(Ipv4Addr::from(a), b, "value of {:?}", s),
+            (Err(_), Err(_)) => {}
+            (Err(e), Ok(b)) => panic!(
+                "correctness error: {:?} failed with {} but std parsed {}",
+                s, e, b
+            ),
+            (Ok(a), Err(_)) => panic!(
+                "correctness error: {:?} parsed as {} but std rejected it",
+                s, a
+            ),
+        }
+
+        match ParseOptions::permissive().parse(s) {
+            Ok(a) if ours.is_err() => {
+                let stripped = s
+                    .split('.')
+                    .map(|block| match block.trim_start_matches('0') {
+                        "" => "0",
+                        rest => rest,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".");
+                assert_ne!(
+                    stripped, s,
+                    "correctness error: {:?} parsed as {} but std rejected it",
+                    s, a
+                );
+                assert_eq!(
+                    stripped.parse::<Ipv4Addr>().map(Addr::from),
+                    Ok(a),
+                    "permissive value of {:?}",
+                    s
+                );
+            }
+            permissive => assert_eq!(permissive.is_ok(), ours.is_ok(), "permissive on {:?}", s),
+        }
+    }
+
+    #[test]
+    fn test_valid_ip() {
+        let valids = Vec::from([
+            "127.0.0.1",
+            "192.168.0.9",
+            "10.0.0.1",
+            "255.255.255.255",
+            "2.255.99.254",
+        ]);
+        let invalids = Vec::from([
+            "295.34.1.5.",
+            "215.0",
+            "215",
+            ".10.256.0.9",
+            "365",
+            "365.1.0.9",
+            "10.256.0.1",
+            "10.358.0.1",
+        ]);
+
+        for addr in valids {
+            let r = valid_ipv4(addr);
+            if r.is_err() {
+                panic!(
+                    "correctness error: {} failed but should have succeeded",
+                    addr
+                );
+            }
+        }
+
+        for addr in invalids {
+            let r = valid_ipv4(addr);
+            if r.is_ok() {
+                panic!(
+                    "correctness error: {} succeeded but should have failed",
+                    addr
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_addr() {
+        let cases = Vec::from([
+            ("127.0.0.1", [127, 0, 0, 1]),
+            ("192.168.0.9", [192, 168, 0, 9]),
+            ("0.0.0.0", [0, 0, 0, 0]),
+            ("255.255.255.255", [255, 255, 255, 255]),
+            ("2.255.99.254", [2, 255, 99, 254]),
+        ]);
+
+        for (s, octets) in cases {
+            let addr = match parse(s) {
+                Ok(addr) => addr,
+                Err(e) => panic!("correctness error: {} failed to parse: {}", s, e),
+            };
+            assert_eq!(addr.octets(), octets, "octets of {}", s);
+            assert_eq!(addr, s.parse::<Addr>().unwrap(), "FromStr of {}", s);
+            assert_eq!(addr.to_string(), s, "round trip of {}", s);
+        }
+    }
+
+    #[test]
+    fn test_addr_conversions() {
+        let addr = Addr::new(10, 1, 2, 3);
+
+        assert_eq!(u32::from(addr), 0x0a010203);
+        assert_eq!(Addr::from(0x0a010203), addr);
+        assert_eq!(Addr::from([10, 1, 2, 3]), addr);
+        assert_eq!(<[u8; 4]>::from(addr), [10, 1, 2, 3]);
+        assert_eq!(Ipv4Addr::from(addr), Ipv4Addr::new(10, 1, 2, 3));
+        assert_eq!(Addr::from(Ipv4Addr::new(10, 1, 2, 3)), addr);
+
+        assert!(Addr::new(9, 255, 255, 255) < Addr::new(10, 0, 0, 0));
+        assert!(Addr::new(10, 0, 0, 0) < Addr::new(10, 0, 0, 1));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = Vec::from([
+            (
+                "10.0.0.x",
+                InvalidAddrErr::InvalidChar {
+                    offset: 7,
+                    octet: 3,
+                },
+            ),
+            (
+                " 10.0.0.1",
+                InvalidAddrErr::InvalidChar {
+                    offset: 0,
+                    octet: 0,
+                },
+            ),
+            (
+                "10..0.1",
+                InvalidAddrErr::EmptyOctet {
+                    offset: 3,
+                    octet: 1,
+                },
+            ),
+            (
+                ".10.0.0.1",
+                InvalidAddrErr::EmptyOctet {
+                    offset: 0,
+                    octet: 0,
+                },
+            ),
+            (
+                "10.0.0.",
+                InvalidAddrErr::EmptyOctet {
+                    offset: 7,
+                    octet: 3,
+                },
+            ),
+            (
+                "",
+                InvalidAddrErr::EmptyOctet {
+                    offset: 0,
+                    octet: 0,
+                },
+            ),
+            (
+                "10.0001.0.1",
+                InvalidAddrErr::LeadingZero {
+                    offset: 3,
+                    octet: 1,
+                },
+            ),
+            (
+                "10.1000.0.1",
+                InvalidAddrErr::OctetTooLong {
+                    offset: 3,
+                    octet: 1,
+                },
+            ),
+            (
+                "10.256.0.1",
+                InvalidAddrErr::OctetOutOfRange {
+                    offset: 3,
+                    octet: 1,
+                },
+            ),
+            (
+                "10.0.0.300",
+                InvalidAddrErr::OctetOutOfRange {
+                    offset: 7,
+                    octet: 3,
+                },
+            ),
+            (
+                "10.0.0.1.",
+                InvalidAddrErr::TooManyOctets {
+                    offset: 8,
+                    octet: 3,
+                },
+            ),
+            (
+                "10.0.1",
+                InvalidAddrErr::TooFewOctets {
+                    offset: 6,
+                    octet: 2,
+                },
+            ),
+        ]);
+
+        for (s, want) in cases {
+            match parse(s) {
+                Ok(addr) => panic!("correctness error: {} parsed as {}", s, addr),
+                Err(e) => assert_eq!(e, want, "error for {:?}", s),
+            }
+        }
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let input = "10.256.0.1";
+        let err = parse(input).unwrap_err();
+        let want = "\
+error: invalid ipv4 address: octet out of range
+ --> input:1:4
+  |
+1 | 10.256.0.1
+  |    ^^^ octet 1 must be between 0 and 255";
+        assert_eq!(err.diagnostic(input).to_string(), want);
+
+        let input = "10.0.é.1";
+        let err = parse(input).unwrap_err();
+        let want = "\
+error: invalid ipv4 address: invalid character
+ --> input:1:6
+  |
+1 | 10.0.é.1
+  |      ^ expected a digit or '.'";
+        assert_eq!(err.diagnostic(input).to_string(), want);
+    }
+
+    #[test]
+    fn test_conformance() {
+        for (s, want) in CONFORMANCE {
+            assert_eq!(parse(s).map(|a| a.octets()), *want, "parse of {:?}", s);
+            check_against_std(s);
+        }
+    }
+
+    #[test]
+    fn test_parse_ascii() {
+        assert_eq!(parse_ascii(b"10.0.0.1"), Ok(Addr::new(10, 0, 0, 1)));
+        assert_eq!(
+            ParseOptions::permissive().parse_ascii(b"010.0.0.1"),
+            Ok(Addr::new(10, 0, 0, 1))
+        );
+
+        // bytes that are not UTF-8 are invalid characters like any other.
+        assert_eq!(
+            parse_ascii(b"10.0.\xff.1"),
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 5,
+                octet: 2,
+            })
+        );
+        assert_eq!(
+            parse_ascii(b"10.0.0.1\x00"),
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 8,
+                octet: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_ascii_partial() {
+        let cases = Vec::from([
+            (&b"10.0.0.1"[..], Ok(([10, 0, 0, 1], 8))),
+            (b"10.0.0.1:8080", Ok(([10, 0, 0, 1], 8))),
+            (b"10.0.0.1.", Ok(([10, 0, 0, 1], 8))),
+            (b"10.0.0.1.5", Ok(([10, 0, 0, 1], 8))),
+            (
+                b"192.168.1.254 - - [10/Oct/2000]",
+                Ok(([192, 168, 1, 254], 13)),
+            ),
+            (b"10.0.0.1\xff\xfe", Ok(([10, 0, 0, 1], 8))),
+            (b"1.2.3.4a", Ok(([1, 2, 3, 4], 7))),
+            (
+                b"10.0.1 ",
+                Err(InvalidAddrErr::TooFewOctets {
+                    offset: 6,
+                    octet: 2,
+                }),
+            ),
+            (
+                b"10.0.0. ",
+                Err(InvalidAddrErr::EmptyOctet {
+                    offset: 7,
+                    octet: 3,
+                }),
+            ),
+            (
+                b"10.0.0.1000",
+                Err(InvalidAddrErr::OctetTooLong {
+                    offset: 7,
+                    octet: 3,
+                }),
+            ),
+            (
+                b"10.0.0.256:80",
+                Err(InvalidAddrErr::OctetOutOfRange {
+                    offset: 7,
+                    octet: 3,
+                }),
+            ),
+            (
+                b"host",
+                Err(InvalidAddrErr::EmptyOctet {
+                    offset: 0,
+                    octet: 0,
+                }),
+            ),
+        ]);
+
+        for (input, want) in cases {
+            let want = want.map(|(octets, len)| (Addr::from(octets), len));
+            assert_eq!(
+                parse_ascii_partial(input),
+                want,
+                "parse_ascii_partial of {:?}",
+                input.escape_ascii().to_string()
+            );
+        }
+
+        // the rest of the input is left for the caller.
+        let input = b"10.0.0.1,10.0.0.2";
+        let (first, len) = parse_ascii_partial(input).unwrap();
+        let (second, _) = parse_ascii_partial(&input[len + 1..]).unwrap();
+        assert_eq!(
+            (first, second),
+            (Addr::new(10, 0, 0, 1), Addr::new(10, 0, 0, 2))
+        );
+    }
+
+    #[test]
+    fn test_std_exhaustive_octets() {
+        // every one, two and three digit block, with and without leading zeros,
+        // in every position of the address.
+        for n in 0..=999 {
+            for block in [format!("{}", n), format!("{:02}", n), format!("{:03}", n)] {
+                for pos in 0..4 {
+                    let mut blocks = ["1", "2", "3", "4"];
+                    blocks[pos] = &block;
+                    check_against_std(&blocks.join("."));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_std_exhaustive_short_strings() {
+        // every string of up to 7 characters over an alphabet of digits that
+        // sit on the range boundaries, the separator and one invalid character.
+        const ALPHABET: &[u8] = b"01259.a";
+        let mut buf = Vec::new();
+        for len in 0..=7u32 {
+            for mut n in 0..ALPHABET.len().pow(len) {
+                buf.clear();
+                for _ in 0..len {
+                    buf.push(ALPHABET[n % ALPHABET.len()]);
+                    n /= ALPHABET.len();
+                }
+                check_against_std(std::str::from_utf8(&buf).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_std_random() {
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+
+        // random strings biased towards digits and dots, so that a good share
+        // of them are close to valid.
+        const ALPHABET: &[u8] = b"0123456789......0125x -";
+        let mut buf = Vec::new();
+        for _ in 0..200_000 {
+            buf.clear();
+            let len = rng.next() % 20;
+            for _ in 0..len {
+                buf.push(ALPHABET[(rng.next() % ALPHABET.len() as u64) as usize]);
+            }
+            check_against_std(std::str::from_utf8(&buf).unwrap());
+        }
+
+        // random addresses must round trip through Display.
+        for _ in 0..200_000 {
+            let addr = Addr::from(rng.next() as u32);
+            let s = addr.to_string();
+            assert_eq!(parse(&s), Ok(addr), "round trip of {}", s);
+            check_against_std(&s);
+        }
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let strict = ParseOptions::strict();
+        let permissive = ParseOptions::permissive();
+        let inet_aton = ParseOptions::inet_aton();
+
+        let cases = Vec::from([
+            // (input, strict, permissive, inet_aton)
+            (
+                "10.1.1.1",
+                Some([10, 1, 1, 1]),
+                Some([10, 1, 1, 1]),
+                Some([10, 1, 1, 1]),
+            ),
+            ("010.1.1.1", None, Some([10, 1, 1, 1]), Some([8, 1, 1, 1])),
+            ("0.00.000.0", None, Some([0, 0, 0, 0]), Some([0, 0, 0, 0])),
+            ("1.1.1.0377", None, None, Some([1, 1, 1, 255])),
+            ("1.1.1.0400", None, None, None),
+            ("1.1.1.00000012", None, None, Some([1, 1, 1, 10])),
+            ("1.1.1.08", None, Some([1, 1, 1, 8]), None),
+            ("1.1.1.019", None, Some([1, 1, 1, 19]), None),
+            ("1.1.1.256", None, None, None),
+        ]);
+
+        for (s, want_strict, want_permissive, want_inet_aton) in cases {
+            assert_eq!(
+                strict.parse(s).ok().map(|a| a.octets()),
+                want_strict,
+                "strict {:?}",
+                s
+            );
+            assert_eq!(
+                permissive.parse(s).ok().map(|a| a.octets()),
+                want_permissive,
+                "permissive {:?}",
+                s
+            );
+            assert_eq!(
+                inet_aton.parse(s).ok().map(|a| a.octets()),
+                want_inet_aton,
+                "inet_aton {:?}",
+                s
+            );
+        }
+
+        assert_eq!(
+            inet_aton.parse("1.1.1.09"),
+            Err(InvalidAddrErr::InvalidOctalDigit {
+                offset: 7,
+                octet: 3
+            })
+        );
+        assert_eq!(
+            inet_aton.parse("1.1.1.0400"),
+            Err(InvalidAddrErr::OctetOutOfRange {
+                offset: 6,
+                octet: 3
+            })
+        );
+
+        let custom = ParseOptions::strict()
+            .leading_zeros(LeadingZeros::Octal)
+            .host_bits(HostBits::Mask);
+        assert_eq!(custom, inet_aton);
+        assert_eq!(custom.get_leading_zeros(), LeadingZeros::Octal);
+        assert_eq!(ParseOptions::default(), ParseOptions::std_compat());
+        assert_eq!(ParseOptions::strict(), ParseOptions::std_compat());
+    }
+}
@@ -0,0 +1,283 @@
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+use super::Addr;
+
+// The arithmetic below treats an address as its u32, the way scanners and
+// allocators walk address space, so that callers do not have to round-trip
+// through to_bits and from_bits. Like the integer methods they are named
+// after, the checked forms return None where the result would leave the
+// address space, the saturating forms stop at 0.0.0.0 and 255.255.255.255,
+// and the wrapping forms wrap around.
+impl Addr {
+    // checked_add returns the address n after self, or None if that is past
+    // 255.255.255.255.
+    pub const fn checked_add(self, n: u32) -> Option<Addr> {
+        match self.to_bits().checked_add(n) {
+            Some(bits) => Some(Addr::from_bits(bits)),
+            None => None,
+        }
+    }
+
+    // checked_sub returns the address n before self, or None if that is
+    // before 0.0.0.0.
+    pub const fn checked_sub(self, n: u32) -> Option<Addr> {
+        match self.to_bits().checked_sub(n) {
+            Some(bits) => Some(Addr::from_bits(bits)),
+            None => None,
+        }
+    }
+
+    // saturating_add returns the address n after self, or 255.255.255.255 if
+    // that is past the end.
+    pub const fn saturating_add(self, n: u32) -> Addr {
+        Addr::from_bits(self.to_bits().saturating_add(n))
+    }
+
+    // saturating_sub returns the address n before self, or 0.0.0.0 if that
+    // is before the start.
+    pub const fn saturating_sub(self, n: u32) -> Addr {
+        Addr::from_bits(self.to_bits().saturating_sub(n))
+    }
+
+    // wrapping_add returns the address n after self, wrapping around from
+    // 255.255.255.255 to 0.0.0.0.
+    pub const fn wrapping_add(self, n: u32) -> Addr {
+        Addr::from_bits(self.to_bits().wrapping_add(n))
+    }
+
+    // wrapping_sub returns the address n before self, wrapping around from
+    // 0.0.0.0 to 255.255.255.255.
+    pub const fn wrapping_sub(self, n: u32) -> Addr {
+        Addr::from_bits(self.to_bits().wrapping_sub(n))
+    }
+
+    // successor returns the next address, or None for 255.255.255.255. With
+    // predecessor it stands in for core::iter::Step, which is unstable.
+    pub const fn successor(self) -> Option<Addr> {
+        self.checked_add(1)
+    }
+
+    // predecessor returns the previous address, or None for 0.0.0.0.
+    pub const fn predecessor(self) -> Option<Addr> {
+        self.checked_sub(1)
+    }
+
+    // distance returns how many addresses apart self and other are, in
+    // either order, so 10.0.0.1 and 10.0.1.0 are 255 apart. It is one less
+    // than the size of the range between them.
+    pub const fn distance(self, other: Addr) -> u32 {
+        self.to_bits().abs_diff(other.to_bits())
+    }
+
+    // leading_zeros returns the number of leading zero bits, which is 32 for
+    // 0.0.0.0.
+    pub const fn leading_zeros(self) -> u32 {
+        self.to_bits().leading_zeros()
+    }
+
+    // trailing_zeros returns the number of trailing zero bits, which is the
+    // length of the longest host part the address could be the network of:
+    // 10.0.0.0 has 25, so it starts a /7. It is 32 for 0.0.0.0.
+    pub const fn trailing_zeros(self) -> u32 {
+        self.to_bits().trailing_zeros()
+    }
+}
+
+// The bitwise operators apply masks to addresses, e.g. addr & netmask.addr()
+// is the network of addr, and addr | !netmask.addr() the last address of
+// it.
+impl BitAnd for Addr {
+    type Output = Addr;
+
+    fn bitand(self, mask: Addr) -> Addr {
+        Addr::from_bits(self.to_bits() & mask.to_bits())
+    }
+}
+
+impl BitOr for Addr {
+    type Output = Addr;
+
+    fn bitor(self, mask: Addr) -> Addr {
+        Addr::from_bits(self.to_bits() | mask.to_bits())
+    }
+}
+
+impl BitXor for Addr {
+    type Output = Addr;
+
+    fn bitxor(self, mask: Addr) -> Addr {
+        Addr::from_bits(self.to_bits() ^ mask.to_bits())
+    }
+}
+
+impl Not for Addr {
+    type Output = Addr;
+
+    fn not(self) -> Addr {
+        Addr::from_bits(!self.to_bits())
+    }
+}
+
+impl BitAndAssign for Addr {
+    fn bitand_assign(&mut self, mask: Addr) {
+        *self = *self & mask;
+    }
+}
+
+impl BitOrAssign for Addr {
+    fn bitor_assign(&mut self, mask: Addr) {
+        *self = *self | mask;
+    }
+}
+
+impl BitXorAssign for Addr {
+    fn bitxor_assign(&mut self, mask: Addr) {
+        *self = *self ^ mask;
+    }
+}
+
+#[cfg(test)]
+mod arith_tests {
+    use crate::ipv4::{Addr, Netmask};
+    use crate::rng::Rng;
+    use crate::testing::ipv4::addr;
+    use crate::testing::matches_bits;
+
+    #[test]
+    fn test_add_sub() {
+        // (addr, n, checked_add, saturating_add, wrapping_add)
+        let cases = Vec::from([
+            ("10.0.0.1", 1, Some("10.0.0.2"), "10.0.0.2", "10.0.0.2"),
+            ("10.0.0.255", 1, Some("10.0.1.0"), "10.0.1.0", "10.0.1.0"),
+            ("0.0.0.0", 0, Some("0.0.0.0"), "0.0.0.0", "0.0.0.0"),
+            (
+                "255.255.255.254",
+                1,
+                Some("255.255.255.255"),
+                "255.255.255.255",
+                "255.255.255.255",
+            ),
+            ("255.255.255.255", 1, None, "255.255.255.255", "0.0.0.0"),
+            ("255.255.255.0", 512, None, "255.255.255.255", "0.0.1.0"),
+        ]);
+
+        for (s, n, checked, saturating, wrapping) in cases {
+            let a = addr(s);
+            assert_eq!(a.checked_add(n), checked.map(addr), "{} checked + {}", s, n);
+            assert_eq!(
+                a.saturating_add(n),
+                addr(saturating),
+                "{} saturating + {}",
+                s,
+                n
+            );
+            assert_eq!(a.wrapping_add(n), addr(wrapping), "{} wrapping + {}", s, n);
+
+            // subtracting undoes adding, and saturates or wraps the same way
+            // from the other end.
+            let back = addr(wrapping);
+            assert_eq!(back.wrapping_sub(n), a, "{} wrapping - {}", wrapping, n);
+            if let Some(c) = checked {
+                assert_eq!(addr(c).checked_sub(n), Some(a), "{} checked - {}", c, n);
+            } else {
+                assert_eq!(back.checked_sub(n), None, "{} checked - {}", wrapping, n);
+                assert_eq!(
+                    back.saturating_sub(n),
+                    Addr::UNSPECIFIED,
+                    "{} saturating - {}",
+                    wrapping,
+                    n
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_successor_predecessor() {
+        let cases = Vec::from([
+            ("0.0.0.0", Some("0.0.0.1"), None),
+            ("10.0.0.255", Some("10.0.1.0"), Some("10.0.0.254")),
+            ("10.0.1.0", Some("10.0.1.1"), Some("10.0.0.255")),
+            ("255.255.255.255", None, Some("255.255.255.254")),
+        ]);
+
+        for (s, succ, pred) in cases {
+            assert_eq!(addr(s).successor(), succ.map(addr), "successor of {}", s);
+            assert_eq!(
+                addr(s).predecessor(),
+                pred.map(addr),
+                "predecessor of {}",
+                s
+            );
+        }
+
+        // walking forward stops at the end of the address space.
+        let mut walk = Vec::new();
+        let mut next = Some(addr("255.255.255.252"));
+        while let Some(a) = next {
+            walk.push(a);
+            next = a.successor();
+        }
+        assert_eq!(walk.len(), 4, "walk from 255.255.255.252");
+    }
+
+    #[test]
+    fn test_distance() {
+        let cases = Vec::from([
+            ("10.0.0.1", "10.0.0.1", 0),
+            ("10.0.0.1", "10.0.1.0", 255),
+            ("10.0.1.0", "10.0.0.1", 255),
+            ("0.0.0.0", "255.255.255.255", u32::MAX),
+        ]);
+
+        for (a, b, want) in cases {
+            assert_eq!(addr(a).distance(addr(b)), want, "{} to {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_zeros() {
+        let cases = Vec::from([
+            ("0.0.0.0", 32, 32),
+            ("255.255.255.255", 0, 0),
+            ("10.0.0.0", 4, 25),
+            ("0.0.1.0", 23, 8),
+            ("0.0.0.1", 31, 0),
+        ]);
+
+        for (s, leading, trailing) in cases {
+            assert_eq!(addr(s).leading_zeros(), leading, "leading zeros of {}", s);
+            assert_eq!(
+                addr(s).trailing_zeros(),
+                trailing,
+                "trailing zeros of {}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_masks() {
+        let a = addr("192.0.2.130");
+        let netmask = Netmask::from_len(25).unwrap().addr();
+
+        assert_eq!(a & netmask, addr("192.0.2.128"), "network");
+        assert_eq!(a | !netmask, addr("192.0.2.255"), "last address");
+        assert_eq!(a ^ addr("0.0.0.3"), addr("192.0.2.129"), "xor");
+        assert_eq!(!Addr::UNSPECIFIED, Addr::BROADCAST, "not");
+
+        let mut b = a;
+        b &= netmask;
+        assert_eq!(b, addr("192.0.2.128"), "&=");
+        b |= addr("0.0.0.1");
+        assert_eq!(b, addr("192.0.2.129"), "|=");
+        b ^= addr("0.0.0.129");
+        assert_eq!(b, addr("192.0.2.0"), "^=");
+    }
+
+    #[test]
+    fn test_matches_bits() {
+        // every operation must agree with the same one on the u32.
+        matches_bits!(Addr, |rng: &mut Rng| rng.next() as u32);
+    }
+}
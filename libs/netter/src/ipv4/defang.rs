@@ -0,0 +1,226 @@
+use core::fmt;
+use core::fmt::Write;
+
+use super::Addr;
+#[cfg(feature = "alloc")]
+use super::{InvalidAddrErr, ParseOptions, Result};
+use crate::defang::Defang;
+#[cfg(feature = "alloc")]
+use crate::defang::{Refanged, Url};
+
+// Defanged renders an Addr the way threat-intel reports write indicators,
+// with every '.' bracketed, e.g. "192[.]0[.]2[.]1". See Addr::defanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Defanged(Addr);
+
+impl fmt::Display for Defanged {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(Defang(f), "{}", self.0)
+    }
+}
+
+impl Addr {
+    // defanged returns the address formatted so that it is not turned into
+    // a link when pasted into a report or a chat. parse_defanged reads it
+    // back.
+    pub fn defanged(&self) -> Defanged {
+        Defanged(*self)
+    }
+}
+
+// parse_defanged will parse an address that has been defanged for a
+// threat-intel report back into the real Addr. Any of these separators may
+// stand in for a '.', in either case:
+//
+//   "192[.]0[.]2[.]1", "192(.)0(.)2(.)1", "192{.}0{.}2{.}1",
+//   "192[dot]0[dot]2[dot]1", "192(dot)0(dot)2(dot)1"
+//
+// and a plain '.' is fine too, so "192.0.2[.]1" is an address. The address
+// may also be the host of a URL with any scheme, defanged or not, as in
+// "hxxp://192[.]0[.]2[.]1:8080/gate.php" or "hxxps[://]192.0.2.1"; the
+// port and path are skipped. A port is skipped without a scheme too, as in
+// "192[.]0[.]2[.]1:443".
+//
+// Errors are reported at their offset in s, so a diagnostic points at the
+// defanged text the caller has. It uses the default ParseOptions; see
+// ParseOptions::parse_defanged to pick a different profile.
+#[cfg(feature = "alloc")]
+pub fn parse_defanged(s: &str) -> Result<Addr> {
+    ParseOptions::default().parse_defanged(s)
+}
+
+// scan_defanged is the scanner behind parse_defanged.
+#[cfg(feature = "alloc")]
+pub(super) fn scan_defanged(s: &str, options: &ParseOptions) -> Result<Addr> {
+    let text = Refanged::new(s.as_bytes());
+    scan_refanged(&text, &text.url(), options)
+}
+
+// scan_refanged parses the host of url in text, which is shared with the
+// crate-level parse_defanged once it has picked the family.
+#[cfg(feature = "alloc")]
+pub(crate) fn scan_refanged(text: &Refanged, url: &Url, options: &ParseOptions) -> Result<Addr> {
+    // an IPv4 host is never in brackets.
+    if let Some(at) = url.bracket {
+        return Err(InvalidAddrErr::InvalidChar {
+            offset: text.offset(at),
+            octet: 0,
+        });
+    }
+
+    let start = url.host.start;
+    let addr = options
+        .parse_ascii(&text.text()[url.host.clone()])
+        .map_err(|err| err.with_offset(text.offset(start + err.offset())))?;
+    if let Some(at) = url.junk {
+        return Err(InvalidAddrErr::InvalidChar {
+            offset: text.offset(at),
+            octet: 3,
+        });
+    }
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod defang_tests {
+    use crate::ipv4::Addr;
+
+    #[test]
+    fn test_defanged() {
+        let cases = Vec::from([
+            (Addr::new(192, 168, 1, 1), "192[.]168[.]1[.]1"),
+            (Addr::new(0, 0, 0, 0), "0[.]0[.]0[.]0"),
+            (Addr::new(255, 255, 255, 255), "255[.]255[.]255[.]255"),
+        ]);
+
+        for (addr, want) in cases {
+            assert_eq!(addr.defanged().to_string(), want, "{}", addr);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_defanged() {
+        use super::parse_defanged;
+        use crate::ipv4::InvalidAddrErr;
+
+        let cases = Vec::from([
+            ("192[.]168[.]1[.]1", Ok(Addr::new(192, 168, 1, 1))),
+            ("10(.)0(.)0(.)1", Ok(Addr::new(10, 0, 0, 1))),
+            ("10{.}0{.}0{.}1", Ok(Addr::new(10, 0, 0, 1))),
+            ("10[dot]0[DOT]0(dot)1", Ok(Addr::new(10, 0, 0, 1))),
+            ("10.0.0[.]1", Ok(Addr::new(10, 0, 0, 1))),
+            ("10.0.0.1", Ok(Addr::new(10, 0, 0, 1))),
+            ("hxxp://1.2.3.4", Ok(Addr::new(1, 2, 3, 4))),
+            ("hXXps://1[.]2[.]3[.]4/", Ok(Addr::new(1, 2, 3, 4))),
+            ("hxxp[://]1.2.3.4:8080/gate.php", Ok(Addr::new(1, 2, 3, 4))),
+            ("fxp[:]//1.2.3.4?q#f", Ok(Addr::new(1, 2, 3, 4))),
+            ("45[.]9[.]148[.]108:443", Ok(Addr::new(45, 9, 148, 108))),
+            ("1[.]2[.]3[.]4:80", Ok(Addr::new(1, 2, 3, 4))),
+            (
+                "1[.]2[.]3[.]4:",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 13,
+                    octet: 3,
+                }),
+            ),
+            (
+                "10[.]0[.]0[.]256",
+                Err(InvalidAddrErr::OctetOutOfRange {
+                    offset: 13,
+                    octet: 3,
+                }),
+            ),
+            (
+                "10[.]0[.]0",
+                Err(InvalidAddrErr::TooFewOctets {
+                    offset: 10,
+                    octet: 2,
+                }),
+            ),
+            (
+                "10[.][.]0[.]1",
+                Err(InvalidAddrErr::EmptyOctet {
+                    offset: 5,
+                    octet: 1,
+                }),
+            ),
+            (
+                "10[.]0[.]0[.]1[.]",
+                Err(InvalidAddrErr::TooManyOctets {
+                    offset: 14,
+                    octet: 3,
+                }),
+            ),
+            (
+                "10[,]0[.]0[.]1",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 2,
+                    octet: 0,
+                }),
+            ),
+            (
+                "hxxp://1.2.3.4:http/",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 15,
+                    octet: 3,
+                }),
+            ),
+            (
+                "hxxp://1.2.3.4 ",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 14,
+                    octet: 3,
+                }),
+            ),
+            (
+                "hxxp://[1.2.3.4]/",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 7,
+                    octet: 0,
+                }),
+            ),
+            (
+                "hxxp:1.2.3.4",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 0,
+                    octet: 0,
+                }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_defanged(s), want, "{}", s);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_defang_round_trip() {
+        use crate::ipv4::ParseOptions;
+        use crate::rng::Rng;
+
+        // every address must come back from its defanged form, under every
+        // profile.
+        let profiles = [
+            ParseOptions::strict(),
+            ParseOptions::permissive(),
+            ParseOptions::inet_aton(),
+        ];
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+
+        for _ in 0..10_000 {
+            let addr = Addr::from_bits(rng.next() as u32);
+            let defanged = addr.defanged().to_string();
+            for options in profiles {
+                assert_eq!(
+                    options.parse_defanged(&defanged),
+                    Ok(addr),
+                    "{} under {:?}",
+                    defanged,
+                    options
+                );
+            }
+        }
+    }
+}
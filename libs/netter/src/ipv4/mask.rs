@@ -0,0 +1,466 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::prefix::{host_bits_offset, mask, octet_offset};
+use super::{Addr, HostBits, InvalidPrefixErr, ParseOptions, Prefix};
+
+// Netmask is a contiguous dotted netmask, e.g. 255.255.0.0. It is the
+// dotted-quad spelling of a prefix length, so it is stored as one and only
+// contiguous masks (a run of ones followed by a run of zeros) can be built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Netmask(u8);
+
+impl Netmask {
+    // from_len returns the netmask for a prefix length, or None if len is
+    // greater than 32.
+    pub const fn from_len(len: u8) -> Option<Netmask> {
+        if len > 32 {
+            return None;
+        }
+        Some(Netmask(len))
+    }
+
+    // from_addr returns the netmask for a dotted mask, or None if the mask is
+    // not contiguous, e.g. 255.0.255.0.
+    pub const fn from_addr(addr: Addr) -> Option<Netmask> {
+        let bits = addr.to_bits();
+        let len = bits.leading_ones();
+        if len < 32 && bits << len != 0 {
+            return None;
+        }
+        Some(Netmask(len as u8))
+    }
+
+    // prefix_len returns the number of one bits in the netmask.
+    pub const fn prefix_len(&self) -> u8 {
+        self.0
+    }
+
+    // addr returns the netmask as a dotted address, e.g. 255.255.0.0.
+    pub const fn addr(&self) -> Addr {
+        Addr::from_bits(mask(self.0))
+    }
+
+    // wildcard returns the inverse of the netmask, which is how ACLs spell a
+    // prefix, e.g. 0.0.255.255 for 255.255.0.0.
+    pub const fn wildcard(&self) -> Addr {
+        Addr::from_bits(!mask(self.0))
+    }
+}
+
+impl fmt::Display for Netmask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.addr())
+    }
+}
+
+impl FromStr for Netmask {
+    type Err = InvalidPrefixErr;
+
+    fn from_str(s: &str) -> Result<Netmask, InvalidPrefixErr> {
+        parse_netmask(s)
+    }
+}
+
+impl From<Netmask> for Addr {
+    fn from(netmask: Netmask) -> Addr {
+        netmask.addr()
+    }
+}
+
+// Wildcard is an address with an ACL wildcard mask, e.g. "10.0.0.0 0.0.255.255".
+// Bits set in the mask are "don't care" bits, so unlike a netmask the mask
+// does not have to be contiguous: "10.0.0.1 0.255.0.0" matches 10.x.0.1 for
+// any x. Bits of the address under the mask are always cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wildcard {
+    addr: Addr,
+    mask: Addr,
+}
+
+impl Wildcard {
+    // ANY matches every address, "any" in an ACL.
+    pub const ANY: Wildcard = Wildcard {
+        addr: Addr::UNSPECIFIED,
+        mask: Addr::BROADCAST,
+    };
+
+    // new builds a wildcard from an address and a wildcard mask, clearing
+    // the bits of addr that the mask ignores.
+    pub const fn new(addr: Addr, mask: Addr) -> Wildcard {
+        Wildcard {
+            addr: Addr::from_bits(addr.to_bits() & !mask.to_bits()),
+            mask,
+        }
+    }
+
+    // addr returns the address with the don't care bits cleared.
+    pub const fn addr(&self) -> Addr {
+        self.addr
+    }
+
+    // mask returns the wildcard mask.
+    pub const fn mask(&self) -> Addr {
+        self.mask
+    }
+
+    // matches returns true if addr is equal to the wildcard's address in
+    // every bit the mask cares about.
+    pub const fn matches(&self, addr: Addr) -> bool {
+        (addr.to_bits() ^ self.addr.to_bits()) & !self.mask.to_bits() == 0
+    }
+
+    // size returns the number of addresses the wildcard matches.
+    pub const fn size(&self) -> u64 {
+        1 << self.mask.to_bits().count_ones()
+    }
+
+    // is_contiguous returns true if the wildcard is the inverse of a netmask,
+    // i.e. it matches exactly the addresses of a prefix.
+    pub const fn is_contiguous(&self) -> bool {
+        Netmask::from_addr(Addr::from_bits(!self.mask.to_bits())).is_some()
+    }
+
+    // to_prefix returns the prefix the wildcard matches, or None if the mask
+    // is not contiguous.
+    pub const fn to_prefix(&self) -> Option<Prefix> {
+        match Netmask::from_addr(Addr::from_bits(!self.mask.to_bits())) {
+            Some(netmask) => Prefix::new(self.addr, netmask.prefix_len()),
+            None => None,
+        }
+    }
+}
+
+impl fmt::Display for Wildcard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.addr, self.mask)
+    }
+}
+
+impl FromStr for Wildcard {
+    type Err = InvalidPrefixErr;
+
+    fn from_str(s: &str) -> Result<Wildcard, InvalidPrefixErr> {
+        parse_wildcard(s)
+    }
+}
+
+impl From<Prefix> for Wildcard {
+    fn from(prefix: Prefix) -> Wildcard {
+        Wildcard {
+            addr: prefix.network(),
+            mask: prefix.hostmask(),
+        }
+    }
+}
+
+impl From<Addr> for Wildcard {
+    // an address on its own matches only itself, "host <addr>" in an ACL.
+    fn from(addr: Addr) -> Wildcard {
+        Wildcard {
+            addr,
+            mask: Addr::UNSPECIFIED,
+        }
+    }
+}
+
+// NetmaskNotation displays a prefix as an address and a dotted netmask, e.g.
+// "10.0.0.0 255.255.0.0". See Prefix::netmask_notation.
+#[derive(Debug, Clone, Copy)]
+pub struct NetmaskNotation(Prefix);
+
+impl fmt::Display for NetmaskNotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0.network(), self.0.netmask())
+    }
+}
+
+impl Prefix {
+    // from_netmask builds a prefix from a network address and a netmask. It
+    // returns None if addr has bits set outside the netmask.
+    pub const fn from_netmask(addr: Addr, netmask: Netmask) -> Option<Prefix> {
+        Prefix::new(addr, netmask.prefix_len())
+    }
+
+    // netmask_notation returns a Display for the prefix in address and
+    // netmask notation, e.g. "10.0.0.0 255.255.0.0".
+    pub const fn netmask_notation(&self) -> NetmaskNotation {
+        NetmaskNotation(*self)
+    }
+
+    // wildcard returns the prefix as an address and wildcard mask, e.g.
+    // "10.0.0.0 0.0.255.255".
+    pub const fn wildcard(&self) -> Wildcard {
+        Wildcard {
+            addr: self.network(),
+            mask: self.hostmask(),
+        }
+    }
+}
+
+// parse_netmask will parse a dotted netmask, e.g. "255.255.0.0", using the
+// default ParseOptions.
+pub fn parse_netmask(s: &str) -> Result<Netmask, InvalidPrefixErr> {
+    ParseOptions::default().parse_netmask(s)
+}
+
+// parse_netmask_prefix will parse a prefix written as an address and a dotted
+// netmask, separated either by whitespace as in router configs
+// ("10.0.0.0 255.255.0.0") or by a slash ("10.0.0.0/255.255.0.0"), using the
+// default ParseOptions.
+pub fn parse_netmask_prefix(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+    ParseOptions::default().parse_netmask_prefix(s)
+}
+
+// parse_wildcard will parse an ACL address and wildcard mask, e.g.
+// "10.0.0.0 0.0.255.255", using the default ParseOptions. The ACL shorthands
+// "any" and "host <addr>" are accepted too.
+pub fn parse_wildcard(s: &str) -> Result<Wildcard, InvalidPrefixErr> {
+    ParseOptions::default().parse_wildcard(s)
+}
+
+// scan_netmask is the parser behind ParseOptions::parse_netmask. start is
+// the offset of s in the original input, for error reporting.
+pub(super) fn scan_netmask(
+    s: &str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Netmask, InvalidPrefixErr> {
+    let addr = options.parse(s).map_err(|err| err.shifted(start))?;
+    Netmask::from_addr(addr).ok_or_else(|| InvalidPrefixErr::NonContiguousMask {
+        offset: start + octet_offset(s.as_bytes(), non_contiguous_octet(addr)),
+    })
+}
+
+// scan_netmask_prefix is the parser behind ParseOptions::parse_netmask_prefix.
+pub(super) fn scan_netmask_prefix(
+    s: &str,
+    options: &ParseOptions,
+) -> Result<Prefix, InvalidPrefixErr> {
+    let (addr_str, mask_str, mask_start) = split_pair(s)?;
+    let addr = options.parse(addr_str)?;
+    let netmask = scan_netmask(mask_str, mask_start, options)?;
+    let len = netmask.prefix_len();
+
+    match options.get_host_bits() {
+        HostBits::Mask => Ok(Prefix::new_masked(addr, len).unwrap()),
+        HostBits::Reject => Prefix::new(addr, len).ok_or_else(|| InvalidPrefixErr::HostBitsSet {
+            offset: host_bits_offset(addr_str.as_bytes(), addr, len),
+        }),
+    }
+}
+
+// scan_wildcard is the parser behind ParseOptions::parse_wildcard.
+pub(super) fn scan_wildcard(s: &str, options: &ParseOptions) -> Result<Wildcard, InvalidPrefixErr> {
+    if s == "any" {
+        return Ok(Wildcard::ANY);
+    }
+    if let Some(host) = s.strip_prefix("host ") {
+        let start = s.len() - host.trim_start().len();
+        let addr = options
+            .parse(host.trim_start())
+            .map_err(|err| err.shifted(start))?;
+        return Ok(Wildcard::from(addr));
+    }
+
+    let (addr_str, mask_str, mask_start) = split_pair(s)?;
+    let addr = options.parse(addr_str)?;
+    let mask = options
+        .parse(mask_str)
+        .map_err(|err| err.shifted(mask_start))?;
+    Ok(Wildcard::new(addr, mask))
+}
+
+// split_pair splits "<addr> <mask>" or "<addr>/<mask>" into the address, the
+// mask and the offset of the mask in s. Any amount of whitespace may
+// separate the two in the first form.
+fn split_pair(s: &str) -> Result<(&str, &str, usize), InvalidPrefixErr> {
+    let end = s
+        .find(|c: char| c == '/' || c.is_ascii_whitespace())
+        .ok_or(InvalidPrefixErr::MissingMask { offset: s.len() })?;
+
+    let rest = if s.as_bytes()[end] == b'/' {
+        &s[end + 1..]
+    } else {
+        s[end..].trim_start()
+    };
+    let start = s.len() - rest.len();
+    if rest.is_empty() {
+        return Err(InvalidPrefixErr::MissingMask { offset: start });
+    }
+
+    Ok((&s[..end], rest, start))
+}
+
+// non_contiguous_octet returns the index of the first octet of a mask that
+// has a one bit after a zero bit.
+const fn non_contiguous_octet(addr: Addr) -> u32 {
+    let bits = addr.to_bits();
+    let ones = bits.leading_ones();
+    match bits.checked_shl(ones) {
+        Some(rest) => (ones + rest.leading_zeros()) / 8,
+        None => 4,
+    }
+}
+
+#[cfg(test)]
+mod mask_tests {
+    use super::{Netmask, Wildcard, parse_netmask, parse_netmask_prefix, parse_wildcard};
+    use crate::ipv4::{InvalidAddrErr, InvalidPrefixErr, ParseOptions};
+    use crate::testing::ipv4::{addr, prefix};
+
+    #[test]
+    fn test_netmask() {
+        for len in 0..=32 {
+            let netmask = Netmask::from_len(len).unwrap();
+            let s = netmask.to_string();
+            assert_eq!(parse_netmask(&s), Ok(netmask), "round trip of {}", s);
+            assert_eq!(netmask.prefix_len(), len);
+            assert_eq!(Netmask::from_addr(netmask.addr()), Some(netmask));
+            assert_eq!(
+                netmask.wildcard().to_bits(),
+                !netmask.addr().to_bits(),
+                "wildcard of {}",
+                s
+            );
+        }
+        assert_eq!(Netmask::from_len(33), None);
+        assert_eq!(parse_netmask("255.255.0.0").unwrap().prefix_len(), 16);
+        assert_eq!(parse_netmask("255.255.255.128").unwrap().prefix_len(), 25);
+
+        let invalids = Vec::from([
+            (
+                "255.0.255.0",
+                InvalidPrefixErr::NonContiguousMask { offset: 6 },
+            ),
+            ("0.0.0.1", InvalidPrefixErr::NonContiguousMask { offset: 6 }),
+            (
+                "255.255.253.0",
+                InvalidPrefixErr::NonContiguousMask { offset: 8 },
+            ),
+            (
+                "255.255.0",
+                InvalidPrefixErr::Addr(InvalidAddrErr::TooFewOctets {
+                    offset: 9,
+                    octet: 2,
+                }),
+            ),
+        ]);
+        for (s, want) in invalids {
+            assert_eq!(parse_netmask(s), Err(want), "parse_netmask({:?})", s);
+        }
+        assert_eq!(Netmask::from_addr(addr("255.0.255.0")), None);
+    }
+
+    #[test]
+    fn test_netmask_prefix() {
+        let cases = Vec::from([
+            ("10.0.0.0 255.0.0.0", "10.0.0.0/8"),
+            ("10.0.0.0  \t255.255.0.0", "10.0.0.0/16"),
+            ("10.0.0.0/255.255.0.0", "10.0.0.0/16"),
+            ("192.168.1.128 255.255.255.192", "192.168.1.128/26"),
+            ("0.0.0.0 0.0.0.0", "0.0.0.0/0"),
+            ("10.0.0.1 255.255.255.255", "10.0.0.1/32"),
+        ]);
+        for (s, want) in cases {
+            let p = prefix(want);
+            assert_eq!(
+                parse_netmask_prefix(s),
+                Ok(p),
+                "parse_netmask_prefix({:?})",
+                s
+            );
+            let round_trip = p.netmask_notation().to_string();
+            assert_eq!(parse_netmask_prefix(&round_trip), Ok(p));
+        }
+        assert_eq!(
+            prefix("10.0.0.0/16").netmask_notation().to_string(),
+            "10.0.0.0 255.255.0.0"
+        );
+
+        let invalids = Vec::from([
+            ("10.0.0.0", InvalidPrefixErr::MissingMask { offset: 8 }),
+            ("10.0.0.0 ", InvalidPrefixErr::MissingMask { offset: 9 }),
+            (
+                "10.0.0.0 255.0.255.0",
+                InvalidPrefixErr::NonContiguousMask { offset: 15 },
+            ),
+            (
+                "10.0.0.1 255.0.0.0",
+                InvalidPrefixErr::HostBitsSet { offset: 7 },
+            ),
+            (
+                "10.0.0.0 255.0.0.256",
+                InvalidPrefixErr::Addr(InvalidAddrErr::OctetOutOfRange {
+                    offset: 17,
+                    octet: 3,
+                }),
+            ),
+        ]);
+        for (s, want) in invalids {
+            assert_eq!(
+                parse_netmask_prefix(s),
+                Err(want),
+                "parse_netmask_prefix({:?})",
+                s
+            );
+        }
+        assert_eq!(
+            ParseOptions::permissive().parse_netmask_prefix("10.0.0.1 255.0.0.0"),
+            Ok(prefix("10.0.0.0/8"))
+        );
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let w = parse_wildcard("10.0.0.0 0.0.255.255").unwrap();
+        assert!(w.matches(addr("10.0.1.2")));
+        assert!(!w.matches(addr("10.1.0.0")));
+        assert!(w.is_contiguous());
+        assert_eq!(w.to_prefix(), Some(prefix("10.0.0.0/16")));
+        assert_eq!(w.size(), 65536);
+        assert_eq!(Wildcard::from(prefix("10.0.0.0/16")), w);
+        assert_eq!(prefix("10.0.0.0/16").wildcard(), w);
+        assert_eq!(w.to_string(), "10.0.0.0 0.0.255.255");
+
+        // non-contiguous masks match every combination of the masked bits.
+        let w = parse_wildcard("10.0.0.1 0.255.0.0").unwrap();
+        assert!(w.matches(addr("10.0.0.1")));
+        assert!(w.matches(addr("10.77.0.1")));
+        assert!(!w.matches(addr("10.77.0.2")));
+        assert!(!w.is_contiguous());
+        assert_eq!(w.to_prefix(), None);
+        assert_eq!(w.size(), 256);
+
+        // odd addresses only.
+        let w = parse_wildcard("0.0.0.1 255.255.255.254").unwrap();
+        assert!(w.matches(addr("192.168.1.1")));
+        assert!(!w.matches(addr("192.168.1.2")));
+
+        // bits under the mask are cleared.
+        assert_eq!(
+            parse_wildcard("10.1.1.1 0.0.0.255").unwrap().to_string(),
+            "10.1.1.0 0.0.0.255"
+        );
+
+        assert_eq!(parse_wildcard("any"), Ok(Wildcard::ANY));
+        assert!(Wildcard::ANY.matches(addr("1.2.3.4")));
+        let host = parse_wildcard("host 10.0.0.1").unwrap();
+        assert_eq!(host, Wildcard::from(addr("10.0.0.1")));
+        assert!(host.matches(addr("10.0.0.1")));
+        assert!(!host.matches(addr("10.0.0.2")));
+        assert_eq!(host.to_prefix(), Some(prefix("10.0.0.1/32")));
+
+        assert_eq!(
+            parse_wildcard("host 10.0.0.256"),
+            Err(InvalidPrefixErr::Addr(InvalidAddrErr::OctetOutOfRange {
+                offset: 12,
+                octet: 3
+            }))
+        );
+        assert_eq!(
+            parse_wildcard("10.0.0.0"),
+            Err(InvalidPrefixErr::MissingMask { offset: 8 })
+        );
+    }
+}
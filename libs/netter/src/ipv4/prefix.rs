@@ -0,0 +1,682 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Addr, InvalidAddrErr, LeadingZeros, ParseOptions, scan};
+use crate::ascii::find;
+
+// InvalidPrefixErr describes why a string is not a valid IPv4 prefix. Like
+// InvalidAddrErr, every variant carries the byte offset in the input where
+// the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidPrefixErr {
+    // the address before the '/' is invalid.
+    Addr(InvalidAddrErr),
+    // there was no '/' after the address, e.g. "10.0.0.0".
+    MissingLength { offset: usize },
+    // the prefix length is not a decimal number, e.g. "10.0.0.0/x".
+    InvalidLength { offset: usize },
+    // the prefix length is greater than 32, e.g. "10.0.0.0/33".
+    LengthOutOfRange { offset: usize },
+    // the address has bits set past the prefix length and the ParseOptions
+    // reject host bits, e.g. "10.0.0.1/8". offset points at the first octet
+    // with host bits in it.
+    HostBitsSet { offset: usize },
+    // there was no mask after the address, e.g. "10.0.0.0" where an address
+    // and a netmask or wildcard mask were expected.
+    MissingMask { offset: usize },
+    // a netmask has a zero bit before a one bit, e.g. "255.0.255.0". offset
+    // points at the first octet that breaks the run of ones.
+    NonContiguousMask { offset: usize },
+}
+
+impl InvalidPrefixErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidPrefixErr::Addr(err) => err.offset(),
+            InvalidPrefixErr::MissingLength { offset }
+            | InvalidPrefixErr::InvalidLength { offset }
+            | InvalidPrefixErr::LengthOutOfRange { offset }
+            | InvalidPrefixErr::HostBitsSet { offset }
+            | InvalidPrefixErr::MissingMask { offset }
+            | InvalidPrefixErr::NonContiguousMask { offset } => offset,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidPrefixErr::Addr(err) => err.reason(),
+            InvalidPrefixErr::MissingLength { .. } => "missing prefix length",
+            InvalidPrefixErr::InvalidLength { .. } => "invalid prefix length",
+            InvalidPrefixErr::LengthOutOfRange { .. } => "prefix length out of range",
+            InvalidPrefixErr::HostBitsSet { .. } => "host bits set",
+            InvalidPrefixErr::MissingMask { .. } => "missing mask",
+            InvalidPrefixErr::NonContiguousMask { .. } => "netmask is not contiguous",
+        }
+    }
+}
+
+impl fmt::Display for InvalidPrefixErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let InvalidPrefixErr::Addr(err) = self {
+            return write!(f, "{}", err);
+        }
+        write!(
+            f,
+            "invalid ipv4 prefix string: {} at byte {}",
+            self.reason(),
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidPrefixErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidPrefixErr::Addr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidAddrErr> for InvalidPrefixErr {
+    fn from(err: InvalidAddrErr) -> InvalidPrefixErr {
+        InvalidPrefixErr::Addr(err)
+    }
+}
+
+// HostBits decides what parsing a prefix does when the address has bits set
+// past the prefix length, like the ".1" in "10.0.0.1/8".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HostBits {
+    // Reject fails the parse with InvalidPrefixErr::HostBitsSet.
+    Reject,
+    // Mask clears the host bits, so "10.0.0.1/8" is 10.0.0.0/8.
+    Mask,
+}
+
+// Prefix is an IPv4 network prefix in CIDR notation, e.g. 10.0.0.0/8. The
+// address is always the network address: host bits are never set. Prefixes
+// are ordered by network address and then by length, so a prefix sorts
+// right before the prefixes it contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Prefix {
+    addr: Addr,
+    len: u8,
+}
+
+impl Prefix {
+    // new builds a prefix from a network address and a length. It returns
+    // None if len is greater than 32 or addr has host bits set.
+    pub const fn new(addr: Addr, len: u8) -> Option<Prefix> {
+        if len > 32 || addr.to_bits() & !mask(len) != 0 {
+            return None;
+        }
+        Some(Prefix { addr, len })
+    }
+
+    // new_masked builds a prefix from any address in it and a length, by
+    // clearing the host bits of addr. It returns None if len is greater
+    // than 32.
+    pub const fn new_masked(addr: Addr, len: u8) -> Option<Prefix> {
+        if len > 32 {
+            return None;
+        }
+        Some(Prefix {
+            addr: Addr::from_bits(addr.to_bits() & mask(len)),
+            len,
+        })
+    }
+
+    // prefix_len returns the length of the prefix in bits.
+    pub const fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    // network returns the first address in the prefix.
+    pub const fn network(&self) -> Addr {
+        self.addr
+    }
+
+    // broadcast returns the last address in the prefix.
+    pub const fn broadcast(&self) -> Addr {
+        Addr::from_bits(self.addr.to_bits() | !mask(self.len))
+    }
+
+    // netmask returns the prefix length as a mask, e.g. 255.0.0.0 for a /8.
+    pub const fn netmask(&self) -> Addr {
+        Addr::from_bits(mask(self.len))
+    }
+
+    // hostmask returns the inverse of the netmask, e.g. 0.255.255.255 for a /8.
+    pub const fn hostmask(&self) -> Addr {
+        Addr::from_bits(!mask(self.len))
+    }
+
+    // size returns the number of addresses in the prefix, including the
+    // network and broadcast addresses.
+    pub const fn size(&self) -> u64 {
+        1 << (32 - self.len)
+    }
+
+    // host_count returns the number of usable host addresses in the prefix.
+    // The network and broadcast addresses are not usable, except in a /31
+    // where both addresses are hosts on a point-to-point link (RFC 3021) and
+    // in a /32 where the single address is the host.
+    pub const fn host_count(&self) -> u64 {
+        match self.len {
+            31 | 32 => self.size(),
+            _ => self.size() - 2,
+        }
+    }
+
+    // first_host returns the first usable host address. See host_count.
+    pub const fn first_host(&self) -> Addr {
+        match self.len {
+            31 | 32 => self.network(),
+            _ => Addr::from_bits(self.addr.to_bits() + 1),
+        }
+    }
+
+    // last_host returns the last usable host address. See host_count.
+    pub const fn last_host(&self) -> Addr {
+        match self.len {
+            31 | 32 => self.broadcast(),
+            _ => Addr::from_bits(self.broadcast().to_bits() - 1),
+        }
+    }
+
+    // hosts returns an iterator over the usable host addresses, from
+    // first_host to last_host.
+    pub fn hosts(&self) -> Hosts {
+        Hosts {
+            next: self.first_host().to_bits() as u64,
+            end: self.last_host().to_bits() as u64,
+        }
+    }
+
+    // contains returns true if addr is in the prefix.
+    pub const fn contains(&self, addr: Addr) -> bool {
+        addr.to_bits() & mask(self.len) == self.addr.to_bits()
+    }
+
+    // contains_prefix returns true if every address in other is also in
+    // this prefix. A prefix contains itself.
+    pub const fn contains_prefix(&self, other: &Prefix) -> bool {
+        other.len >= self.len && self.contains(other.addr)
+    }
+
+    // overlaps returns true if the two prefixes have any address in common,
+    // which for prefixes means one of them contains the other.
+    pub const fn overlaps(&self, other: &Prefix) -> bool {
+        self.contains_prefix(other) || other.contains_prefix(self)
+    }
+
+    // supernet returns the prefix one bit shorter that contains this one,
+    // or None for 0.0.0.0/0.
+    pub const fn supernet(&self) -> Option<Prefix> {
+        if self.len == 0 {
+            return None;
+        }
+        Prefix::new_masked(self.addr, self.len - 1)
+    }
+
+    // supernets returns an iterator over every prefix that contains this
+    // one, from the next shorter prefix up to 0.0.0.0/0.
+    pub fn supernets(&self) -> Supernets {
+        Supernets {
+            next: self.supernet(),
+        }
+    }
+
+    // subnets returns an iterator over the prefixes of length len that make
+    // up this prefix, in order. It returns None if len is shorter than this
+    // prefix or greater than 32. For example 10.0.0.0/23 has the /24
+    // subnets 10.0.0.0/24 and 10.0.1.0/24.
+    pub fn subnets(&self, len: u8) -> Option<Subnets> {
+        if len < self.len || len > 32 {
+            return None;
+        }
+        Some(Subnets {
+            next: self.addr.to_bits() as u64,
+            end: self.broadcast().to_bits() as u64,
+            len,
+        })
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+impl FromStr for Prefix {
+    type Err = InvalidPrefixErr;
+
+    fn from_str(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+        parse_prefix(s)
+    }
+}
+
+impl From<Addr> for Prefix {
+    // an address on its own is the /32 prefix that contains only it.
+    fn from(addr: Addr) -> Prefix {
+        Prefix { addr, len: 32 }
+    }
+}
+
+// Hosts iterates over the usable host addresses of a prefix. See
+// Prefix::hosts.
+#[derive(Debug, Clone)]
+pub struct Hosts {
+    next: u64,
+    end: u64,
+}
+
+impl Iterator for Hosts {
+    type Item = Addr;
+
+    fn next(&mut self) -> Option<Addr> {
+        if self.next > self.end {
+            return None;
+        }
+        let addr = Addr::from_bits(self.next as u32);
+        self.next += 1;
+        Some(addr)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint((self.end + 1).saturating_sub(self.next))
+    }
+}
+
+// Subnets iterates over the subnets of a prefix. See Prefix::subnets.
+#[derive(Debug, Clone)]
+pub struct Subnets {
+    next: u64,
+    end: u64,
+    len: u8,
+}
+
+impl Iterator for Subnets {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        if self.next > self.end {
+            return None;
+        }
+        let prefix = Prefix {
+            addr: Addr::from_bits(self.next as u32),
+            len: self.len,
+        };
+        self.next += 1 << (32 - self.len);
+        Some(prefix)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint((self.end + 1).saturating_sub(self.next) >> (32 - self.len))
+    }
+}
+
+// size_hint is the size hint of an iterator with n items left. There can be
+// 2^32 of them, which does not fit in a usize on 32-bit targets, so the
+// iterators are not ExactSizeIterator.
+pub(super) fn size_hint(n: u64) -> (usize, Option<usize>) {
+    match usize::try_from(n) {
+        Ok(n) => (n, Some(n)),
+        Err(_) => (usize::MAX, None),
+    }
+}
+
+// Supernets iterates over the prefixes that contain a prefix. See
+// Prefix::supernets.
+#[derive(Debug, Clone)]
+pub struct Supernets {
+    next: Option<Prefix>,
+}
+
+impl Iterator for Supernets {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        let prefix = self.next?;
+        self.next = prefix.supernet();
+        Some(prefix)
+    }
+}
+
+// parse_prefix will parse a string in CIDR notation, e.g. "10.0.0.0/8", into
+// a Prefix using the default ParseOptions, which reject host bits.
+pub fn parse_prefix(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+    ParseOptions::default().parse_prefix(s)
+}
+
+// parse_prefix_const will parse a string in CIDR notation into a Prefix in a
+// const context, using the default ParseOptions. It is what the
+// ipv4_prefix! macro expands to.
+pub const fn parse_prefix_const(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+    ParseOptions::std_compat().parse_prefix_const(s)
+}
+
+// scan_prefix is the parser behind ParseOptions::parse_prefix and
+// ParseOptions::parse_prefix_const. It works on bytes and is a const fn so
+// that prefixes can also be parsed at compile time.
+pub(super) const fn scan_prefix(
+    s: &[u8],
+    options: &ParseOptions,
+) -> Result<Prefix, InvalidPrefixErr> {
+    let Some(slash) = find(s, b'/') else {
+        // report a bad address before a missing length, since "10.0.0/8"
+        // style typos are more common than a forgotten length.
+        if let Err(err) = scan(s, options, false) {
+            return Err(InvalidPrefixErr::Addr(err));
+        }
+        return Err(InvalidPrefixErr::MissingLength { offset: s.len() });
+    };
+
+    let (addr, rest) = s.split_at(slash);
+    let addr = match scan(addr, options, false) {
+        Ok(([a, b, c, d], _)) => Addr::new(a, b, c, d),
+        Err(err) => return Err(InvalidPrefixErr::Addr(err)),
+    };
+    let len = match parse_len(rest.split_at(1).1, slash + 1, options) {
+        Ok(len) => len,
+        Err(err) => return Err(err),
+    };
+
+    match options.get_host_bits() {
+        HostBits::Mask => Ok(Prefix::new_masked(addr, len).unwrap()),
+        HostBits::Reject => match Prefix::new(addr, len) {
+            Some(prefix) => Ok(prefix),
+            None => Err(InvalidPrefixErr::HostBitsSet {
+                offset: host_bits_offset(s, addr, len),
+            }),
+        },
+    }
+}
+
+// parse_len parses the prefix length after the '/'. start is its offset in
+// the input.
+const fn parse_len(s: &[u8], start: usize, options: &ParseOptions) -> Result<u8, InvalidPrefixErr> {
+    if s.is_empty() {
+        return Err(InvalidPrefixErr::InvalidLength { offset: start });
+    }
+    let mut i = 0;
+    while i < s.len() {
+        if !s[i].is_ascii_digit() {
+            return Err(InvalidPrefixErr::InvalidLength { offset: start + i });
+        }
+        i += 1;
+    }
+    if s.len() > 1 && s[0] == b'0' && matches!(options.get_leading_zeros(), LeadingZeros::Reject) {
+        return Err(InvalidPrefixErr::InvalidLength { offset: start });
+    }
+
+    // the length is a number at this point, but three or more digits can
+    // only be out of range, and checking that first keeps len from overflowing.
+    if s.len() > 2 {
+        return Err(InvalidPrefixErr::LengthOutOfRange { offset: start });
+    }
+    let mut len = 0;
+    let mut i = 0;
+    while i < s.len() {
+        len = len * 10 + (s[i] - b'0');
+        i += 1;
+    }
+    if len > 32 {
+        return Err(InvalidPrefixErr::LengthOutOfRange { offset: start });
+    }
+
+    Ok(len)
+}
+
+// host_bits_offset returns the byte offset in s, which starts with a dotted
+// quad, of the first octet of addr that has bits set past len.
+pub(super) const fn host_bits_offset(s: &[u8], addr: Addr, len: u8) -> usize {
+    octet_offset(s, (addr.to_bits() & !mask(len)).leading_zeros() / 8)
+}
+
+// octet_offset returns the byte offset in s, which starts with a dotted quad,
+// of the octet with the given index.
+pub(super) const fn octet_offset(s: &[u8], octet: u32) -> usize {
+    let mut dots = 0;
+    let mut i = 0;
+    while i < s.len() && dots < octet {
+        if s[i] == b'.' {
+            dots += 1;
+            if dots == octet {
+                return i + 1;
+            }
+        }
+        i += 1;
+    }
+    0
+}
+
+// mask returns the netmask for a prefix length as a u32. len must be at
+// most 32.
+pub(super) const fn mask(len: u8) -> u32 {
+    match len {
+        0 => 0,
+        _ => u32::MAX << (32 - len),
+    }
+}
+
+#[cfg(test)]
+mod prefix_tests {
+    use super::{HostBits, InvalidPrefixErr, Prefix, parse_prefix};
+    use crate::ipv4::{Addr, InvalidAddrErr, ParseOptions};
+    use crate::testing::ipv4::{addr, prefix};
+
+    #[test]
+    fn test_parse_prefix() {
+        let valids = Vec::from([
+            "0.0.0.0/0",
+            "10.0.0.0/8",
+            "172.16.0.0/12",
+            "192.168.1.0/24",
+            "192.168.1.128/25",
+            "10.0.0.0/31",
+            "10.0.0.1/32",
+        ]);
+        for s in valids {
+            assert_eq!(prefix(s).to_string(), s, "round trip of {}", s);
+        }
+
+        let invalids = Vec::from([
+            ("10.0.0.0", InvalidPrefixErr::MissingLength { offset: 8 }),
+            (
+                "10.0.0/8",
+                InvalidPrefixErr::Addr(InvalidAddrErr::TooFewOctets {
+                    offset: 6,
+                    octet: 2,
+                }),
+            ),
+            ("10.0.0.0/", InvalidPrefixErr::InvalidLength { offset: 9 }),
+            ("10.0.0.0/x", InvalidPrefixErr::InvalidLength { offset: 9 }),
+            (
+                "10.0.0.0/8x",
+                InvalidPrefixErr::InvalidLength { offset: 10 },
+            ),
+            ("10.0.0.0/08", InvalidPrefixErr::InvalidLength { offset: 9 }),
+            (
+                "10.0.0.0/33",
+                InvalidPrefixErr::LengthOutOfRange { offset: 9 },
+            ),
+            (
+                "10.0.0.0/128",
+                InvalidPrefixErr::LengthOutOfRange { offset: 9 },
+            ),
+            ("10.0.0.1/8", InvalidPrefixErr::HostBitsSet { offset: 7 }),
+            ("10.0.1.0/16", InvalidPrefixErr::HostBitsSet { offset: 5 }),
+            ("128.0.0.0/0", InvalidPrefixErr::HostBitsSet { offset: 0 }),
+            ("10.0.0.1/31", InvalidPrefixErr::HostBitsSet { offset: 7 }),
+        ]);
+        for (s, want) in invalids {
+            assert_eq!(parse_prefix(s), Err(want), "parse_prefix({:?})", s);
+        }
+
+        let masked = ParseOptions::default().host_bits(HostBits::Mask);
+        assert_eq!(masked.parse_prefix("10.1.2.3/8"), Ok(prefix("10.0.0.0/8")));
+        assert_eq!(
+            ParseOptions::permissive().parse_prefix("10.1.2.3/16"),
+            Ok(prefix("10.1.0.0/16"))
+        );
+    }
+
+    #[test]
+    fn test_network_math() {
+        let cases = Vec::from([
+            // prefix, network, broadcast, netmask, hostmask, first, last, hosts
+            (
+                "10.0.0.0/8",
+                "10.0.0.0",
+                "10.255.255.255",
+                "255.0.0.0",
+                "0.255.255.255",
+                "10.0.0.1",
+                "10.255.255.254",
+                16777214,
+            ),
+            (
+                "192.168.1.64/26",
+                "192.168.1.64",
+                "192.168.1.127",
+                "255.255.255.192",
+                "0.0.0.63",
+                "192.168.1.65",
+                "192.168.1.126",
+                62,
+            ),
+            (
+                "192.168.1.4/30",
+                "192.168.1.4",
+                "192.168.1.7",
+                "255.255.255.252",
+                "0.0.0.3",
+                "192.168.1.5",
+                "192.168.1.6",
+                2,
+            ),
+            (
+                "192.168.1.4/31",
+                "192.168.1.4",
+                "192.168.1.5",
+                "255.255.255.254",
+                "0.0.0.1",
+                "192.168.1.4",
+                "192.168.1.5",
+                2,
+            ),
+            (
+                "192.168.1.4/32",
+                "192.168.1.4",
+                "192.168.1.4",
+                "255.255.255.255",
+                "0.0.0.0",
+                "192.168.1.4",
+                "192.168.1.4",
+                1,
+            ),
+            (
+                "0.0.0.0/0",
+                "0.0.0.0",
+                "255.255.255.255",
+                "0.0.0.0",
+                "255.255.255.255",
+                "0.0.0.1",
+                "255.255.255.254",
+                4294967294,
+            ),
+        ]);
+
+        for (p, network, broadcast, netmask, hostmask, first, last, hosts) in cases {
+            let p = prefix(p);
+            assert_eq!(p.network(), addr(network), "network of {}", p);
+            assert_eq!(p.broadcast(), addr(broadcast), "broadcast of {}", p);
+            assert_eq!(p.netmask(), addr(netmask), "netmask of {}", p);
+            assert_eq!(p.hostmask(), addr(hostmask), "hostmask of {}", p);
+            assert_eq!(p.first_host(), addr(first), "first host of {}", p);
+            assert_eq!(p.last_host(), addr(last), "last host of {}", p);
+            assert_eq!(p.host_count(), hosts, "host count of {}", p);
+        }
+
+        let hosts: Vec<Addr> = prefix("10.0.0.0/30").hosts().collect();
+        assert_eq!(hosts, [addr("10.0.0.1"), addr("10.0.0.2")]);
+        let hosts: Vec<Addr> = prefix("10.0.0.0/31").hosts().collect();
+        assert_eq!(hosts, [addr("10.0.0.0"), addr("10.0.0.1")]);
+        assert_eq!(prefix("255.255.255.255/32").hosts().count(), 1);
+        assert_eq!(
+            prefix("10.0.0.0/16").hosts().size_hint(),
+            (65534, Some(65534))
+        );
+        let n = u32::MAX as usize - 1;
+        assert_eq!(
+            prefix("0.0.0.0/0").hosts().size_hint(),
+            (n, Some(n)),
+            "hosts of 0.0.0.0/0"
+        );
+    }
+
+    #[test]
+    fn test_containment() {
+        let p = prefix("10.0.0.0/8");
+        assert!(p.contains(addr("10.0.0.0")));
+        assert!(p.contains(addr("10.255.255.255")));
+        assert!(!p.contains(addr("11.0.0.0")));
+        assert!(!p.contains(addr("9.255.255.255")));
+        assert!(prefix("0.0.0.0/0").contains(addr("255.255.255.255")));
+
+        assert!(p.contains_prefix(&p));
+        assert!(p.contains_prefix(&prefix("10.1.0.0/16")));
+        assert!(!p.contains_prefix(&prefix("0.0.0.0/0")));
+        assert!(!p.contains_prefix(&prefix("11.0.0.0/16")));
+
+        assert!(p.overlaps(&prefix("10.1.0.0/16")));
+        assert!(prefix("10.1.0.0/16").overlaps(&p));
+        assert!(!prefix("10.0.0.0/16").overlaps(&prefix("10.1.0.0/16")));
+    }
+
+    #[test]
+    fn test_supernets_and_subnets() {
+        let p = prefix("10.1.2.0/24");
+        assert_eq!(p.supernet(), Some(prefix("10.1.2.0/23")));
+        assert_eq!(prefix("0.0.0.0/0").supernet(), None);
+
+        let supernets: Vec<Prefix> = prefix("10.1.2.0/30").supernets().collect();
+        assert_eq!(supernets.len(), 30);
+        assert_eq!(supernets[0], prefix("10.1.2.0/29"));
+        assert_eq!(supernets[29], prefix("0.0.0.0/0"));
+
+        let subnets: Vec<Prefix> = prefix("10.0.0.0/23").subnets(24).unwrap().collect();
+        assert_eq!(subnets, [prefix("10.0.0.0/24"), prefix("10.0.1.0/24")]);
+        assert_eq!(p.subnets(24).unwrap().collect::<Vec<_>>(), [p]);
+        assert_eq!(
+            prefix("0.0.0.0/0").subnets(8).unwrap().size_hint(),
+            (256, Some(256))
+        );
+        assert_eq!(
+            prefix("0.0.0.0/0").subnets(32).unwrap().size_hint().1,
+            usize::try_from(1u64 << 32).ok(),
+            "/32 subnets of 0.0.0.0/0"
+        );
+        assert_eq!(
+            prefix("255.255.255.0/24").subnets(32).unwrap().last(),
+            Some(prefix("255.255.255.255/32"))
+        );
+        assert!(p.subnets(23).is_none());
+        assert!(p.subnets(33).is_none());
+
+        assert!(prefix("10.0.0.0/8") < prefix("10.0.0.0/16"));
+        assert!(prefix("10.0.0.0/16") < prefix("10.1.0.0/16"));
+        assert_eq!(Prefix::from(addr("10.0.0.1")), prefix("10.0.0.1/32"));
+        assert_eq!(Prefix::new(addr("10.0.0.1"), 8), None);
+        assert_eq!(
+            Prefix::new_masked(addr("10.0.0.1"), 8),
+            Some(prefix("10.0.0.0/8"))
+        );
+    }
+}
@@ -0,0 +1,522 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::prefix::size_hint;
+use super::{Addr, InvalidAddrErr, ParseOptions, Prefix};
+
+// InvalidRangeErr describes why a string is not a valid IPv4 address range.
+// Like InvalidAddrErr, every variant carries the byte offset in the input
+// where the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidRangeErr {
+    // the first or the last address is invalid. The offset of the error is
+    // in the whole input, not in the address.
+    Addr(InvalidAddrErr),
+    // there was no '-' after the first address, e.g. "10.0.0.1".
+    MissingEnd { offset: usize },
+    // the last address is before the first, e.g. "10.0.0.9-10.0.0.1".
+    // offset points at the last address.
+    Reversed { offset: usize },
+}
+
+impl InvalidRangeErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidRangeErr::Addr(err) => err.offset(),
+            InvalidRangeErr::MissingEnd { offset } | InvalidRangeErr::Reversed { offset } => offset,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidRangeErr::Addr(err) => err.reason(),
+            InvalidRangeErr::MissingEnd { .. } => "missing end of range",
+            InvalidRangeErr::Reversed { .. } => "end of range before start",
+        }
+    }
+}
+
+impl fmt::Display for InvalidRangeErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let InvalidRangeErr::Addr(err) = self {
+            return write!(f, "{}", err);
+        }
+        write!(
+            f,
+            "invalid ipv4 range string: {} at byte {}",
+            self.reason(),
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidRangeErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidRangeErr::Addr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidAddrErr> for InvalidRangeErr {
+    fn from(err: InvalidAddrErr) -> InvalidRangeErr {
+        InvalidRangeErr::Addr(err)
+    }
+}
+
+// AddrRange is an inclusive range of IPv4 addresses, e.g.
+// 10.0.0.5-10.0.3.17. Unlike a Prefix it can start and end anywhere, which
+// is how vendor feeds and whois records often list address blocks. Ranges
+// are ordered by start and then by end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AddrRange {
+    start: Addr,
+    end: Addr,
+}
+
+impl AddrRange {
+    // new builds the range from start to end, both included. It returns
+    // None if end is before start.
+    pub const fn new(start: Addr, end: Addr) -> Option<AddrRange> {
+        if end.to_bits() < start.to_bits() {
+            return None;
+        }
+        Some(AddrRange { start, end })
+    }
+
+    // start returns the first address in the range.
+    pub const fn start(&self) -> Addr {
+        self.start
+    }
+
+    // end returns the last address in the range.
+    pub const fn end(&self) -> Addr {
+        self.end
+    }
+
+    // size returns the number of addresses in the range.
+    pub const fn size(&self) -> u64 {
+        (self.end.to_bits() - self.start.to_bits()) as u64 + 1
+    }
+
+    // contains returns true if addr is in the range.
+    pub const fn contains(&self, addr: Addr) -> bool {
+        self.start.to_bits() <= addr.to_bits() && addr.to_bits() <= self.end.to_bits()
+    }
+
+    // contains_range returns true if every address in other is also in this
+    // range. A range contains itself.
+    pub const fn contains_range(&self, other: &AddrRange) -> bool {
+        self.contains(other.start) && self.contains(other.end)
+    }
+
+    // overlaps returns true if the two ranges have any address in common.
+    pub const fn overlaps(&self, other: &AddrRange) -> bool {
+        self.start.to_bits() <= other.end.to_bits() && other.start.to_bits() <= self.end.to_bits()
+    }
+
+    // iter returns an iterator over the addresses in the range, in order.
+    pub fn iter(&self) -> Addrs {
+        Addrs {
+            next: self.start.to_bits() as u64,
+            end: self.end.to_bits() as u64,
+        }
+    }
+
+    // prefixes returns an iterator over the fewest prefixes that together
+    // cover exactly the range, in order. For example 10.0.0.5-10.0.0.17 is
+    // 10.0.0.5/32, 10.0.0.6/31, 10.0.0.8/29 and 10.0.0.16/31.
+    pub fn prefixes(&self) -> Prefixes {
+        Prefixes {
+            next: self.start.to_bits() as u64,
+            end: self.end.to_bits() as u64,
+        }
+    }
+}
+
+impl fmt::Display for AddrRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl FromStr for AddrRange {
+    type Err = InvalidRangeErr;
+
+    fn from_str(s: &str) -> Result<AddrRange, InvalidRangeErr> {
+        parse_range(s)
+    }
+}
+
+impl From<Prefix> for AddrRange {
+    // a prefix is the range from its network address to its broadcast
+    // address.
+    fn from(prefix: Prefix) -> AddrRange {
+        AddrRange {
+            start: prefix.network(),
+            end: prefix.broadcast(),
+        }
+    }
+}
+
+impl From<Addr> for AddrRange {
+    // an address on its own is the range that contains only it.
+    fn from(addr: Addr) -> AddrRange {
+        AddrRange {
+            start: addr,
+            end: addr,
+        }
+    }
+}
+
+impl IntoIterator for AddrRange {
+    type Item = Addr;
+    type IntoIter = Addrs;
+
+    fn into_iter(self) -> Addrs {
+        self.iter()
+    }
+}
+
+// Addrs iterates over the addresses in a range. See AddrRange::iter.
+#[derive(Debug, Clone)]
+pub struct Addrs {
+    next: u64,
+    end: u64,
+}
+
+impl Iterator for Addrs {
+    type Item = Addr;
+
+    fn next(&mut self) -> Option<Addr> {
+        if self.next > self.end {
+            return None;
+        }
+        let addr = Addr::from_bits(self.next as u32);
+        self.next += 1;
+        Some(addr)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint((self.end + 1).saturating_sub(self.next))
+    }
+}
+
+// Prefixes iterates over the prefixes that make up a range. See
+// AddrRange::prefixes.
+#[derive(Debug, Clone)]
+pub struct Prefixes {
+    next: u64,
+    end: u64,
+}
+
+impl Iterator for Prefixes {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        if self.next > self.end {
+            return None;
+        }
+        // the largest prefix that starts at next is limited by how next is
+        // aligned and by how many addresses are left; taking it every time
+        // gives the fewest prefixes.
+        let align = self.next.trailing_zeros().min(32);
+        let fits = 63 - (self.end - self.next + 1).leading_zeros();
+        let bits = align.min(fits);
+        let prefix = Prefix::new(Addr::from_bits(self.next as u32), (32 - bits) as u8).unwrap();
+        self.next += 1 << bits;
+        Some(prefix)
+    }
+}
+
+// parse_range will parse a range of addresses written as the first and the
+// last address with a '-' between them, e.g. "10.0.0.5-10.0.3.17", into an
+// AddrRange. Spaces around the '-' are allowed, as whois writes ranges
+// like "10.0.0.0 - 10.255.255.255". It uses the default ParseOptions.
+pub fn parse_range(s: &str) -> Result<AddrRange, InvalidRangeErr> {
+    ParseOptions::default().parse_range(s)
+}
+
+// scan_range is the parser behind ParseOptions::parse_range.
+pub(super) fn scan_range(s: &str, options: &ParseOptions) -> Result<AddrRange, InvalidRangeErr> {
+    let Some(dash) = s.find('-') else {
+        // report a bad address before a missing end, like the prefix parser.
+        options.parse(s)?;
+        return Err(InvalidRangeErr::MissingEnd { offset: s.len() });
+    };
+
+    let first = s[..dash].trim_end_matches(' ');
+    let rest = &s[dash + 1..];
+    let last_start = dash + 1 + rest.len() - rest.trim_start_matches(' ').len();
+    let start = options.parse(first)?;
+    let end = options
+        .parse(&s[last_start..])
+        .map_err(|err| err.shifted(last_start))?;
+
+    AddrRange::new(start, end).ok_or(InvalidRangeErr::Reversed { offset: last_start })
+}
+
+// collapse_prefixes merges prefixes into the fewest ranges that cover the
+// same addresses, in order. Prefixes that overlap or are next to each other
+// become one range; the input does not have to be sorted.
+#[cfg(feature = "alloc")]
+pub fn collapse_prefixes(prefixes: impl IntoIterator<Item = Prefix>) -> alloc::vec::Vec<AddrRange> {
+    let mut ranges: alloc::vec::Vec<AddrRange> =
+        prefixes.into_iter().map(AddrRange::from).collect();
+    ranges.sort_unstable();
+
+    let mut merged: alloc::vec::Vec<AddrRange> = alloc::vec::Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start.to_bits() as u64 <= last.end.to_bits() as u64 + 1 => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod range_tests {
+    use super::{AddrRange, InvalidRangeErr, parse_range};
+    use crate::ipv4::{Addr, InvalidAddrErr, Prefix};
+    use crate::rng::Rng;
+    use crate::testing::ipv4::{addr, prefix, range};
+
+    #[test]
+    fn test_parse_range() {
+        let cases = Vec::from([
+            ("10.0.0.5-10.0.3.17", Ok(range("10.0.0.5", "10.0.3.17"))),
+            (
+                "10.0.0.0 - 10.255.255.255",
+                Ok(range("10.0.0.0", "10.255.255.255")),
+            ),
+            ("10.0.0.1-10.0.0.1", Ok(range("10.0.0.1", "10.0.0.1"))),
+            (
+                "10.0.0.9-10.0.0.1",
+                Err(InvalidRangeErr::Reversed { offset: 9 }),
+            ),
+            ("10.0.0.1", Err(InvalidRangeErr::MissingEnd { offset: 8 })),
+            (
+                "10.0.0.1-",
+                Err(InvalidRangeErr::Addr(InvalidAddrErr::EmptyOctet {
+                    offset: 9,
+                    octet: 0,
+                })),
+            ),
+            (
+                "10.0.0.1 - 10.0.0.256",
+                Err(InvalidRangeErr::Addr(InvalidAddrErr::OctetOutOfRange {
+                    offset: 18,
+                    octet: 3,
+                })),
+            ),
+            (
+                "10.0.0-10.0.0.1",
+                Err(InvalidRangeErr::Addr(InvalidAddrErr::TooFewOctets {
+                    offset: 6,
+                    octet: 2,
+                })),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_range(s), want, "parse_range({:?})", s);
+        }
+
+        let r = range("10.0.0.5", "10.0.3.17");
+        assert_eq!(r.to_string(), "10.0.0.5-10.0.3.17");
+        assert_eq!(r.to_string().parse::<AddrRange>(), Ok(r));
+    }
+
+    #[test]
+    fn test_range_membership() {
+        let r = range("10.0.0.5", "10.0.3.17");
+        assert_eq!(r.size(), 3 * 256 + 13);
+        assert!(r.contains(addr("10.0.0.5")));
+        assert!(r.contains(addr("10.0.3.17")));
+        assert!(!r.contains(addr("10.0.0.4")));
+        assert!(!r.contains(addr("10.0.3.18")));
+        assert!(r.contains_range(&range("10.0.1.0", "10.0.1.255")));
+        assert!(!r.contains_range(&range("10.0.3.0", "10.0.4.0")));
+        assert!(r.overlaps(&range("10.0.3.0", "10.0.4.0")));
+        assert!(!r.overlaps(&range("10.0.3.18", "10.0.4.0")));
+
+        assert_eq!(range("0.0.0.0", "255.255.255.255").size(), 1 << 32);
+        assert_eq!(AddrRange::new(addr("10.0.0.2"), addr("10.0.0.1")), None);
+        assert_eq!(
+            AddrRange::from(prefix("10.0.0.0/8")),
+            range("10.0.0.0", "10.255.255.255")
+        );
+
+        let addrs: Vec<Addr> = range("10.0.0.254", "10.0.1.1").into_iter().collect();
+        assert_eq!(
+            addrs,
+            ["10.0.0.254", "10.0.0.255", "10.0.1.0", "10.0.1.1"].map(addr)
+        );
+        assert_eq!(
+            range("255.255.255.255", "255.255.255.255").iter().count(),
+            1
+        );
+        let n = r.size() as usize;
+        assert_eq!(r.iter().size_hint(), (n, Some(n)));
+        assert_eq!(
+            range("0.0.0.0", "255.255.255.255").iter().size_hint().1,
+            usize::try_from(1u64 << 32).ok(),
+            "whole address space"
+        );
+    }
+
+    #[test]
+    fn test_prefixes() {
+        let cases = Vec::from([
+            (
+                range("10.0.0.5", "10.0.3.17"),
+                Vec::from([
+                    "10.0.0.5/32",
+                    "10.0.0.6/31",
+                    "10.0.0.8/29",
+                    "10.0.0.16/28",
+                    "10.0.0.32/27",
+                    "10.0.0.64/26",
+                    "10.0.0.128/25",
+                    "10.0.1.0/24",
+                    "10.0.2.0/24",
+                    "10.0.3.0/28",
+                    "10.0.3.16/31",
+                ]),
+            ),
+            (
+                range("0.0.0.0", "255.255.255.255"),
+                Vec::from(["0.0.0.0/0"]),
+            ),
+            (
+                range("10.0.0.0", "10.255.255.255"),
+                Vec::from(["10.0.0.0/8"]),
+            ),
+            (
+                range("255.255.255.254", "255.255.255.255"),
+                Vec::from(["255.255.255.254/31"]),
+            ),
+            (range("0.0.0.1", "0.0.0.1"), Vec::from(["0.0.0.1/32"])),
+            (
+                range("0.0.0.0", "255.255.255.254"),
+                Vec::from([
+                    "0.0.0.0/1",
+                    "128.0.0.0/2",
+                    "192.0.0.0/3",
+                    "224.0.0.0/4",
+                    "240.0.0.0/5",
+                    "248.0.0.0/6",
+                    "252.0.0.0/7",
+                    "254.0.0.0/8",
+                    "255.0.0.0/9",
+                    "255.128.0.0/10",
+                    "255.192.0.0/11",
+                    "255.224.0.0/12",
+                    "255.240.0.0/13",
+                    "255.248.0.0/14",
+                    "255.252.0.0/15",
+                    "255.254.0.0/16",
+                    "255.255.0.0/17",
+                    "255.255.128.0/18",
+                    "255.255.192.0/19",
+                    "255.255.224.0/20",
+                    "255.255.240.0/21",
+                    "255.255.248.0/22",
+                    "255.255.252.0/23",
+                    "255.255.254.0/24",
+                    "255.255.255.0/25",
+                    "255.255.255.128/26",
+                    "255.255.255.192/27",
+                    "255.255.255.224/28",
+                    "255.255.255.240/29",
+                    "255.255.255.248/30",
+                    "255.255.255.252/31",
+                    "255.255.255.254/32",
+                ]),
+            ),
+        ]);
+
+        for (r, want) in cases {
+            let got: Vec<Prefix> = r.prefixes().collect();
+            let want: Vec<Prefix> = want.into_iter().map(prefix).collect();
+            assert_eq!(got, want, "prefixes of {}", r);
+        }
+    }
+
+    #[test]
+    fn test_prefixes_random() {
+        // the prefixes of a range are in order, next to each other, cover the
+        // range exactly, and no two neighbours could be one prefix.
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..10000 {
+            let (a, b) = (rng.next() as u32, rng.next() as u32 >> (rng.next() % 32));
+            let (a, b) = (a.min(a.wrapping_add(b)), a.max(a.wrapping_add(b)));
+            let r = AddrRange::new(Addr::from_bits(a), Addr::from_bits(b)).unwrap();
+
+            let prefixes: Vec<Prefix> = r.prefixes().collect();
+            assert_eq!(prefixes[0].network(), r.start(), "start of {}", r);
+            assert_eq!(
+                prefixes.last().unwrap().broadcast(),
+                r.end(),
+                "end of {}",
+                r
+            );
+            for pair in prefixes.windows(2) {
+                assert_eq!(
+                    pair[0].broadcast().to_bits() + 1,
+                    pair[1].network().to_bits(),
+                    "gap in {}",
+                    r
+                );
+                let siblings = pair[0].prefix_len() == pair[1].prefix_len()
+                    && pair[0].supernet() == pair[1].supernet();
+                assert!(
+                    !siblings,
+                    "{} and {} in {} could be one prefix",
+                    pair[0], pair[1], r
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_collapse_prefixes() {
+        use super::collapse_prefixes;
+
+        let cases = Vec::from([
+            (Vec::from([]), Vec::from([])),
+            (
+                Vec::from(["10.0.1.0/24", "10.0.0.0/24", "10.0.3.0/24"]),
+                Vec::from([
+                    range("10.0.0.0", "10.0.1.255"),
+                    range("10.0.3.0", "10.0.3.255"),
+                ]),
+            ),
+            (
+                Vec::from(["10.0.0.0/8", "10.1.0.0/16", "11.0.0.0/8"]),
+                Vec::from([range("10.0.0.0", "11.255.255.255")]),
+            ),
+            (
+                Vec::from(["255.255.255.255/32", "0.0.0.0/32", "0.0.0.0/0"]),
+                Vec::from([range("0.0.0.0", "255.255.255.255")]),
+            ),
+        ]);
+
+        for (prefixes, want) in cases {
+            let got = collapse_prefixes(prefixes.iter().map(|p| prefix(p)));
+            assert_eq!(got, want, "collapse of {:?}", prefixes);
+        }
+
+        // collapsing the prefixes of a range gives the range back.
+        let r = range("10.0.0.5", "10.0.3.17");
+        assert_eq!(collapse_prefixes(r.prefixes()), Vec::from([r]));
+    }
+}
@@ -0,0 +1,394 @@
+use core::fmt;
+
+use super::{Addr, Prefix};
+use crate::reverse::labels;
+
+// InvalidReverseNameErr describes why a string is not a valid in-addr.arpa
+// name. Like InvalidAddrErr, every variant carries the byte offset in the
+// input where the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidReverseNameErr {
+    // the name does not end in "in-addr.arpa", e.g. "1.2.0.192.ip6.arpa".
+    // offset is the end of the name.
+    MissingSuffix { offset: usize },
+    // a label is empty, e.g. "1..0.192.in-addr.arpa".
+    EmptyLabel { offset: usize },
+    // a label is not a decimal octet from 0 to 255 without leading zeros,
+    // e.g. "01.2.0.192.in-addr.arpa".
+    InvalidLabel { offset: usize },
+    // there were more than four labels, e.g. "5.1.2.0.192.in-addr.arpa".
+    TooManyLabels { offset: usize },
+    // there were fewer than four labels where a whole address was expected,
+    // e.g. "2.0.192.in-addr.arpa". offset is the start of the suffix.
+    TooFewLabels { offset: usize },
+    // an RFC 2317 classless label has a length outside 25 to 31, e.g.
+    // "0/24.2.0.192.in-addr.arpa".
+    LengthOutOfRange { offset: usize },
+    // an RFC 2317 classless label has bits set past its length, e.g.
+    // "65/26.2.0.192.in-addr.arpa".
+    HostBitsSet { offset: usize },
+}
+
+impl InvalidReverseNameErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidReverseNameErr::MissingSuffix { offset }
+            | InvalidReverseNameErr::EmptyLabel { offset }
+            | InvalidReverseNameErr::InvalidLabel { offset }
+            | InvalidReverseNameErr::TooManyLabels { offset }
+            | InvalidReverseNameErr::TooFewLabels { offset }
+            | InvalidReverseNameErr::LengthOutOfRange { offset }
+            | InvalidReverseNameErr::HostBitsSet { offset } => offset,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidReverseNameErr::MissingSuffix { .. } => "missing in-addr.arpa suffix",
+            InvalidReverseNameErr::EmptyLabel { .. } => "empty label",
+            InvalidReverseNameErr::InvalidLabel { .. } => "invalid label",
+            InvalidReverseNameErr::TooManyLabels { .. } => "too many labels",
+            InvalidReverseNameErr::TooFewLabels { .. } => "too few labels",
+            InvalidReverseNameErr::LengthOutOfRange { .. } => "prefix length out of range",
+            InvalidReverseNameErr::HostBitsSet { .. } => "host bits set",
+        }
+    }
+}
+
+impl fmt::Display for InvalidReverseNameErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid ipv4 reverse name: {} at byte {}",
+            self.reason(),
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidReverseNameErr {}
+
+// ReverseName renders an Addr as the in-addr.arpa name its PTR record lives
+// at, e.g. "1.2.0.192.in-addr.arpa". See Addr::reverse_name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseName(Addr);
+
+impl fmt::Display for ReverseName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d] = self.0.octets();
+        write!(f, "{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+    }
+}
+
+// ReverseZone renders a Prefix as the name of its in-addr.arpa zone. See
+// Prefix::reverse_zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseZone(Prefix);
+
+impl fmt::Display for ReverseZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let octets = self.0.network().octets();
+        let len = self.0.prefix_len() as usize;
+
+        // an RFC 2317 zone has the classless label in place of the octet
+        // the prefix ends in.
+        let whole = len / 8;
+        if !len.is_multiple_of(8) {
+            write!(f, "{}/{}.", octets[whole], len)?;
+        }
+        for i in (0..whole).rev() {
+            write!(f, "{}.", octets[i])?;
+        }
+        write!(f, "in-addr.arpa")
+    }
+}
+
+impl Addr {
+    // reverse_name returns the in-addr.arpa name for the address, the octets
+    // in reverse order, as used for its PTR record. It is written without
+    // the trailing dot of a fully qualified name, which a zone file needs.
+    pub fn reverse_name(&self) -> ReverseName {
+        ReverseName(*self)
+    }
+}
+
+impl Prefix {
+    // reverse_zone returns the in-addr.arpa zone that holds the PTR records
+    // for the prefix. An octet-aligned prefix is a zone of its own:
+    // 192.0.2.0/24 is "2.0.192.in-addr.arpa". A prefix longer than /24 that
+    // is not is named as in RFC 2317's classless delegation:
+    // 192.0.2.64/26 is "64/26.2.0.192.in-addr.arpa". Any other prefix has no
+    // zone of its own, and None is returned; its records are spread over
+    // the zones of subnets(len rounded up to a multiple of 8).
+    pub fn reverse_zone(&self) -> Option<ReverseZone> {
+        let len = self.prefix_len();
+        (len.is_multiple_of(8) || len > 24).then_some(ReverseZone(*self))
+    }
+}
+
+// parse_reverse_name will parse the in-addr.arpa name of a PTR record, as
+// in a PTR query, back into an Addr:
+//
+//   parse_reverse_name("1.2.0.192.in-addr.arpa") == Ok(Addr::new(192, 0, 2, 1))
+//
+// The suffix may be in any case and the name may end in a dot. The four
+// labels must be decimal octets without leading zeros, as Addr::reverse_name
+// writes them.
+pub fn parse_reverse_name(s: &str) -> Result<Addr, InvalidReverseNameErr> {
+    let (octets, len, end) = scan_reverse(s.as_bytes(), false)?;
+    if len < 32 {
+        return Err(InvalidReverseNameErr::TooFewLabels { offset: end });
+    }
+    Ok(Addr::new(octets[0], octets[1], octets[2], octets[3]))
+}
+
+// parse_reverse_zone will parse an in-addr.arpa zone name back into a
+// Prefix. Each label is an octet, so "2.0.192.in-addr.arpa" is
+// 192.0.2.0/24 and "in-addr.arpa" is 0.0.0.0/0, and a name with four labels
+// is a /32. The first of four labels may be an RFC 2317 classless label,
+// the network's last octet and its length from 25 to 31:
+//
+//   parse_reverse_zone("64/26.2.0.192.in-addr.arpa") == Ok(192.0.2.64/26)
+pub fn parse_reverse_zone(s: &str) -> Result<Prefix, InvalidReverseNameErr> {
+    let (octets, len, _) = scan_reverse(s.as_bytes(), true)?;
+    Ok(Prefix::new(Addr::from(octets), len).unwrap())
+}
+
+// scan_reverse reads the labels of an in-addr.arpa name into the octets of
+// an address and a prefix length, and returns them with the offset of the
+// suffix. If classless is true the first label may be an RFC 2317 label,
+// which has to be followed by three more.
+fn scan_reverse(
+    bytes: &[u8],
+    classless: bool,
+) -> Result<([u8; 4], u8, usize), InvalidReverseNameErr> {
+    let Some((labels, end)) = labels(bytes, b"in-addr.arpa") else {
+        return Err(InvalidReverseNameErr::MissingSuffix {
+            offset: bytes.len(),
+        });
+    };
+
+    // read holds the octets in the order of the labels, the reverse of the
+    // order they have in the address.
+    let mut read = [0u8; 4];
+    let mut n = 0;
+    let mut len = None;
+    for (start, label) in labels {
+        if n == 4 {
+            return Err(InvalidReverseNameErr::TooManyLabels { offset: start });
+        }
+        if label.is_empty() {
+            return Err(InvalidReverseNameErr::EmptyLabel { offset: start });
+        }
+
+        let (octet, rest) = match label.iter().position(|&b| b == b'/') {
+            Some(slash) if classless && n == 0 => (&label[..slash], Some(slash + 1)),
+            _ => (label, None),
+        };
+        let Some(value) = decimal(octet).filter(|&v| v <= 255) else {
+            return Err(InvalidReverseNameErr::InvalidLabel { offset: start });
+        };
+        if let Some(at) = rest {
+            let Some(bits) = decimal(&label[at..]) else {
+                return Err(InvalidReverseNameErr::InvalidLabel { offset: start + at });
+            };
+            if !(25..=31).contains(&bits) {
+                return Err(InvalidReverseNameErr::LengthOutOfRange { offset: start + at });
+            }
+            if value as u8 & (0xff >> (bits - 24)) != 0 {
+                return Err(InvalidReverseNameErr::HostBitsSet { offset: start });
+            }
+            len = Some(bits as u8);
+        }
+        read[n] = value as u8;
+        n += 1;
+    }
+
+    if len.is_some() && n < 4 {
+        return Err(InvalidReverseNameErr::TooFewLabels { offset: end });
+    }
+    let mut octets = [0u8; 4];
+    for i in 0..n {
+        octets[i] = read[n - 1 - i];
+    }
+    Ok((octets, len.unwrap_or(8 * n as u8), end))
+}
+
+// decimal reads a decimal number of one to three digits without leading
+// zeros.
+fn decimal(digits: &[u8]) -> Option<u16> {
+    if digits.is_empty() || digits.len() > 3 || (digits[0] == b'0' && digits.len() > 1) {
+        return None;
+    }
+    digits.iter().try_fold(0u16, |value, &b| {
+        b.is_ascii_digit().then(|| value * 10 + (b - b'0') as u16)
+    })
+}
+
+#[cfg(test)]
+mod reverse_tests {
+    use super::{InvalidReverseNameErr, parse_reverse_name, parse_reverse_zone};
+    use crate::ipv4::{Addr, Prefix};
+    use crate::rng::Rng;
+    use crate::testing::ipv4::prefix;
+
+    #[test]
+    fn test_reverse_name() {
+        let cases = Vec::from([
+            (Addr::new(192, 0, 2, 1), "1.2.0.192.in-addr.arpa"),
+            (Addr::new(0, 0, 0, 0), "0.0.0.0.in-addr.arpa"),
+            (Addr::new(10, 20, 30, 255), "255.30.20.10.in-addr.arpa"),
+        ]);
+
+        for (addr, want) in cases {
+            assert_eq!(addr.reverse_name().to_string(), want, "{}", addr);
+            assert_eq!(parse_reverse_name(want), Ok(addr), "{}", want);
+        }
+    }
+
+    #[test]
+    fn test_reverse_zone() {
+        let cases = Vec::from([
+            ("0.0.0.0/0", Some("in-addr.arpa")),
+            ("10.0.0.0/8", Some("10.in-addr.arpa")),
+            ("192.168.0.0/16", Some("168.192.in-addr.arpa")),
+            ("192.0.2.0/24", Some("2.0.192.in-addr.arpa")),
+            ("192.0.2.1/32", Some("1.2.0.192.in-addr.arpa")),
+            ("192.0.2.0/25", Some("0/25.2.0.192.in-addr.arpa")),
+            ("192.0.2.64/26", Some("64/26.2.0.192.in-addr.arpa")),
+            ("192.0.2.252/30", Some("252/30.2.0.192.in-addr.arpa")),
+            ("192.0.2.254/31", Some("254/31.2.0.192.in-addr.arpa")),
+            ("172.16.0.0/12", None),
+            ("192.0.2.0/23", None),
+            ("128.0.0.0/1", None),
+        ]);
+
+        for (s, want) in cases {
+            let prefix = prefix(s);
+            let got = prefix.reverse_zone().map(|zone| zone.to_string());
+            assert_eq!(got.as_deref(), want, "{}", s);
+            if let Some(name) = want {
+                assert_eq!(parse_reverse_zone(name), Ok(prefix), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reverse() {
+        let cases = Vec::from([
+            ("1.2.0.192.in-addr.arpa.", Ok(Addr::new(192, 0, 2, 1))),
+            ("1.2.0.192.IN-ADDR.ARPA", Ok(Addr::new(192, 0, 2, 1))),
+            (
+                "1.2.0.192.ip6.arpa",
+                Err(InvalidReverseNameErr::MissingSuffix { offset: 18 }),
+            ),
+            (
+                "1.2.0.192in-addr.arpa",
+                Err(InvalidReverseNameErr::MissingSuffix { offset: 21 }),
+            ),
+            (
+                "1.2.0.192.in-addr.arpa..",
+                Err(InvalidReverseNameErr::MissingSuffix { offset: 24 }),
+            ),
+            (
+                "1..0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::EmptyLabel { offset: 2 }),
+            ),
+            (
+                ".1.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::EmptyLabel { offset: 0 }),
+            ),
+            (
+                "01.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 0 }),
+            ),
+            (
+                "1.2.256.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 4 }),
+            ),
+            (
+                "1.2.0.x.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 6 }),
+            ),
+            (
+                "64/26.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 0 }),
+            ),
+            (
+                "5.1.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::TooManyLabels { offset: 8 }),
+            ),
+            (
+                "2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::TooFewLabels { offset: 8 }),
+            ),
+            (
+                "in-addr.arpa",
+                Err(InvalidReverseNameErr::TooFewLabels { offset: 0 }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_reverse_name(s), want, "{}", s);
+        }
+
+        let zones = Vec::from([
+            ("2.0.192.in-addr.arpa.", Ok(prefix("192.0.2.0/24"))),
+            ("in-addr.arpa.", Ok(prefix("0.0.0.0/0"))),
+            ("128/25.2.0.192.in-addr.arpa", Ok(prefix("192.0.2.128/25"))),
+            (
+                "0/24.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::LengthOutOfRange { offset: 2 }),
+            ),
+            (
+                "0/32.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::LengthOutOfRange { offset: 2 }),
+            ),
+            (
+                "0/x.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 2 }),
+            ),
+            (
+                "65/26.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::HostBitsSet { offset: 0 }),
+            ),
+            (
+                "64/26.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::TooFewLabels { offset: 12 }),
+            ),
+            (
+                "2.64/26.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 2 }),
+            ),
+            (
+                "1.64/26.2.0.192.in-addr.arpa",
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 2 }),
+            ),
+        ]);
+
+        for (s, want) in zones {
+            assert_eq!(parse_reverse_zone(s), want, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_reverse_round_trip() {
+        // every prefix that has a zone must come back from its name.
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+
+        for _ in 0..10_000 {
+            let addr = Addr::from_bits(rng.next() as u32);
+            let name = addr.reverse_name().to_string();
+            assert_eq!(parse_reverse_name(&name), Ok(addr), "{}", name);
+
+            let prefix = Prefix::new_masked(addr, (rng.next() % 33) as u8).unwrap();
+            if let Some(zone) = prefix.reverse_zone() {
+                let name = zone.to_string();
+                assert_eq!(parse_reverse_zone(&name), Ok(prefix), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// simd.rs is the vector fast path for dotted quads. A dotted quad fits in
+// one 16 byte register, so a single pass classifies every byte, and the
+// digits are then shuffled into place and combined with two multiply-adds.
+// Like the SWAR path it handles only the common case and returns None for
+// anything else.
+//
+// The kernel needs SSE4.1. It is also compiled for AVX2, which gains nothing
+// from wider registers at these lengths but gets the VEX encodings and
+// avoids mixing legacy SSE and AVX code in callers built for AVX2. The best
+// of the two is picked at runtime, and machines with neither, or that are not
+// x86_64, use the SWAR path. Without std there is no runtime detection, and
+// the pick goes by the target features the crate is compiled with instead.
+
+use super::swar;
+
+// parse returns the octets of input if it is a dotted quad the fast path
+// handles, and None otherwise.
+#[cfg(target_arch = "x86_64")]
+pub(super) fn parse(input: &[u8]) -> Option<[u8; 4]> {
+    if has_avx2() {
+        // SAFETY: the CPU supports AVX2.
+        unsafe { x86::parse_avx2(input) }
+    } else if has_sse41() {
+        // SAFETY: the CPU supports SSE4.1.
+        unsafe { x86::parse_sse41(input) }
+    } else {
+        swar::parse(input)
+    }
+}
+
+// has_avx2 and has_sse41 report whether the CPU has AVX2 and SSE4.1.
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+fn has_avx2() -> bool {
+    std::is_x86_feature_detected!("avx2")
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+fn has_sse41() -> bool {
+    std::is_x86_feature_detected!("sse4.1")
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+fn has_avx2() -> bool {
+    cfg!(target_feature = "avx2")
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+fn has_sse41() -> bool {
+    cfg!(target_feature = "sse4.1")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(super) fn parse(input: &[u8]) -> Option<[u8; 4]> {
+    swar::parse(input)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::swar;
+    use core::arch::x86_64::*;
+
+    // parse_avx2 is parse_sse41 inlined into a function built for AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(crate) fn parse_avx2(input: &[u8]) -> Option<[u8; 4]> {
+        parse_sse41(input)
+    }
+
+    // parse_sse41 is the kernel. SSE4.1 brings packus_epi32, and with it
+    // the SSSE3 shuffle and multiply-add the kernel is built around.
+    #[target_feature(enable = "sse4.1")]
+    #[inline]
+    pub(crate) fn parse_sse41(input: &[u8]) -> Option<[u8; 4]> {
+        let n = input.len();
+        if !(swar::MIN_LEN..=swar::MAX_LEN).contains(&n) {
+            return None;
+        }
+        let [lo, hi] = swar::load(input);
+        let v = _mm_set_epi64x(hi as i64, lo as i64);
+
+        // every byte of the input has to be a digit or a dot. Subtracting
+        // b'0' turns digits into 0 to 9, and min leaves exactly those alone.
+        let dots = _mm_cmpeq_epi8(v, _mm_set1_epi8(b'.' as i8));
+        let values = _mm_sub_epi8(v, _mm_set1_epi8(b'0' as i8));
+        let digits = _mm_cmpeq_epi8(_mm_min_epu8(values, _mm_set1_epi8(9)), values);
+        let mask = (1u32 << n) - 1;
+        let valid = _mm_movemask_epi8(_mm_or_si128(dots, digits)) as u32;
+        if valid & mask != mask {
+            return None;
+        }
+        let dots = _mm_movemask_epi8(dots) as u32 & mask;
+        let lens = swar::split(input, dots)?;
+
+        // lay the digits out as hundreds, tens, ones and zero for each
+        // octet, then weigh them 100, 10, 1 and add up each group of four.
+        let table = &swar::SHUFFLES[swar::shuffle_index(lens)];
+        // SAFETY: table is 16 bytes, and loadu has no alignment requirement.
+        let shuffle = unsafe { _mm_loadu_si128(table.as_ptr().cast()) };
+        let digits = _mm_shuffle_epi8(values, shuffle);
+        let pairs = _mm_maddubs_epi16(digits, _mm_set1_epi32(0x0001_0a64));
+        let octets = _mm_madd_epi16(pairs, _mm_set1_epi16(1));
+        if _mm_movemask_epi8(_mm_cmpgt_epi32(octets, _mm_set1_epi32(255))) != 0 {
+            return None;
+        }
+
+        let words = _mm_packus_epi32(octets, octets);
+        let bytes = _mm_packus_epi16(words, words);
+        Some((_mm_cvtsi128_si32(bytes) as u32).to_le_bytes())
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod simd_tests {
+    use super::swar;
+
+    type Kernel = fn(&[u8]) -> Option<[u8; 4]>;
+
+    #[test]
+    fn test_kernels() {
+        let mut kernels: Vec<(&str, Kernel)> = Vec::new();
+        if is_x86_feature_detected!("sse4.1") {
+            // SAFETY: the CPU supports SSE4.1.
+            kernels.push(("sse4.1", |input| unsafe { super::x86::parse_sse41(input) }));
+        }
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports AVX2.
+            kernels.push(("avx2", |input| unsafe { super::x86::parse_avx2(input) }));
+        }
+
+        // every octet value in every position, plus a few near misses, as
+        // the SWAR path sees them.
+        let mut inputs = Vec::new();
+        for n in 0..=999 {
+            for block in [format!("{}", n), format!("{:02}", n)] {
+                for pos in 0..4 {
+                    let mut blocks = ["1", "22", "133", "4"];
+                    blocks[pos] = &block;
+                    inputs.push(blocks.join("."));
+                }
+            }
+        }
+        inputs.extend(
+            [
+                "1.2.3.4.",
+                "1.2.3.4 ",
+                "1..2.3",
+                "1.2.3.4/8",
+                "1.2.3.\u{e9}",
+            ]
+            .map(String::from),
+        );
+
+        for (name, kernel) in kernels {
+            for input in &inputs {
+                assert_eq!(
+                    kernel(input.as_bytes()),
+                    swar::parse(input.as_bytes()),
+                    "{} on {:?}",
+                    name,
+                    input
+                );
+            }
+        }
+    }
+}
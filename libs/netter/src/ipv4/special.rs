@@ -0,0 +1,482 @@
+use super::{Addr, Prefix};
+
+// Classification is the kind of special-purpose block an address belongs
+// to. Most variants correspond to an entry of the IANA IPv4 Special-Purpose
+// Address Registry (see SpecialPurpose); multicast addresses are classified
+// by scope instead, and everything else is Global.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Classification {
+    // "this network", 0.0.0.0/8 (RFC 791).
+    ThisNetwork,
+    // "this host on this network", 0.0.0.0/32 (RFC 1122).
+    ThisHost,
+    // private-use, 10.0.0.0/8, 172.16.0.0/12 and 192.168.0.0/16 (RFC 1918).
+    Private,
+    // shared address space for carrier-grade NAT, 100.64.0.0/10 (RFC 6598).
+    Shared,
+    // loopback, 127.0.0.0/8 (RFC 1122).
+    Loopback,
+    // link local, 169.254.0.0/16 (RFC 3927).
+    LinkLocal,
+    // IETF protocol assignments, 192.0.0.0/24 (RFC 6890), other than the
+    // more specific assignments below.
+    ProtocolAssignments,
+    // IPv4 service continuity prefix, 192.0.0.0/29 (RFC 7335).
+    ServiceContinuity,
+    // IPv4 dummy address, 192.0.0.8/32 (RFC 7600).
+    Dummy,
+    // Port Control Protocol anycast, 192.0.0.9/32 (RFC 7723).
+    PcpAnycast,
+    // TURN anycast, 192.0.0.10/32 (RFC 8155).
+    TurnAnycast,
+    // NAT64/DNS64 discovery, 192.0.0.170/32 and 192.0.0.171/32 (RFC 7050).
+    Nat64Discovery,
+    // documentation, TEST-NET-1, -2 and -3 (RFC 5737).
+    Documentation,
+    // AS112 DNS service, 192.31.196.0/24 (RFC 7535) and 192.175.48.0/24
+    // (RFC 7534).
+    As112,
+    // automatic multicast tunneling, 192.52.193.0/24 (RFC 7450).
+    Amt,
+    // the deprecated 6to4 relay anycast block, 192.88.99.0/24 (RFC 7526).
+    SixToFourRelayAnycast,
+    // 6a44 relay anycast, 192.88.99.2/32 (RFC 6751).
+    SixA44Relay,
+    // benchmarking, 198.18.0.0/15 (RFC 2544).
+    Benchmarking,
+    // reserved for future use, 240.0.0.0/4 (RFC 1112).
+    Reserved,
+    // limited broadcast, 255.255.255.255/32 (RFC 919).
+    Broadcast,
+    // multicast, 224.0.0.0/4 (RFC 5771), with its scope.
+    Multicast(MulticastScope),
+    // an address with no special purpose.
+    Global,
+}
+
+// MulticastScope is the scope of a multicast address, from the IANA IPv4
+// Multicast Address Space Registry (RFC 5771) and administratively scoped
+// multicast (RFC 2365).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MulticastScope {
+    // local network control block, 224.0.0.0/24. Never forwarded.
+    LocalNetworkControl,
+    // internetwork control block, 224.0.1.0/24.
+    InternetworkControl,
+    // source-specific multicast, 232.0.0.0/8 (RFC 4607).
+    SourceSpecific,
+    // GLOP addressing, 233.0.0.0/8 (RFC 3180), other than 233.252.0.0/14.
+    Glop,
+    // MCAST-TEST-NET for documentation, 233.252.0.0/24 (RFC 6676).
+    Documentation,
+    // unicast-prefix-based addressing, 234.0.0.0/8 (RFC 6034).
+    UnicastPrefixBased,
+    // organization-local scope, 239.192.0.0/14 (RFC 2365).
+    OrganizationLocal,
+    // IPv4 local scope, 239.255.0.0/16 (RFC 2365).
+    Local,
+    // the rest of administratively scoped multicast, 239.0.0.0/8 (RFC 2365).
+    AdminScoped,
+    // any other multicast address.
+    Global,
+}
+
+impl MulticastScope {
+    // is_global returns true if multicast traffic in this scope may be
+    // routed on the public internet.
+    pub const fn is_global(&self) -> bool {
+        matches!(
+            self,
+            MulticastScope::InternetworkControl
+                | MulticastScope::SourceSpecific
+                | MulticastScope::Glop
+                | MulticastScope::UnicastPrefixBased
+                | MulticastScope::Global
+        )
+    }
+}
+
+// SpecialPurpose is an entry of the IANA IPv4 Special-Purpose Address
+// Registry (RFC 6890). The flags are None where the registry says "N/A" or
+// leaves them blank, as it does for deprecated entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecialPurpose {
+    // prefix is the address block of the entry.
+    pub prefix: Prefix,
+    // name is the name of the entry in the registry.
+    pub name: &'static str,
+    // rfc is the reference of the entry, e.g. "[RFC1918]".
+    pub rfc: &'static str,
+    // allocated is the allocation date, e.g. "1996-02".
+    pub allocated: &'static str,
+    // terminated is the termination date of deprecated entries.
+    pub terminated: Option<&'static str>,
+    // source is true if an address from the block is valid as a source.
+    pub source: Option<bool>,
+    // destination is true if an address from the block is valid as a
+    // destination.
+    pub destination: Option<bool>,
+    // forwardable is true if routers may forward packets with an address
+    // from the block.
+    pub forwardable: Option<bool>,
+    // globally_reachable is true if an address from the block is reachable
+    // from the public internet.
+    pub globally_reachable: Option<bool>,
+    // reserved_by_protocol is true if the block is reserved by the IP
+    // protocol itself rather than by a later assignment.
+    pub reserved_by_protocol: Option<bool>,
+    // classification is the Classification of addresses in the block.
+    pub classification: Classification,
+}
+
+impl SpecialPurpose {
+    // entry builds a registry entry. It is only used by the generated
+    // table, and fails the build if a block in the registry is invalid.
+    #[allow(clippy::too_many_arguments)]
+    const fn entry(
+        octets: [u8; 4],
+        len: u8,
+        name: &'static str,
+        rfc: &'static str,
+        allocated: &'static str,
+        terminated: Option<&'static str>,
+        source: Option<bool>,
+        destination: Option<bool>,
+        forwardable: Option<bool>,
+        globally_reachable: Option<bool>,
+        reserved_by_protocol: Option<bool>,
+        classification: Classification,
+    ) -> SpecialPurpose {
+        let prefix = match Prefix::new(Addr(octets), len) {
+            Some(prefix) => prefix,
+            None => panic!("invalid address block in the special-purpose registry"),
+        };
+        SpecialPurpose {
+            prefix,
+            name,
+            rfc,
+            allocated,
+            terminated,
+            source,
+            destination,
+            forwardable,
+            globally_reachable,
+            reserved_by_protocol,
+            classification,
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/ipv4_special.rs"));
+
+// special_purpose_registry returns every entry of the vendored IANA IPv4
+// Special-Purpose Address Registry, in registry order.
+pub fn special_purpose_registry() -> &'static [SpecialPurpose] {
+    REGISTRY
+}
+
+// MULTICAST_SCOPES maps multicast blocks to their scope. More specific
+// blocks come first, and anything not listed is MulticastScope::Global.
+const MULTICAST_SCOPES: &[(Prefix, MulticastScope)] = &[
+    (
+        scope_block(224, 0, 0, 24),
+        MulticastScope::LocalNetworkControl,
+    ),
+    (
+        scope_block(224, 0, 1, 24),
+        MulticastScope::InternetworkControl,
+    ),
+    (scope_block(232, 0, 0, 8), MulticastScope::SourceSpecific),
+    (scope_block(233, 252, 0, 24), MulticastScope::Documentation),
+    (scope_block(233, 252, 0, 14), MulticastScope::Global),
+    (scope_block(233, 0, 0, 8), MulticastScope::Glop),
+    (
+        scope_block(234, 0, 0, 8),
+        MulticastScope::UnicastPrefixBased,
+    ),
+    (scope_block(239, 255, 0, 16), MulticastScope::Local),
+    (
+        scope_block(239, 192, 0, 14),
+        MulticastScope::OrganizationLocal,
+    ),
+    (scope_block(239, 0, 0, 8), MulticastScope::AdminScoped),
+];
+
+// scope_block builds an entry of MULTICAST_SCOPES.
+const fn scope_block(a: u8, b: u8, c: u8, len: u8) -> Prefix {
+    Prefix::new(Addr::new(a, b, c, 0), len).unwrap()
+}
+
+impl Addr {
+    // special_purpose returns the most specific entry of the IANA IPv4
+    // Special-Purpose Address Registry that contains the address, if any.
+    pub fn special_purpose(&self) -> Option<&'static SpecialPurpose> {
+        REGISTRY
+            .iter()
+            .filter(|entry| entry.prefix.contains(*self))
+            .max_by_key(|entry| entry.prefix.prefix_len())
+    }
+
+    // classification returns what kind of address this is. See
+    // Classification.
+    pub fn classification(&self) -> Classification {
+        if let Some(entry) = self.special_purpose() {
+            return entry.classification;
+        }
+        match self.multicast_scope() {
+            Some(scope) => Classification::Multicast(scope),
+            None => Classification::Global,
+        }
+    }
+
+    // multicast_scope returns the scope of a multicast address, or None if
+    // the address is not multicast.
+    pub fn multicast_scope(&self) -> Option<MulticastScope> {
+        if !self.is_multicast() {
+            return None;
+        }
+        let scope = MULTICAST_SCOPES
+            .iter()
+            .find(|(block, _)| block.contains(*self))
+            .map_or(MulticastScope::Global, |(_, scope)| *scope);
+        Some(scope)
+    }
+
+    // is_global returns true if the address is globally reachable: the most
+    // specific special-purpose entry containing it says so, or there is no
+    // such entry and it is not a multicast address of a non-global scope.
+    // Deprecated entries without flags are treated as not reachable.
+    pub fn is_global(&self) -> bool {
+        match self.special_purpose() {
+            Some(entry) => entry.globally_reachable == Some(true),
+            None => self.multicast_scope().is_none_or(|scope| scope.is_global()),
+        }
+    }
+
+    // is_unspecified returns true for 0.0.0.0.
+    pub const fn is_unspecified(&self) -> bool {
+        self.to_bits() == 0
+    }
+
+    // is_this_network returns true for addresses in 0.0.0.0/8.
+    pub const fn is_this_network(&self) -> bool {
+        self.0[0] == 0
+    }
+
+    // is_private returns true for the RFC 1918 private-use blocks.
+    pub fn is_private(&self) -> bool {
+        self.classification() == Classification::Private
+    }
+
+    // is_shared returns true for the RFC 6598 shared address space used by
+    // carrier-grade NAT, 100.64.0.0/10.
+    pub fn is_shared(&self) -> bool {
+        self.classification() == Classification::Shared
+    }
+
+    // is_loopback returns true for addresses in 127.0.0.0/8.
+    pub const fn is_loopback(&self) -> bool {
+        self.0[0] == 127
+    }
+
+    // is_link_local returns true for addresses in 169.254.0.0/16.
+    pub const fn is_link_local(&self) -> bool {
+        self.0[0] == 169 && self.0[1] == 254
+    }
+
+    // is_documentation returns true for TEST-NET-1, -2 and -3.
+    pub fn is_documentation(&self) -> bool {
+        self.classification() == Classification::Documentation
+    }
+
+    // is_benchmarking returns true for addresses in 198.18.0.0/15.
+    pub fn is_benchmarking(&self) -> bool {
+        self.classification() == Classification::Benchmarking
+    }
+
+    // is_reserved returns true for addresses in 240.0.0.0/4, other than the
+    // limited broadcast address.
+    pub fn is_reserved(&self) -> bool {
+        self.classification() == Classification::Reserved
+    }
+
+    // is_broadcast returns true for the limited broadcast address,
+    // 255.255.255.255.
+    pub const fn is_broadcast(&self) -> bool {
+        self.to_bits() == u32::MAX
+    }
+
+    // is_multicast returns true for addresses in 224.0.0.0/4.
+    pub const fn is_multicast(&self) -> bool {
+        self.0[0] >> 4 == 0b1110
+    }
+}
+
+#[cfg(test)]
+mod special_tests {
+    use super::{Classification, MulticastScope, special_purpose_registry};
+
+    use crate::testing::ipv4::addr;
+
+    #[test]
+    fn test_registry() {
+        let registry = special_purpose_registry();
+        assert_eq!(registry.len(), 26);
+
+        let private: Vec<String> = registry
+            .iter()
+            .filter(|e| e.classification == Classification::Private)
+            .map(|e| e.prefix.to_string())
+            .collect();
+        assert_eq!(private, ["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16"]);
+
+        let loopback = addr("127.0.0.1").special_purpose().unwrap();
+        assert_eq!(loopback.name, "Loopback");
+        assert_eq!(loopback.rfc, "[RFC1122], Section 3.2.1.3");
+        assert_eq!(loopback.source, Some(false));
+        assert_eq!(loopback.reserved_by_protocol, Some(true));
+
+        let this_network = addr("0.1.2.3").special_purpose().unwrap();
+        assert_eq!(this_network.name, "This network");
+        let this_host = addr("0.0.0.0").special_purpose().unwrap();
+        assert_eq!(this_host.prefix.to_string(), "0.0.0.0/32");
+
+        let relay = addr("192.88.99.1").special_purpose().unwrap();
+        assert_eq!(relay.terminated, Some("2015-03"));
+        assert_eq!(relay.globally_reachable, None);
+
+        let pcp = addr("192.0.0.9").special_purpose().unwrap();
+        assert_eq!(pcp.globally_reachable, Some(true));
+        assert_eq!(addr("8.8.8.8").special_purpose(), None);
+    }
+
+    #[test]
+    fn test_classification() {
+        let cases = Vec::from([
+            ("0.0.0.0", Classification::ThisHost),
+            ("0.0.0.1", Classification::ThisNetwork),
+            ("10.1.2.3", Classification::Private),
+            ("172.31.255.255", Classification::Private),
+            ("172.32.0.0", Classification::Global),
+            ("192.168.0.1", Classification::Private),
+            ("100.64.0.1", Classification::Shared),
+            ("100.128.0.1", Classification::Global),
+            ("127.0.0.1", Classification::Loopback),
+            ("169.254.1.1", Classification::LinkLocal),
+            ("192.0.0.5", Classification::ServiceContinuity),
+            ("192.0.0.8", Classification::Dummy),
+            ("192.0.0.9", Classification::PcpAnycast),
+            ("192.0.0.10", Classification::TurnAnycast),
+            ("192.0.0.11", Classification::ProtocolAssignments),
+            ("192.0.0.170", Classification::Nat64Discovery),
+            ("192.0.0.171", Classification::Nat64Discovery),
+            ("192.0.2.1", Classification::Documentation),
+            ("198.51.100.1", Classification::Documentation),
+            ("203.0.113.1", Classification::Documentation),
+            ("192.31.196.1", Classification::As112),
+            ("192.175.48.1", Classification::As112),
+            ("192.52.193.1", Classification::Amt),
+            ("192.88.99.1", Classification::SixToFourRelayAnycast),
+            ("192.88.99.2", Classification::SixA44Relay),
+            ("198.19.255.255", Classification::Benchmarking),
+            ("240.0.0.1", Classification::Reserved),
+            ("255.255.255.254", Classification::Reserved),
+            ("255.255.255.255", Classification::Broadcast),
+            (
+                "224.0.0.251",
+                Classification::Multicast(MulticastScope::LocalNetworkControl),
+            ),
+            (
+                "224.0.1.1",
+                Classification::Multicast(MulticastScope::InternetworkControl),
+            ),
+            (
+                "232.1.1.1",
+                Classification::Multicast(MulticastScope::SourceSpecific),
+            ),
+            ("233.1.1.1", Classification::Multicast(MulticastScope::Glop)),
+            (
+                "233.252.0.1",
+                Classification::Multicast(MulticastScope::Documentation),
+            ),
+            (
+                "234.1.1.1",
+                Classification::Multicast(MulticastScope::UnicastPrefixBased),
+            ),
+            (
+                "239.192.1.1",
+                Classification::Multicast(MulticastScope::OrganizationLocal),
+            ),
+            (
+                "239.255.255.250",
+                Classification::Multicast(MulticastScope::Local),
+            ),
+            (
+                "239.1.1.1",
+                Classification::Multicast(MulticastScope::AdminScoped),
+            ),
+            (
+                "225.1.1.1",
+                Classification::Multicast(MulticastScope::Global),
+            ),
+            ("8.8.8.8", Classification::Global),
+            ("1.1.1.1", Classification::Global),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(addr(s).classification(), want, "classification of {}", s);
+        }
+    }
+
+    #[test]
+    fn test_is_global() {
+        let globals = Vec::from([
+            "8.8.8.8",
+            "1.1.1.1",
+            "192.0.0.9",
+            "192.0.0.10",
+            "192.31.196.1",
+            "192.175.48.1",
+            "224.0.1.1",
+            "232.1.1.1",
+        ]);
+        let locals = Vec::from([
+            "0.0.0.0",
+            "10.0.0.1",
+            "100.64.0.1",
+            "127.0.0.1",
+            "169.254.0.1",
+            "192.0.0.1",
+            "192.0.2.1",
+            "192.88.99.1",
+            "192.88.99.2",
+            "192.168.1.1",
+            "198.18.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+            "224.0.0.1",
+            "239.255.255.250",
+        ]);
+
+        for s in globals {
+            assert!(addr(s).is_global(), "{} should be global", s);
+        }
+        for s in locals {
+            assert!(!addr(s).is_global(), "{} should not be global", s);
+        }
+
+        assert!(addr("0.0.0.0").is_unspecified());
+        assert!(addr("0.1.0.0").is_this_network());
+        assert!(addr("10.0.0.1").is_private());
+        assert!(addr("100.100.0.1").is_shared());
+        assert!(addr("127.1.2.3").is_loopback());
+        assert!(addr("169.254.9.9").is_link_local());
+        assert!(addr("203.0.113.9").is_documentation());
+        assert!(addr("198.18.0.1").is_benchmarking());
+        assert!(addr("250.0.0.1").is_reserved());
+        assert!(!addr("255.255.255.255").is_reserved());
+        assert!(addr("255.255.255.255").is_broadcast());
+        assert!(addr("239.0.0.1").is_multicast());
+        assert!(!addr("240.0.0.1").is_multicast());
+    }
+}
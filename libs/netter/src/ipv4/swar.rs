@@ -0,0 +1,252 @@
+// swar.rs is the SWAR (SIMD within a register) fast path for dotted quads.
+// It only handles the common case, four one to three digit octets without
+// leading zeros and each at most 255, and returns None for anything else so
+// that the scalar scanner can parse it or report exactly what is wrong. That
+// split keeps the errors in one place and makes every backend give the same
+// result as the scalar scanner for every input.
+
+// MIN_LEN and MAX_LEN are the lengths of the shortest and longest dotted
+// quads the fast paths handle, "0.0.0.0" and "255.255.255.255".
+pub(super) const MIN_LEN: usize = 7;
+pub(super) const MAX_LEN: usize = 15;
+
+// ONES and HIGH have the lowest and the highest bit of every byte set.
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+// parse returns the octets of input if it is a dotted quad the fast path
+// handles, and None otherwise.
+pub(super) fn parse(input: &[u8]) -> Option<[u8; 4]> {
+    let n = input.len();
+    if !(MIN_LEN..=MAX_LEN).contains(&n) {
+        return None;
+    }
+
+    let [lo, hi] = load(input);
+
+    // every byte of the input has to be a digit or a dot. The zero bytes
+    // past the end are neither, so they are masked off.
+    let (lo_dots, lo_digits) = classify(lo);
+    let (hi_dots, hi_digits) = classify(hi);
+    let dots = gather(lo_dots) | gather(hi_dots) << 8;
+    let valid = dots | gather(lo_digits) | gather(hi_digits) << 8;
+    let mask = (1u32 << n) - 1;
+    if valid & mask != mask {
+        return None;
+    }
+
+    let lens = split(input, dots)?;
+    let bytes = u128::from(lo) | u128::from(hi) << 64;
+    let mut octets = [0u8; 4];
+    let mut start = 0;
+    for (i, len) in lens.into_iter().enumerate() {
+        octets[i] = octet((bytes >> (8 * start)) as u32, len)?;
+        start += len + 1;
+    }
+    Some(octets)
+}
+
+// load reads an input of MIN_LEN to MAX_LEN bytes as two little-endian
+// words, zero past its end. Copying a short slice into a buffer costs a
+// call to memcpy, so the words are put together from two overlapping loads
+// instead, one from the start of the input and one from its end, with the
+// overlap shifted out of the second.
+pub(super) fn load(input: &[u8]) -> [u64; 2] {
+    let n = input.len();
+    debug_assert!((MIN_LEN..=MAX_LEN).contains(&n));
+    if n < 8 {
+        let head = u32::from_le_bytes(input[..4].try_into().unwrap());
+        let tail = u32::from_le_bytes(input[n - 4..].try_into().unwrap());
+        return [u64::from(head) | u64::from(tail) << (8 * (n - 4)), 0];
+    }
+    let head = u64::from_le_bytes(input[..8].try_into().unwrap());
+    let tail = u64::from_le_bytes(input[n - 8..].try_into().unwrap());
+    [head, tail.checked_shr(8 * (16 - n) as u32).unwrap_or(0)]
+}
+
+// classify returns the bytes of w that are dots and the bytes that are ASCII
+// digits, as the high bit of each byte.
+fn classify(w: u64) -> (u64, u64) {
+    // a byte is a dot if it is zero after xoring with dots. Adding 0x7f to
+    // the low seven bits carries into the high bit unless they are all zero,
+    // which finds the zero bytes without a carry crossing into the next byte.
+    let x = w ^ (ONES * b'.' as u64);
+    let nonzero = (((x & !HIGH) + !HIGH) | x) & HIGH;
+    let dots = !nonzero & HIGH;
+
+    // a byte below 0x80 is at least b'0' if adding 0x80 - b'0' sets its high
+    // bit, and likewise for b'9' + 1. Neither sum can carry out of the byte.
+    let low = w & !HIGH;
+    let ge_zero = (low + ONES * (0x80 - b'0') as u64) & HIGH;
+    let gt_nine = (low + ONES * (0x80 - b'9' - 1) as u64) & HIGH;
+    let digits = ge_zero & !gt_nine & !w;
+
+    (dots, digits)
+}
+
+// gather packs the high bit of each byte of m into one bit each, the bit of
+// byte i becoming bit i of the result. The multiplier moves bit 8i to bit
+// 56 + i, and no two products land on the same bit, so nothing carries.
+fn gather(m: u64) -> u32 {
+    ((m >> 7).wrapping_mul(0x0102_0408_1020_4080) >> 56) as u32
+}
+
+// split finds the four octets of a dotted quad of length input.len() whose
+// dots are the set bits of dots, and returns their lengths. It returns None
+// unless there are exactly three dots and every octet has one to three
+// digits without a leading zero.
+pub(super) fn split(input: &[u8], dots: u32) -> Option<[usize; 4]> {
+    if dots.count_ones() != 3 {
+        return None;
+    }
+    let first = dots.trailing_zeros() as usize;
+    let rest = dots & (dots - 1);
+    let second = rest.trailing_zeros() as usize;
+    let third = (rest & (rest - 1)).trailing_zeros() as usize;
+
+    let lens = [
+        first,
+        second - first - 1,
+        third - second - 1,
+        input.len() - third - 1,
+    ];
+    let starts = [0, first + 1, second + 1, third + 1];
+    for (len, start) in lens.into_iter().zip(starts) {
+        if !(1..=3).contains(&len) || (len > 1 && input[start] == b'0') {
+            return None;
+        }
+    }
+    Some(lens)
+}
+
+// octet returns the value of the len digits at the bottom of word, or None
+// if it is more than 255. The word is little-endian, which puts the most
+// significant digit in the lowest byte, so the digits are moved up to make
+// missing leading digits read as zeros. Then each step combines pairs of
+// neighbouring lanes, tens with ones and then hundreds with the rest.
+fn octet(word: u32, len: usize) -> Option<u8> {
+    let shift = 8 * (4 - len) as u32;
+    // the digits are all at least b'0', so subtracting cannot borrow into
+    // them from the bytes below; bytes past the octet are masked off.
+    let x = (word.wrapping_sub(0x3030_3030) & (u32::MAX >> shift)) << shift;
+    let x = (x.wrapping_mul(10) + (x >> 8)) & 0x00ff_00ff;
+    let value = (x.wrapping_mul(100) + (x >> 16)) & 0xffff;
+    u8::try_from(value).ok()
+}
+
+// SHUFFLES holds, for every combination of octet lengths, where each digit
+// of the input goes when the digits are laid out four lanes to an octet:
+// hundreds, tens, ones and a zero, with missing leading digits zero too. A
+// lane of 0x80 is zeroed by a byte shuffle. The entry for lengths [a, b, c,
+// d] is at shuffle_index.
+pub(super) const SHUFFLES: [[u8; 16]; 81] = shuffles();
+
+// shuffle_index returns the index in SHUFFLES of the layout for lens.
+pub(super) fn shuffle_index(lens: [usize; 4]) -> usize {
+    (lens[0] - 1) * 27 + (lens[1] - 1) * 9 + (lens[2] - 1) * 3 + (lens[3] - 1)
+}
+
+const fn shuffles() -> [[u8; 16]; 81] {
+    let mut table = [[0x80; 16]; 81];
+    let mut i = 0;
+    while i < 81 {
+        let lens = [i / 27 + 1, i / 9 % 3 + 1, i / 3 % 3 + 1, i % 3 + 1];
+        let mut start = 0;
+        let mut k = 0;
+        while k < 4 {
+            let mut j = 0;
+            while j < lens[k] {
+                table[i][4 * k + 3 - lens[k] + j] = (start + j) as u8;
+                j += 1;
+            }
+            start += lens[k] + 1;
+            k += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod swar_tests {
+    use super::{MAX_LEN, MIN_LEN, SHUFFLES, classify, gather, load, parse, shuffle_index, split};
+
+    #[test]
+    fn test_load() {
+        let input: Vec<u8> = (1..=MAX_LEN as u8).collect();
+        for n in MIN_LEN..=MAX_LEN {
+            let mut buf = [0u8; 16];
+            buf[..n].copy_from_slice(&input[..n]);
+            let want = [
+                u64::from_le_bytes(buf[..8].try_into().unwrap()),
+                u64::from_le_bytes(buf[8..].try_into().unwrap()),
+            ];
+            assert_eq!(load(&input[..n]), want, "load of {} bytes", n);
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        // every byte value in every position of a word.
+        for b in 0..=255u8 {
+            for pos in 0..8 {
+                let mut bytes = *b"1.2.3.4.";
+                bytes[pos] = b;
+                let (dots, digits) = classify(u64::from_le_bytes(bytes));
+                let want_dots = bytes
+                    .iter()
+                    .enumerate()
+                    .fold(0, |m, (i, &c)| m | u32::from(c == b'.') << i);
+                let want_digits = bytes
+                    .iter()
+                    .enumerate()
+                    .fold(0, |m, (i, &c)| m | u32::from(c.is_ascii_digit()) << i);
+                assert_eq!(gather(dots), want_dots, "dots of {:?}", bytes);
+                assert_eq!(gather(digits), want_digits, "digits of {:?}", bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let cases = Vec::from([
+            ("1.2.3.4", Some([1, 1, 1, 1])),
+            ("255.255.255.255", Some([3, 3, 3, 3])),
+            ("10.0.100.1", Some([2, 1, 3, 1])),
+            ("0.0.0.0", Some([1, 1, 1, 1])),
+            ("01.2.3.4", None),
+            ("1.2.3.0004", None),
+            ("1..3.4", None),
+            ("1.2.3", None),
+            ("1.2.3.4.", None),
+        ]);
+
+        for (s, want) in cases {
+            let dots = s
+                .bytes()
+                .enumerate()
+                .fold(0, |m, (i, c)| m | u32::from(c == b'.') << i);
+            assert_eq!(split(s.as_bytes(), dots), want, "split of {:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_shuffles() {
+        // the layout for lengths [1, 2, 3, 1], as in "1.23.456.7".
+        let shuffle = SHUFFLES[shuffle_index([1, 2, 3, 1])];
+        let x = 0x80;
+        assert_eq!(shuffle, [x, x, 0, x, x, 2, 3, x, 5, 6, 7, x, x, x, 9, x]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(b"1.23.456.7"), None);
+        assert_eq!(parse(b"1.23.45.7"), Some([1, 23, 45, 7]));
+        assert_eq!(parse(b"255.255.255.255"), Some([255; 4]));
+        assert_eq!(parse(b"0.0.0.0"), Some([0; 4]));
+        assert_eq!(parse(b"1.2.3.4\xff"), None);
+        assert_eq!(parse(b"1.2.3.4 "), None);
+        assert_eq!(parse(b"1.2.3.256"), None);
+        assert_eq!(parse(b"1.2.3.4567"), None);
+    }
+}
@@ -0,0 +1,378 @@
+use super::{Addr, InvalidAddrErr, Result};
+
+// Form is how many dot-separated parts an address was written with. Parts
+// before the last one are single octets, and the last part fills all of the
+// remaining bytes, so "127.1" is 127.0.0.1 and "2130706433" is 127.0.0.1 too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Form {
+    // a single 32-bit number, e.g. "2130706433".
+    OnePart,
+    // an octet and a 24-bit number, e.g. "127.1".
+    TwoPart,
+    // two octets and a 16-bit number, e.g. "127.0.1".
+    ThreePart,
+    // the usual dotted quad, e.g. "127.0.0.1".
+    DottedQuad,
+}
+
+// Notation describes how an address parsed by parse_whatwg was written, so
+// that callers can tell "127.0.0.1" apart from "0x7f.1" even though both
+// produce the same Addr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Notation {
+    // form is how many parts the address had.
+    pub form: Form,
+    // hex is true if any part was hexadecimal ("0x7f").
+    pub hex: bool,
+    // octal is true if any part was octal ("0177").
+    pub octal: bool,
+    // trailing_dot is true if the address ended in a dot ("127.0.0.1.").
+    pub trailing_dot: bool,
+}
+
+impl Notation {
+    // is_canonical returns true if the address was written as a plain
+    // decimal dotted quad, i.e. the way Addr's Display writes it.
+    pub fn is_canonical(&self) -> bool {
+        self.form == Form::DottedQuad && !self.hex && !self.octal && !self.trailing_dot
+    }
+}
+
+// parse_whatwg will parse a string with the WHATWG URL Standard's IPv4 parser
+// (https://url.spec.whatwg.org/#concept-ipv4-parser), which accepts the same
+// numeric forms as inet_aton(3) plus a trailing dot:
+//
+//   - 1 to 4 dot-separated parts, where the last part fills the remaining
+//     bytes of the address ("127.1", "2130706433").
+//   - parts in hexadecimal with a "0x" or "0X" prefix ("0x7f.1"); "0x" on
+//     its own is zero.
+//   - parts in octal with a leading zero ("0177.0.0.1", "017700000001").
+//   - a single trailing dot ("127.0.0.1.").
+//
+// The address is always returned as the canonical Addr, along with the
+// Notation it was written in. The input has to already be an ASCII host, as
+// produced by the URL host parser after percent-decoding and domain-to-ASCII.
+//
+// A string that fails to parse is not necessarily a domain name: the URL
+// Standard treats any host for which ends_in_a_number is true as an IPv4
+// address, and fails the whole URL if parse_whatwg then fails.
+pub fn parse_whatwg(input: &str) -> Result<(Addr, Notation)> {
+    let bytes = input.as_bytes();
+
+    // a single trailing dot is dropped, unless it is the only thing there.
+    let trailing_dot = bytes.len() > 1 && bytes[bytes.len() - 1] == b'.';
+    let body = if trailing_dot {
+        &bytes[..bytes.len() - 1]
+    } else {
+        bytes
+    };
+
+    // numbers holds the value of each part and parts counts them. Values
+    // are saturated rather than allowed to overflow, since anything that
+    // does not fit in a u32 is out of range anyway.
+    let mut numbers = [0u64; 4];
+    let mut offsets = [0usize; 4];
+    let mut parts = 0;
+    let mut hex = false;
+    let mut octal = false;
+
+    for (start, part) in split_parts(body) {
+        if parts == 4 {
+            return Err(InvalidAddrErr::TooManyOctets {
+                offset: start - 1,
+                octet: 3,
+            });
+        }
+
+        let (value, radix) = parse_number(part, start, parts)?;
+        hex |= radix == 16;
+        octal |= radix == 8;
+        numbers[parts] = value;
+        offsets[parts] = start;
+        parts += 1;
+    }
+
+    // every part but the last one is a single octet, and the last one fills
+    // whatever is left: 32 bits for one part down to 8 bits for four.
+    let mut bits: u32 = 0;
+    for i in 0..parts {
+        let last = i == parts - 1;
+        let limit: u64 = if last { 1 << (8 * (4 - i)) } else { 256 };
+        if numbers[i] >= limit {
+            return Err(InvalidAddrErr::OctetOutOfRange {
+                offset: offsets[i],
+                octet: i,
+            });
+        }
+
+        if last {
+            bits |= numbers[i] as u32;
+        } else {
+            bits |= (numbers[i] as u32) << (8 * (3 - i));
+        }
+    }
+
+    let form = match parts {
+        1 => Form::OnePart,
+        2 => Form::TwoPart,
+        3 => Form::ThreePart,
+        _ => Form::DottedQuad,
+    };
+
+    Ok((
+        Addr::from_bits(bits),
+        Notation {
+            form,
+            hex,
+            octal,
+            trailing_dot,
+        },
+    ))
+}
+
+// ends_in_a_number implements the URL Standard's "ends in a number checker"
+// (https://url.spec.whatwg.org/#ends-in-a-number-checker). A host for which
+// it returns true must be parsed as an IPv4 address, so if parse_whatwg
+// fails on it the host is invalid rather than a domain name: "1.2.3.09" is an
+// error, while "1.2.3.foo" is a domain.
+pub fn ends_in_a_number(input: &str) -> bool {
+    let mut bytes = input.as_bytes();
+
+    // a trailing dot is ignored, unless it is the only part.
+    if let Some((b'.', rest)) = bytes.split_last() {
+        if rest.is_empty() {
+            return false;
+        }
+        bytes = rest;
+    }
+
+    let last = match bytes.iter().rposition(|&b| b == b'.') {
+        Some(i) => &bytes[i + 1..],
+        None => bytes,
+    };
+
+    if !last.is_empty() && last.iter().all(|b| b.is_ascii_digit()) {
+        return true;
+    }
+
+    parse_number(last, 0, 0).is_ok()
+}
+
+// split_parts splits s on dots, yielding each part along with the byte
+// offset at which it starts.
+fn split_parts(s: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut start = 0;
+    s.split(|&b| b == b'.').map(move |part| {
+        let offset = start;
+        start += part.len() + 1;
+        (offset, part)
+    })
+}
+
+// parse_number implements the URL Standard's "IPv4 number parser" for a
+// single part, returning its value and the radix it was written in. start is
+// the byte offset of the part and octet its index, for error reporting.
+fn parse_number(part: &[u8], start: usize, octet: usize) -> Result<(u64, u32)> {
+    if part.is_empty() {
+        return Err(InvalidAddrErr::EmptyOctet {
+            offset: start,
+            octet,
+        });
+    }
+
+    // a "0x" prefix means hex and a leading zero means octal. What is left
+    // after the prefix may be empty, which is zero.
+    let (digits, radix, skip) = match part {
+        [b'0', b'x' | b'X', rest @ ..] => (rest, 16, 2),
+        [b'0', rest @ ..] if !rest.is_empty() => (rest, 8, 1),
+        _ => (part, 10, 0),
+    };
+
+    let mut value: u64 = 0;
+    for (i, &b) in digits.iter().enumerate() {
+        let offset = start + skip + i;
+        let digit = match (b as char).to_digit(radix) {
+            Some(digit) => digit,
+            None if radix == 8 && b.is_ascii_digit() => {
+                return Err(InvalidAddrErr::InvalidOctalDigit { offset, octet });
+            }
+            None => return Err(InvalidAddrErr::InvalidChar { offset, octet }),
+        };
+        value = value
+            .saturating_mul(radix as u64)
+            .saturating_add(digit as u64);
+    }
+
+    Ok((value, radix))
+}
+
+#[cfg(test)]
+mod whatwg_tests {
+    use super::{Form, Notation, ends_in_a_number, parse_whatwg};
+    use crate::ipv4::{Addr, InvalidAddrErr};
+
+    // WPT_VECTORS are IPv4 hosts from the WPT url tests
+    // (url/resources/urltestdata.json), after percent-decoding, plus boundary
+    // cases for each form. Each has the address it must produce, or None if
+    // the URL must fail to parse.
+    const WPT_VECTORS: &[(&str, Option<&str>)] = &[
+        ("192.0x00A80001", Some("192.168.0.1")),
+        ("0xc0.0250.01", Some("192.168.0.1")),
+        ("0Xc0.0250.01", Some("192.168.0.1")),
+        ("192.168.0.257", None),
+        ("192.168.0.1 hello", None),
+        ("10.0.0.255", Some("10.0.0.255")),
+        ("10.0.0.256", None),
+        ("256.256.256.256", None),
+        ("1.2.3.4.", Some("1.2.3.4")),
+        ("1.2.3.4.5", None),
+        ("1.2.3.4.5.", None),
+        ("0..0x300", None),
+        ("0..0x300.", None),
+        ("09.2.3.4", None),
+        ("01.2.3.4", Some("1.2.3.4")),
+        ("1.2.3.08", None),
+        ("1.2.3.09", None),
+        ("0x.0x.0", Some("0.0.0.0")),
+        ("0x100.2.3.4", None),
+        ("127.0.0x0.1", Some("127.0.0.1")),
+        ("127.1", Some("127.0.0.1")),
+        ("0x7f.1", Some("127.0.0.1")),
+        ("2130706433", Some("127.0.0.1")),
+        ("017700000001", Some("127.0.0.1")),
+        ("0xffffffff", Some("255.255.255.255")),
+        ("4294967295", Some("255.255.255.255")),
+        ("4294967296", None),
+        ("0x100000000", None),
+        ("0xffffffff1", None),
+        ("0.0.0.0x100", None),
+        ("256", Some("0.0.1.0")),
+        ("0", Some("0.0.0.0")),
+        ("0x", Some("0.0.0.0")),
+        ("00", Some("0.0.0.0")),
+        ("1.0x", Some("1.0.0.0")),
+        ("1.65535", Some("1.0.255.255")),
+        ("1.2.65535", Some("1.2.255.255")),
+        ("1.2.65536", None),
+        ("1.16777216", None),
+    ];
+
+    #[test]
+    fn test_wpt_vectors() {
+        for (input, want) in WPT_VECTORS {
+            let got = parse_whatwg(input).map(|(addr, _)| addr.to_string()).ok();
+            assert_eq!(got.as_deref(), *want, "parse_whatwg({:?})", input);
+        }
+    }
+
+    #[test]
+    fn test_notation() {
+        let cases = Vec::from([
+            ("127.0.0.1", Form::DottedQuad, false, false, false),
+            ("127.0.0.1.", Form::DottedQuad, false, false, true),
+            ("0x7f.0.0.1", Form::DottedQuad, true, false, false),
+            ("0177.0.1", Form::ThreePart, false, true, false),
+            ("0x7f.01", Form::TwoPart, true, true, false),
+            ("2130706433", Form::OnePart, false, false, false),
+        ]);
+
+        for (input, form, hex, octal, trailing_dot) in cases {
+            let (addr, notation) = parse_whatwg(input).unwrap();
+            assert_eq!(addr, Addr::LOCALHOST, "address of {:?}", input);
+            assert_eq!(
+                notation,
+                Notation {
+                    form,
+                    hex,
+                    octal,
+                    trailing_dot
+                },
+                "notation of {:?}",
+                input
+            );
+            assert_eq!(
+                notation.is_canonical(),
+                input == "127.0.0.1",
+                "is_canonical of {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_whatwg_errors() {
+        let cases = Vec::from([
+            (
+                "1.2.3.4.5",
+                InvalidAddrErr::TooManyOctets {
+                    offset: 7,
+                    octet: 3,
+                },
+            ),
+            (
+                "1..3",
+                InvalidAddrErr::EmptyOctet {
+                    offset: 2,
+                    octet: 1,
+                },
+            ),
+            (
+                "1.2.3.09",
+                InvalidAddrErr::InvalidOctalDigit {
+                    offset: 7,
+                    octet: 3,
+                },
+            ),
+            (
+                "1.0xg",
+                InvalidAddrErr::InvalidChar {
+                    offset: 4,
+                    octet: 1,
+                },
+            ),
+            (
+                "1.256.3",
+                InvalidAddrErr::OctetOutOfRange {
+                    offset: 2,
+                    octet: 1,
+                },
+            ),
+            (
+                "1.2.65536",
+                InvalidAddrErr::OctetOutOfRange {
+                    offset: 4,
+                    octet: 2,
+                },
+            ),
+        ]);
+
+        for (input, want) in cases {
+            assert_eq!(parse_whatwg(input), Err(want), "parse_whatwg({:?})", input);
+        }
+    }
+
+    #[test]
+    fn test_ends_in_a_number() {
+        let cases = Vec::from([
+            ("1.2.3.4", true),
+            ("1.2.3.4.", true),
+            ("1.2.3.09", true),
+            ("foo.0x1f", true),
+            ("foo.0x", true),
+            ("example.com", false),
+            ("1.2.3.foo", false),
+            ("foo.0xg", false),
+            (".", false),
+            ("", false),
+        ]);
+
+        for (input, want) in cases {
+            assert_eq!(
+                ends_in_a_number(input),
+                want,
+                "ends_in_a_number({:?})",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,1431 @@
+use core::fmt;
+use core::net::Ipv6Addr;
+use core::str::FromStr;
+
+use crate::ascii::find;
+use crate::ipv4;
+
+mod arith;
+pub(crate) mod defang;
+mod embed;
+mod prefix;
+mod range;
+mod reverse;
+mod socket;
+mod special;
+mod zone;
+
+pub use crate::ipv4::HostBits;
+pub use defang::Defanged;
+#[cfg(feature = "alloc")]
+pub use defang::parse_defanged;
+pub use prefix::{InvalidPrefixErr, Prefix, Subnets, Supernets, parse_prefix, parse_prefix_const};
+#[cfg(feature = "alloc")]
+pub use range::collapse_prefixes;
+pub use range::{AddrRange, Addrs, InvalidRangeErr, Prefixes, parse_range};
+pub use reverse::{
+    InvalidReverseNameErr, ReverseName, ReverseZone, parse_reverse_name, parse_reverse_zone,
+};
+pub use socket::{InvalidSocketAddrErr, SocketAddr, parse_socket};
+pub use special::{Classification, MulticastScope, SpecialPurpose, special_purpose_registry};
+pub use zone::{UriHost, Zone, ZoneName, Zones, parse_uri_host};
+
+type Result<T> = core::result::Result<T, InvalidAddrErr>;
+
+// InvalidAddrErr describes why a string is not a valid IPv6 address. Like
+// ipv4::InvalidAddrErr, every variant carries the byte offset in the input
+// where the problem was found and the index (0-7) of the group that was
+// being read at the time, or 8 once all the groups are read and the zone is
+// being checked. Errors in an embedded dotted-quad tail come from
+// the ipv4 scanner and are wrapped, with their offset into the whole input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidAddrErr {
+    // a character other than a hex digit, a colon or a dot was found.
+    InvalidChar { offset: usize, group: usize },
+    // a group had no digits, e.g. ":1::" or "1:::2".
+    EmptyGroup { offset: usize, group: usize },
+    // a group had more than four hex digits, e.g. "12345::".
+    GroupTooLong { offset: usize, group: usize },
+    // "::" appeared more than once, e.g. "1::2::3".
+    MultipleCompressions { offset: usize, group: usize },
+    // there were more than eight groups, or eight groups and a "::".
+    TooManyGroups { offset: usize, group: usize },
+    // the input ended before eight groups were read and there was no "::".
+    TooFewGroups { offset: usize, group: usize },
+    // a '%' was not followed by a zone, e.g. "fe80::1%".
+    EmptyZone { offset: usize, group: usize },
+    // a zone had a character other than the RFC 3986 unreserved ones, or
+    // was a number too large for an interface index.
+    InvalidZone { offset: usize, group: usize },
+    // a zone name was longer than ZoneName::MAX_LEN bytes.
+    ZoneTooLong { offset: usize, group: usize },
+    // the address had a zone and the ParseOptions do not allow one on it.
+    ZoneNotAllowed { offset: usize, group: usize },
+    // a zone in a URI host was introduced by a bare '%' rather than the
+    // "%25" that RFC 6874 requires, e.g. "[fe80::1%eth0]".
+    UnescapedZone { offset: usize, group: usize },
+    // a URI host was not enclosed in square brackets.
+    MissingBracket { offset: usize, group: usize },
+    // the embedded dotted-quad tail, e.g. the "1.2.3.256" in "::1.2.3.256",
+    // is not a valid IPv4 address.
+    Ipv4(ipv4::InvalidAddrErr),
+}
+
+impl InvalidAddrErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidAddrErr::InvalidChar { offset, .. }
+            | InvalidAddrErr::EmptyGroup { offset, .. }
+            | InvalidAddrErr::GroupTooLong { offset, .. }
+            | InvalidAddrErr::MultipleCompressions { offset, .. }
+            | InvalidAddrErr::TooManyGroups { offset, .. }
+            | InvalidAddrErr::TooFewGroups { offset, .. }
+            | InvalidAddrErr::EmptyZone { offset, .. }
+            | InvalidAddrErr::InvalidZone { offset, .. }
+            | InvalidAddrErr::ZoneTooLong { offset, .. }
+            | InvalidAddrErr::ZoneNotAllowed { offset, .. }
+            | InvalidAddrErr::UnescapedZone { offset, .. }
+            | InvalidAddrErr::MissingBracket { offset, .. } => offset,
+            InvalidAddrErr::Ipv4(err) => err.offset(),
+        }
+    }
+
+    // group returns the index (0-7) of the group that was being read when
+    // the error was found. For errors in an embedded dotted quad this is 6,
+    // the first of the two groups a dotted quad fills.
+    pub const fn group(&self) -> usize {
+        match *self {
+            InvalidAddrErr::InvalidChar { group, .. }
+            | InvalidAddrErr::EmptyGroup { group, .. }
+            | InvalidAddrErr::GroupTooLong { group, .. }
+            | InvalidAddrErr::MultipleCompressions { group, .. }
+            | InvalidAddrErr::TooManyGroups { group, .. }
+            | InvalidAddrErr::TooFewGroups { group, .. }
+            | InvalidAddrErr::EmptyZone { group, .. }
+            | InvalidAddrErr::InvalidZone { group, .. }
+            | InvalidAddrErr::ZoneTooLong { group, .. }
+            | InvalidAddrErr::ZoneNotAllowed { group, .. }
+            | InvalidAddrErr::UnescapedZone { group, .. }
+            | InvalidAddrErr::MissingBracket { group, .. } => group,
+            InvalidAddrErr::Ipv4(_) => 6,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidAddrErr::InvalidChar { .. } => "invalid character",
+            InvalidAddrErr::EmptyGroup { .. } => "empty group",
+            InvalidAddrErr::GroupTooLong { .. } => "group has more than four digits",
+            InvalidAddrErr::MultipleCompressions { .. } => "more than one '::'",
+            InvalidAddrErr::TooManyGroups { .. } => "too many groups",
+            InvalidAddrErr::TooFewGroups { .. } => "too few groups",
+            InvalidAddrErr::EmptyZone { .. } => "empty zone",
+            InvalidAddrErr::InvalidZone { .. } => "invalid zone",
+            InvalidAddrErr::ZoneTooLong { .. } => "zone name too long",
+            InvalidAddrErr::ZoneNotAllowed { .. } => "zone not allowed",
+            InvalidAddrErr::UnescapedZone { .. } => "unescaped zone delimiter",
+            InvalidAddrErr::MissingBracket { .. } => "missing bracket",
+            InvalidAddrErr::Ipv4(err) => err.reason(),
+        }
+    }
+
+    // shifted returns the error with its offset moved forward by start, for
+    // errors found in a slice that begins at byte start of the input.
+    pub(crate) const fn shifted(self, start: usize) -> InvalidAddrErr {
+        self.with_offset(self.offset() + start)
+    }
+
+    // with_offset returns the error with its offset replaced by offset, for
+    // errors whose position in the input is not a fixed distance from where
+    // they were found, such as in refanged text.
+    pub(crate) const fn with_offset(self, offset: usize) -> InvalidAddrErr {
+        use InvalidAddrErr::*;
+
+        match self {
+            InvalidChar { group, .. } => InvalidChar { offset, group },
+            EmptyGroup { group, .. } => EmptyGroup { offset, group },
+            GroupTooLong { group, .. } => GroupTooLong { offset, group },
+            MultipleCompressions { group, .. } => MultipleCompressions { offset, group },
+            TooManyGroups { group, .. } => TooManyGroups { offset, group },
+            TooFewGroups { group, .. } => TooFewGroups { offset, group },
+            EmptyZone { group, .. } => EmptyZone { offset, group },
+            InvalidZone { group, .. } => InvalidZone { offset, group },
+            ZoneTooLong { group, .. } => ZoneTooLong { offset, group },
+            ZoneNotAllowed { group, .. } => ZoneNotAllowed { offset, group },
+            UnescapedZone { group, .. } => UnescapedZone { offset, group },
+            MissingBracket { group, .. } => MissingBracket { offset, group },
+            Ipv4(err) => Ipv4(err.with_offset(offset)),
+        }
+    }
+
+    // diagnostic returns a rustc-style rendering of the error against the
+    // input it was produced from, with a caret under the offending part:
+    //
+    //   error: invalid ipv6 address: group has more than four digits
+    //    --> input:1:4
+    //     |
+    //   1 | 1::12345
+    //     |    ^^^^^ group 1 must be at most four hex digits
+    pub fn diagnostic<'a>(&self, input: &'a str) -> Diagnostic<'a> {
+        Diagnostic { err: *self, input }
+    }
+
+    // write_label writes the message printed next to the caret in a diagnostic.
+    fn write_label(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidAddrErr::InvalidChar { .. } => write!(f, "expected a hex digit, ':' or '.'"),
+            InvalidAddrErr::EmptyGroup { group, .. } => write!(f, "group {} has no digits", group),
+            InvalidAddrErr::GroupTooLong { group, .. } => {
+                write!(f, "group {} must be at most four hex digits", group)
+            }
+            InvalidAddrErr::MultipleCompressions { .. } => {
+                write!(f, "'::' can only be used once")
+            }
+            InvalidAddrErr::TooManyGroups { .. } => {
+                write!(f, "an address has at most 8 groups")
+            }
+            InvalidAddrErr::TooFewGroups { group, .. } => {
+                write!(f, "expected 8 groups or a '::', found {}", group)
+            }
+            InvalidAddrErr::EmptyZone { .. } => write!(f, "expected a zone after '%'"),
+            InvalidAddrErr::InvalidZone { .. } => {
+                write!(f, "expected an interface name or index")
+            }
+            InvalidAddrErr::ZoneTooLong { .. } => {
+                write!(f, "a zone name is at most {} bytes", ZoneName::MAX_LEN)
+            }
+            InvalidAddrErr::ZoneNotAllowed { .. } => {
+                write!(f, "a zone is not allowed on this address")
+            }
+            InvalidAddrErr::UnescapedZone { .. } => write!(f, "write '%' as '%25' in a URI"),
+            InvalidAddrErr::MissingBracket { .. } => {
+                write!(f, "a URI host is written as '[address]'")
+            }
+            InvalidAddrErr::Ipv4(err) => err.write_label(f),
+        }
+    }
+}
+
+impl fmt::Display for InvalidAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidAddrErr::Ipv4(err) => write!(
+                f,
+                "invalid ipv6 address string: embedded ipv4 {} at byte {} (octet {})",
+                err.reason(),
+                err.offset(),
+                err.octet()
+            ),
+            _ => write!(
+                f,
+                "invalid ipv6 address string: {} at byte {} (group {})",
+                self.reason(),
+                self.offset(),
+                self.group()
+            ),
+        }
+    }
+}
+
+impl core::error::Error for InvalidAddrErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidAddrErr::Ipv4(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// Diagnostic renders an InvalidAddrErr against its input. See
+// InvalidAddrErr::diagnostic.
+pub struct Diagnostic<'a> {
+    err: InvalidAddrErr,
+    input: &'a str,
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = match self.err {
+            InvalidAddrErr::GroupTooLong { offset, .. } => self
+                .input
+                .get(offset..)
+                .unwrap_or("")
+                .bytes()
+                .take_while(|b| b.is_ascii_hexdigit())
+                .count()
+                .max(1),
+            InvalidAddrErr::ZoneTooLong { offset, .. } => {
+                let rest = self.input.get(offset..).unwrap_or("");
+                rest.find(']').unwrap_or(rest.len())
+            }
+            InvalidAddrErr::Ipv4(err) => err.caret_width(self.input),
+            _ => 1,
+        };
+        crate::diagnostic::write_snippet(
+            f,
+            "ipv6 address",
+            self.err.reason(),
+            self.input,
+            self.err.offset(),
+            width,
+        )?;
+        self.err.write_label(f)
+    }
+}
+
+// Addr is an owned IPv6 address with an optional zone. The address is stored
+// as sixteen octets in network (big-endian) order, so the derived ordering
+// sorts addresses numerically, and addresses that differ only in their zone
+// sort next to each other with the unzoned one first. Two addresses with
+// different zones are not equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Addr {
+    octets: [u8; 16],
+    zone: Option<Zone>,
+}
+
+impl Addr {
+    // UNSPECIFIED is the address ::.
+    pub const UNSPECIFIED: Addr = Addr::from_octets([0; 16]);
+    // LOCALHOST is the loopback address ::1.
+    pub const LOCALHOST: Addr = Addr::from_bits(1);
+
+    // new builds an address from its eight 16-bit groups, e.g.
+    // Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(a: u16, b: u16, c: u16, d: u16, e: u16, f: u16, g: u16, h: u16) -> Addr {
+        Addr::from_segments([a, b, c, d, e, f, g, h])
+    }
+
+    // from_segments builds an address from its eight 16-bit groups.
+    pub const fn from_segments(segments: [u16; 8]) -> Addr {
+        let mut octets = [0u8; 16];
+        let mut i = 0;
+        while i < 8 {
+            let [hi, lo] = segments[i].to_be_bytes();
+            octets[2 * i] = hi;
+            octets[2 * i + 1] = lo;
+            i += 1;
+        }
+        Addr::from_octets(octets)
+    }
+
+    // from_octets builds an address from its sixteen octets in network order.
+    pub const fn from_octets(octets: [u8; 16]) -> Addr {
+        Addr { octets, zone: None }
+    }
+
+    // octets returns the sixteen octets of the address in network order.
+    pub const fn octets(&self) -> [u8; 16] {
+        self.octets
+    }
+
+    // segments returns the eight 16-bit groups of the address.
+    pub const fn segments(&self) -> [u16; 8] {
+        let mut segments = [0u16; 8];
+        let mut i = 0;
+        while i < 8 {
+            segments[i] = u16::from_be_bytes([self.octets[2 * i], self.octets[2 * i + 1]]);
+            i += 1;
+        }
+        segments
+    }
+
+    // from_bits builds an address from its big-endian u128 representation.
+    pub const fn from_bits(bits: u128) -> Addr {
+        Addr::from_octets(bits.to_be_bytes())
+    }
+
+    // to_bits returns the address as a big-endian u128. The zone is dropped.
+    pub const fn to_bits(&self) -> u128 {
+        u128::from_be_bytes(self.octets)
+    }
+
+    // zone returns the zone of the address, if it has one.
+    pub const fn zone(&self) -> Option<Zone> {
+        self.zone
+    }
+
+    // with_zone returns the address with its zone set to zone.
+    pub const fn with_zone(mut self, zone: Zone) -> Addr {
+        self.zone = Some(zone);
+        self
+    }
+
+    // without_zone returns the address with its zone removed.
+    pub const fn without_zone(mut self) -> Addr {
+        self.zone = None;
+        self
+    }
+}
+
+impl fmt::Display for Addr {
+    // fmt writes the address in the canonical text form of RFC 5952, with
+    // the zone, if there is one, after a '%' as in RFC 4007 section 11.
+    // Use uri_host for the RFC 6874 form.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_addr(f)?;
+        if let Some(zone) = self.zone {
+            write!(f, "%{}", zone)?;
+        }
+        Ok(())
+    }
+}
+
+impl Addr {
+    // write_addr writes the address without its zone in the canonical text
+    // form of RFC 5952: groups in lowercase hex without leading zeros, the
+    // longest run of two or more zero groups (the first one, if there is a
+    // tie) replaced by "::", and IPv4-mapped addresses with a dotted-quad
+    // tail.
+    fn write_addr(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // IPv4-mapped addresses are the one form RFC 5952 section 5 writes
+        // with a dotted-quad tail.
+        if let Some(v4) = self.to_ipv4_mapped() {
+            return write!(f, "::ffff:{}", v4);
+        }
+
+        let segments = self.segments();
+
+        // find the longest run of zero groups.
+        let (mut best_start, mut best_len) = (0, 0);
+        let (mut start, mut len) = (0, 0);
+        for (i, &segment) in segments.iter().enumerate() {
+            if segment == 0 {
+                if len == 0 {
+                    start = i;
+                }
+                len += 1;
+                if len > best_len {
+                    best_start = start;
+                    best_len = len;
+                }
+            } else {
+                len = 0;
+            }
+        }
+
+        // a single zero group is never compressed.
+        if best_len < 2 {
+            return write_groups(f, &segments);
+        }
+
+        write_groups(f, &segments[..best_start])?;
+        write!(f, "::")?;
+        write_groups(f, &segments[best_start + best_len..])
+    }
+}
+
+// write_groups writes groups in hex separated by colons.
+fn write_groups(f: &mut fmt::Formatter, groups: &[u16]) -> fmt::Result {
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            write!(f, ":")?;
+        }
+        write!(f, "{:x}", group)?;
+    }
+    Ok(())
+}
+
+impl FromStr for Addr {
+    type Err = InvalidAddrErr;
+
+    fn from_str(s: &str) -> Result<Addr> {
+        parse(s)
+    }
+}
+
+impl From<[u8; 16]> for Addr {
+    fn from(octets: [u8; 16]) -> Addr {
+        Addr::from_octets(octets)
+    }
+}
+
+impl From<Addr> for [u8; 16] {
+    fn from(addr: Addr) -> [u8; 16] {
+        addr.octets
+    }
+}
+
+impl From<[u16; 8]> for Addr {
+    fn from(segments: [u16; 8]) -> Addr {
+        Addr::from_segments(segments)
+    }
+}
+
+impl From<Addr> for [u16; 8] {
+    fn from(addr: Addr) -> [u16; 8] {
+        addr.segments()
+    }
+}
+
+impl From<u128> for Addr {
+    fn from(bits: u128) -> Addr {
+        Addr::from_bits(bits)
+    }
+}
+
+impl From<Addr> for u128 {
+    fn from(addr: Addr) -> u128 {
+        addr.to_bits()
+    }
+}
+
+impl From<Ipv6Addr> for Addr {
+    fn from(addr: Ipv6Addr) -> Addr {
+        Addr::from_octets(addr.octets())
+    }
+}
+
+impl From<Addr> for Ipv6Addr {
+    fn from(addr: Addr) -> Ipv6Addr {
+        Ipv6Addr::from(addr.octets)
+    }
+}
+
+// ParseOptions controls how lenient the scanner is, like ipv4::ParseOptions.
+// Options are built from one of the named profiles and can then be adjusted
+// one setting at a time:
+//
+//   let opts = ParseOptions::strict().zones(Zones::Scoped);
+//   let addr = opts.parse("fe80::1%eth0")?;
+//
+// Every parsing entry point in the module either takes a ParseOptions or
+// uses ParseOptions::default(), which is ParseOptions::std_compat().
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    zones: Zones,
+    host_bits: HostBits,
+}
+
+impl ParseOptions {
+    // strict accepts only the RFC 4291 text forms, without a zone. Prefixes
+    // must not have host bits set. std::net takes no zones either, so strict
+    // is another name for std_compat and compares equal to it.
+    pub const fn strict() -> ParseOptions {
+        ParseOptions::std_compat()
+    }
+
+    // std_compat accepts exactly what std::net::Ipv6Addr::from_str accepts,
+    // which has no zones. std has no prefix type, so prefixes must not have
+    // host bits set.
+    pub const fn std_compat() -> ParseOptions {
+        ParseOptions {
+            zones: Zones::Reject,
+            host_bits: HostBits::Reject,
+        }
+    }
+
+    // permissive allows a zone on any address. Host bits in prefixes are
+    // masked.
+    pub const fn permissive() -> ParseOptions {
+        ParseOptions {
+            zones: Zones::Any,
+            host_bits: HostBits::Mask,
+        }
+    }
+
+    // zones sets when an address may have a zone.
+    pub const fn zones(mut self, zones: Zones) -> ParseOptions {
+        self.zones = zones;
+        self
+    }
+
+    // get_zones returns when an address may have a zone.
+    pub const fn get_zones(&self) -> Zones {
+        self.zones
+    }
+
+    // host_bits sets how prefixes with host bits set are handled.
+    pub const fn host_bits(mut self, host_bits: HostBits) -> ParseOptions {
+        self.host_bits = host_bits;
+        self
+    }
+
+    // get_host_bits returns how prefixes with host bits set are handled.
+    pub const fn get_host_bits(&self) -> HostBits {
+        self.host_bits
+    }
+
+    // parse will parse a string into an Addr using these options.
+    pub fn parse(&self, ipstr: &str) -> Result<Addr> {
+        self.parse_ascii(ipstr.as_bytes())
+    }
+
+    // parse_ascii will parse a byte slice into an Addr using these options.
+    // See the parse_ascii function.
+    pub fn parse_ascii(&self, input: &[u8]) -> Result<Addr> {
+        scan(input, self, false).map(|(addr, _)| addr)
+    }
+
+    // parse_ascii_partial will parse an address at the start of a byte
+    // slice using these options. See the parse_ascii_partial function.
+    pub fn parse_ascii_partial(&self, input: &[u8]) -> Result<(Addr, usize)> {
+        scan(input, self, true)
+    }
+
+    // parse_const will parse a string into an Addr using these options, in
+    // a const context. See the parse_const function.
+    pub const fn parse_const(&self, ipstr: &str) -> Result<Addr> {
+        match scan(ipstr.as_bytes(), self, false) {
+            Ok((addr, _)) => Ok(addr),
+            Err(err) => Err(err),
+        }
+    }
+
+    // parse_defanged will parse a defanged address, as in threat-intel
+    // reports, into an Addr using these options. See the parse_defanged
+    // function.
+    #[cfg(feature = "alloc")]
+    pub fn parse_defanged(&self, s: &str) -> Result<Addr> {
+        defang::scan_defanged(s, self)
+    }
+
+    // parse_prefix will parse a string in CIDR notation into a Prefix using
+    // these options. Prefixes never have a zone, whatever the options say.
+    pub fn parse_prefix(&self, s: &str) -> core::result::Result<Prefix, InvalidPrefixErr> {
+        prefix::scan_prefix(s.as_bytes(), self)
+    }
+
+    // parse_prefix_const will parse a string in CIDR notation into a Prefix
+    // using these options, in a const context.
+    pub const fn parse_prefix_const(
+        &self,
+        s: &str,
+    ) -> core::result::Result<Prefix, InvalidPrefixErr> {
+        prefix::scan_prefix(s.as_bytes(), self)
+    }
+
+    // parse_range will parse a range of addresses into an AddrRange using
+    // these options. Ranges never have a zone, whatever the options say.
+    pub fn parse_range(&self, s: &str) -> core::result::Result<AddrRange, InvalidRangeErr> {
+        range::scan_range(s, self)
+    }
+
+    // parse_uri_host will parse an RFC 6874 URI host into an Addr using
+    // these options. See the parse_uri_host function.
+    pub fn parse_uri_host(&self, s: &str) -> Result<Addr> {
+        zone::scan_uri_host(s, self)
+    }
+
+    // parse_socket will parse a bracketed address and a port into a
+    // SocketAddr using these options. See the parse_socket function.
+    pub fn parse_socket(&self, s: &str) -> core::result::Result<SocketAddr, InvalidSocketAddrErr> {
+        socket::scan_socket(s, self)
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions::std_compat()
+    }
+}
+
+// parse will parse a string in any of the RFC 4291 section 2.2 text forms
+// into an Addr:
+//
+//   - eight groups of one to four hex digits, in either case:
+//     "2001:DB8:0:0:8:800:200C:417A".
+//   - a single "::" standing in for one or more zero groups: "2001:db8::1",
+//     "::1", "::".
+//   - the last two groups written as a dotted quad: "::ffff:192.0.2.1",
+//     "64:ff9b::192.0.2.1". The dotted quad is read by the ipv4 scanner
+//     with strict ParseOptions, so it has the same rules and errors as
+//     ipv4::parse.
+//
+// Any of them may be followed by a zone, "fe80::1%eth0", if the options
+// allow it. parse uses the default ParseOptions, which do not; see
+// ParseOptions::parse to pick a different profile.
+pub fn parse(ipstr: &str) -> Result<Addr> {
+    ParseOptions::default().parse(ipstr)
+}
+
+// parse_const will parse a string into an Addr in a const context, using
+// ParseOptions::std_compat, the default. It is what the ipv6! macro expands
+// to. A zone needs other options, as in
+//
+//   const ROUTER: Addr = match ParseOptions::permissive().parse_const("fe80::1%eth0") {
+//       Ok(addr) => addr,
+//       Err(_) => panic!("bad router address"),
+//   };
+pub const fn parse_const(ipstr: &str) -> Result<Addr> {
+    ParseOptions::std_compat().parse_const(ipstr)
+}
+
+// valid_ipv6 will parse a string and return a Result indicating if the
+// string is a valid RFC 4291 IPv6 address, like ipv4::valid_ipv4.
+pub fn valid_ipv6(ipstr: &str) -> Result<bool> {
+    scan(ipstr.as_bytes(), &ParseOptions::default(), false).map(|_| true)
+}
+
+// parse_ascii will parse a byte slice into an Addr, like parse does for a
+// string, without checking that the bytes are UTF-8 first. Any byte that is
+// not ASCII is an InvalidChar, or an InvalidZone in a zone.
+pub fn parse_ascii(input: &[u8]) -> Result<Addr> {
+    ParseOptions::default().parse_ascii(input)
+}
+
+// parse_ascii_partial will parse the address at the start of input and
+// return it with the number of bytes it took up, leaving the rest of the
+// input alone. The address ends at the first byte that cannot continue it:
+//
+//   - a byte that is not a hex digit, ':', '.' or '%', as in "::1]:80".
+//   - a ':' after the last group that fits, as in "1:2:3:4:5:6:7:8:80".
+//   - a single ':' not followed by a hex digit, as in "2001:db8::1: ok".
+//   - a '.' that does not start a valid dotted-quad tail, as in
+//     "2001:db8::1." at the end of a sentence.
+//   - in a zone, a byte that is not an RFC 3986 unreserved character.
+//
+// Everything before the end must still be a valid address: "1:2:3 " is
+// TooFewGroups at byte 5.
+pub fn parse_ascii_partial(input: &[u8]) -> Result<(Addr, usize)> {
+    ParseOptions::default().parse_ascii_partial(input)
+}
+
+// scan is the scanner behind all the parse functions. It reads the address
+// up to the '%' and then hands the rest to the zone scanner, and returns the
+// address and the offset at which it ends. If partial is false the address
+// has to take up the whole input; if it is true the address ends as
+// parse_ascii_partial describes. Like the ipv4 scanner it is a const fn, so
+// that addresses can also be parsed at compile time.
+const fn scan(input: &[u8], options: &ParseOptions, partial: bool) -> Result<(Addr, usize)> {
+    if partial {
+        let (groups, end) = match scan_groups(input, true) {
+            Ok(scanned) => scanned,
+            Err(err) => return Err(err),
+        };
+        let addr = Addr::from_segments(groups);
+        if end == input.len() || input[end] != b'%' {
+            return Ok((addr, end));
+        }
+        let zone = input.split_at(end + 1).1;
+        let mut len = 0;
+        while len < zone.len() && zone::is_unreserved(zone[len]) {
+            len += 1;
+        }
+        return match zone::scan_zone(addr, zone.split_at(len).0, end, end + 1, options) {
+            Ok(addr) => Ok((addr, end + 1 + len)),
+            Err(err) => Err(err),
+        };
+    }
+
+    let Some(at) = find(input, b'%') else {
+        return match scan_groups(input, false) {
+            Ok((groups, end)) => Ok((Addr::from_segments(groups), end)),
+            Err(err) => Err(err),
+        };
+    };
+    let (addr, zone) = input.split_at(at);
+    let groups = match scan_groups(addr, false) {
+        Ok((groups, _)) => groups,
+        Err(err) => return Err(err),
+    };
+    let zone = zone.split_at(1).1;
+    match zone::scan_zone(Addr::from_segments(groups), zone, at, at + 1, options) {
+        Ok(addr) => Ok((addr, input.len())),
+        Err(err) => Err(err),
+    }
+}
+
+// scan_groups is the single-pass scanner for the address itself. It returns
+// the eight groups of the address and the offset at which it ends if the
+// input is valid.
+const fn scan_groups(bytes: &[u8], partial: bool) -> Result<([u16; 8], usize)> {
+    // groups collects the groups as they are read, n counts them, and
+    // compressed is the number of groups read before the "::", if there was
+    // one. The groups after the "::" are moved into place at the end.
+    let mut groups = [0u16; 8];
+    let mut n = 0;
+    let mut compressed: Option<usize> = None;
+    let mut i = 0;
+
+    // a leading "::" has no group before it, so it is handled up front.
+    // Otherwise every colon follows a group.
+    if bytes.len() >= 2 && bytes[0] == b':' && bytes[1] == b':' {
+        compressed = Some(0);
+        i = 2;
+    }
+
+    while i < bytes.len() || !matches!(compressed, Some(at) if at == n) {
+        // read up to the end of the group. Hex digits are read even past
+        // four, so that the length error can point at the whole group.
+        let start = i;
+        let mut value: u32 = 0;
+        while i < bytes.len() && bytes[i].is_ascii_hexdigit() {
+            value = value << 4 | (bytes[i] as char).to_digit(16).unwrap();
+            i += 1;
+        }
+        let digits = i - start;
+
+        // a dot means this "group" is really the start of a dotted-quad
+        // tail, which takes up the last two groups and the rest of the input.
+        if i < bytes.len() && bytes[i] == b'.' {
+            // it needs two groups to itself, plus one for a "::" to stand
+            // in for.
+            let limit = if compressed.is_some() { 5 } else { 6 };
+            if n > limit {
+                return Err(InvalidAddrErr::TooManyGroups {
+                    offset: start,
+                    group: n,
+                });
+            }
+            let tail = bytes.split_at(start).1;
+            match ipv4::scan(tail, &ipv4::ParseOptions::strict(), partial) {
+                Ok(([a, b, c, d], len)) => {
+                    groups[n] = u16::from_be_bytes([a, b]);
+                    groups[n + 1] = u16::from_be_bytes([c, d]);
+                    n += 2;
+                    i = start + len;
+                    break;
+                }
+                // in a partial scan the dot ends the address instead, and
+                // the group before it is read as an ordinary group.
+                Err(_) if partial => {}
+                Err(err) => return Err(InvalidAddrErr::Ipv4(err.shifted(start))),
+            }
+        }
+
+        if digits == 0 {
+            // a partial scan can end right after a "::".
+            if partial && matches!(compressed, Some(at) if at == n) {
+                break;
+            }
+            if i == bytes.len() || bytes[i] == b':' {
+                return Err(InvalidAddrErr::EmptyGroup {
+                    offset: i,
+                    group: n,
+                });
+            }
+            return Err(InvalidAddrErr::InvalidChar {
+                offset: i,
+                group: n,
+            });
+        }
+        if digits > 4 {
+            return Err(InvalidAddrErr::GroupTooLong {
+                offset: start,
+                group: n,
+            });
+        }
+
+        // a "::" must stand in for at least one group.
+        if n == 7 && compressed.is_some() {
+            return Err(InvalidAddrErr::TooManyGroups {
+                offset: start,
+                group: n,
+            });
+        }
+
+        groups[n] = value as u16;
+        n += 1;
+
+        if i == bytes.len() || (partial && bytes[i] != b':') {
+            break;
+        }
+        if bytes[i] != b':' {
+            return Err(InvalidAddrErr::InvalidChar {
+                offset: i,
+                group: n - 1,
+            });
+        }
+
+        // a colon after the last group that fits: eight groups, or seven
+        // if a "::" already stands in for at least one.
+        if n == 8 || (n == 7 && compressed.is_some()) {
+            if partial {
+                break;
+            }
+            return Err(InvalidAddrErr::TooManyGroups {
+                offset: i,
+                group: n - 1,
+            });
+        }
+
+        // a partial scan ends at a single colon that no group follows.
+        if partial
+            && (i + 1 == bytes.len()
+                || !matches!(bytes[i + 1], b':' | b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F'))
+        {
+            break;
+        }
+        i += 1;
+
+        // a second colon makes this a "::".
+        if i < bytes.len() && bytes[i] == b':' {
+            if compressed.is_some() {
+                return Err(InvalidAddrErr::MultipleCompressions {
+                    offset: i - 1,
+                    group: n,
+                });
+            }
+            compressed = Some(n);
+            i += 1;
+        } else if i == bytes.len() {
+            // a single trailing colon.
+            return Err(InvalidAddrErr::EmptyGroup {
+                offset: i,
+                group: n,
+            });
+        }
+    }
+
+    match compressed {
+        // the "::" stands in for 8 - n zero groups, so the groups read after
+        // it move to the end.
+        Some(at) => {
+            let tail = n - at;
+            let mut k = 0;
+            while k < tail {
+                groups[7 - k] = groups[n - 1 - k];
+                k += 1;
+            }
+            while k < 8 - at {
+                groups[7 - k] = 0;
+                k += 1;
+            }
+        }
+        None if n < 8 => {
+            return Err(InvalidAddrErr::TooFewGroups {
+                offset: i,
+                group: n,
+            });
+        }
+        None => {}
+    }
+
+    Ok((groups, i))
+}
+
+#[cfg(test)]
+mod net_tests {
+    use super::{
+        Addr, InvalidAddrErr, ParseOptions, Zones, parse, parse_ascii, parse_ascii_partial,
+        valid_ipv6,
+    };
+    use crate::ipv4;
+    use crate::rng::Rng;
+    use crate::testing::ipv6::addr;
+    use std::net::Ipv6Addr;
+
+    // CONFORMANCE is a table of inputs and the groups they parse to, or None
+    // if they are invalid, covering each of the RFC 4291 text forms and the
+    // edge cases around "::" and dotted-quad tails.
+    const CONFORMANCE: &[(&str, Option<[u16; 8]>)] = &[
+        ("1:2:3:4:5:6:7:8", Some([1, 2, 3, 4, 5, 6, 7, 8])),
+        (
+            "2001:DB8:0:0:8:800:200C:417A",
+            Some([0x2001, 0xdb8, 0, 0, 8, 0x800, 0x200c, 0x417a]),
+        ),
+        ("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff", Some([0xffff; 8])),
+        (
+            "0000:0000:0000:0000:0000:0000:0000:0001",
+            Some([0, 0, 0, 0, 0, 0, 0, 1]),
+        ),
+        ("::", Some([0; 8])),
+        ("::1", Some([0, 0, 0, 0, 0, 0, 0, 1])),
+        ("1::", Some([1, 0, 0, 0, 0, 0, 0, 0])),
+        ("2001:db8::1", Some([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1])),
+        ("1::8", Some([1, 0, 0, 0, 0, 0, 0, 8])),
+        ("::2:3:4:5:6:7:8", Some([0, 2, 3, 4, 5, 6, 7, 8])),
+        ("1:2:3:4:5:6:7::", Some([1, 2, 3, 4, 5, 6, 7, 0])),
+        ("1:2:3::5:6:7:8", Some([1, 2, 3, 0, 5, 6, 7, 8])),
+        (
+            "::ffff:192.0.2.1",
+            Some([0, 0, 0, 0, 0, 0xffff, 0xc000, 0x0201]),
+        ),
+        ("::192.0.2.1", Some([0, 0, 0, 0, 0, 0, 0xc000, 0x0201])),
+        (
+            "64:ff9b::192.0.2.1",
+            Some([0x64, 0xff9b, 0, 0, 0, 0, 0xc000, 0x0201]),
+        ),
+        (
+            "1:2:3:4:5:6:1.2.3.4",
+            Some([1, 2, 3, 4, 5, 6, 0x0102, 0x0304]),
+        ),
+        (
+            "1:2:3:4:5::1.2.3.4",
+            Some([1, 2, 3, 4, 5, 0, 0x0102, 0x0304]),
+        ),
+        ("", None),
+        (":", None),
+        (":::", None),
+        (":1", None),
+        ("1:", None),
+        ("1:2:3:4:5:6:7", None),
+        ("1:2:3:4:5:6:7:8:9", None),
+        ("1:2:3:4:5:6:7:8::", None),
+        ("::1:2:3:4:5:6:7:8", None),
+        ("1:2:3:4::5:6:7:8", None),
+        ("1::2::3", None),
+        ("1:::2", None),
+        ("12345::", None),
+        ("g::", None),
+        ("::1 ", None),
+        (" ::1", None),
+        ("[::1]", None),
+        ("::1%eth0", None),
+        ("1:2:3:4:5:6:7:1.2.3.4", None),
+        ("1:2:3:4:5:6::1.2.3.4", None),
+        ("1:2:3:4:5:1.2.3.4", None),
+        ("::1.2.3", None),
+        ("::1.2.3.4.5", None),
+        ("::1.2.3.256", None),
+        ("::01.2.3.4", None),
+        ("::1.2.3.4:5", None),
+        ("::a.b.c.d", None),
+        ("1.2.3.4", None),
+        ("::١", None),
+    ];
+
+    // check_against_std asserts that parse and valid_ipv6 agree with each
+    // other and with the standard library on s, and that anything that
+    // parses is formatted the same way as the standard library does.
+    fn check_against_std(s: &str) {
+        let ours = parse(s);
+        assert_eq!(
+            valid_ipv6(s).is_ok(),
+            ours.is_ok(),
+            "valid_ipv6 and parse disagree on {:?}",
+            s
+        );
+        // a valid address is also the whole of itself as a partial parse.
+        if let Ok(addr) = ours {
+            assert_eq!(
+                parse_ascii_partial(s.as_bytes()),
+                Ok((addr, s.len())),
+                "parse_ascii_partial of {:?}",
+                s
+            );
+        }
+        let std = s.parse::<Ipv6Addr>().ok().map(Addr::from);
+        assert_eq!(ours.ok(), std, "parse of {:?} disagrees with std", s);
+        if let Some(addr) = std {
+            assert_eq!(
+                addr.to_string(),
+                Ipv6Addr::from(addr).to_string(),
+                "display of {:?} disagrees with std",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_addr() {
+        let cases = Vec::from([
+            ("::1", Addr::LOCALHOST),
+            ("::", Addr::UNSPECIFIED),
+            ("2001:db8::1", Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            ("FE80::ABCD", Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0xabcd)),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse(s), Ok(want), "parse of {:?}", s);
+            assert_eq!(s.parse::<Addr>(), Ok(want), "from_str of {:?}", s);
+            assert_eq!(valid_ipv6(s), Ok(true), "valid_ipv6 of {:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_addr_conversions() {
+        let addr = Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        assert_eq!(u128::from(addr), 0x2001_0db8_0000_0000_0000_0000_0000_0001);
+        assert_eq!(Addr::from(addr.to_bits()), addr);
+        assert_eq!(<[u16; 8]>::from(addr), [0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(Addr::from(addr.segments()), addr);
+        assert_eq!(
+            <[u8; 16]>::from(addr),
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(Addr::from(addr.octets()), addr);
+        assert_eq!(
+            Ipv6Addr::from(addr),
+            "2001:db8::1".parse::<Ipv6Addr>().unwrap()
+        );
+        assert_eq!(Addr::from(Ipv6Addr::LOCALHOST), Addr::LOCALHOST);
+        assert_eq!(Addr::default(), Addr::UNSPECIFIED);
+        assert!(Addr::LOCALHOST < Addr::new(0, 0, 0, 0, 0, 0, 1, 0));
+    }
+
+    #[test]
+    fn test_display() {
+        let cases = Vec::from([
+            (Addr::UNSPECIFIED, "::"),
+            (Addr::LOCALHOST, "::1"),
+            (Addr::new(1, 0, 0, 0, 0, 0, 0, 0), "1::"),
+            (Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), "2001:db8::1"),
+            // a single zero group is not compressed.
+            (
+                Addr::new(0x2001, 0xdb8, 0, 1, 1, 1, 1, 1),
+                "2001:db8:0:1:1:1:1:1",
+            ),
+            // the longest run is compressed.
+            (Addr::new(0x2001, 0, 0, 1, 0, 0, 0, 1), "2001:0:0:1::1"),
+            // the first of two equal runs is compressed.
+            (
+                Addr::new(0x2001, 0xdb8, 0, 0, 1, 0, 0, 1),
+                "2001:db8::1:0:0:1",
+            ),
+            (
+                Addr::new(0xABCD, 0xEF, 1, 2, 3, 4, 5, 6),
+                "abcd:ef:1:2:3:4:5:6",
+            ),
+            (
+                Addr::new(0, 0, 0, 0, 0, 0xffff, 0xc000, 0x0201),
+                "::ffff:192.0.2.1",
+            ),
+            // only mapped addresses get a dotted quad.
+            (Addr::new(0, 0, 0, 0, 0, 0, 0xc000, 0x0201), "::c000:201"),
+            (
+                Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0xc000, 0x0201),
+                "64:ff9b::c000:201",
+            ),
+        ]);
+
+        for (addr, want) in cases {
+            assert_eq!(addr.to_string(), want, "display of {:?}", addr);
+            assert_eq!(parse(want), Ok(addr), "round trip of {}", want);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = Vec::from([
+            (
+                "",
+                InvalidAddrErr::EmptyGroup {
+                    offset: 0,
+                    group: 0,
+                },
+            ),
+            (
+                ":1",
+                InvalidAddrErr::EmptyGroup {
+                    offset: 0,
+                    group: 0,
+                },
+            ),
+            (
+                "1:",
+                InvalidAddrErr::EmptyGroup {
+                    offset: 2,
+                    group: 1,
+                },
+            ),
+            (
+                "1:::2",
+                InvalidAddrErr::EmptyGroup {
+                    offset: 3,
+                    group: 1,
+                },
+            ),
+            (
+                "1::12345",
+                InvalidAddrErr::GroupTooLong {
+                    offset: 3,
+                    group: 1,
+                },
+            ),
+            (
+                "1::g",
+                InvalidAddrErr::InvalidChar {
+                    offset: 3,
+                    group: 1,
+                },
+            ),
+            (
+                "1:2g",
+                InvalidAddrErr::InvalidChar {
+                    offset: 3,
+                    group: 1,
+                },
+            ),
+            (
+                "1::2::3",
+                InvalidAddrErr::MultipleCompressions {
+                    offset: 4,
+                    group: 2,
+                },
+            ),
+            (
+                "1:2:3:4:5:6:7:8:9",
+                InvalidAddrErr::TooManyGroups {
+                    offset: 15,
+                    group: 7,
+                },
+            ),
+            (
+                "1:2:3:4::5:6:7:8",
+                InvalidAddrErr::TooManyGroups {
+                    offset: 14,
+                    group: 6,
+                },
+            ),
+            (
+                "1:2:3:4:5:6:7:1.2.3.4",
+                InvalidAddrErr::TooManyGroups {
+                    offset: 14,
+                    group: 7,
+                },
+            ),
+            (
+                "1:2:3:4:5:6:7",
+                InvalidAddrErr::TooFewGroups {
+                    offset: 13,
+                    group: 7,
+                },
+            ),
+            (
+                "1:2:3:4:5:1.2.3.4",
+                InvalidAddrErr::TooFewGroups {
+                    offset: 17,
+                    group: 7,
+                },
+            ),
+            (
+                "::1.2.3.256",
+                InvalidAddrErr::Ipv4(ipv4::InvalidAddrErr::OctetOutOfRange {
+                    offset: 8,
+                    octet: 3,
+                }),
+            ),
+            (
+                "::1.02.3.4",
+                InvalidAddrErr::Ipv4(ipv4::InvalidAddrErr::LeadingZero {
+                    offset: 4,
+                    octet: 1,
+                }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse(s), Err(want), "parse of {:?}", s);
+            assert_eq!(valid_ipv6(s), Err(want), "valid_ipv6 of {:?}", s);
+        }
+
+        let err = parse("1::2::3").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid ipv6 address string: more than one '::' at byte 4 (group 2)"
+        );
+        let err = parse("::1.2.3.256").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid ipv6 address string: embedded ipv4 octet out of range at byte 8 (octet 3)"
+        );
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let input = "1::12345";
+        let err = parse(input).unwrap_err();
+        let want = "\
+error: invalid ipv6 address: group has more than four digits
+ --> input:1:4
+  |
+1 | 1::12345
+  |    ^^^^^ group 1 must be at most four hex digits";
+        assert_eq!(err.diagnostic(input).to_string(), want);
+
+        let input = "::ffff:10.0.300.1";
+        let err = parse(input).unwrap_err();
+        let want = "\
+error: invalid ipv6 address: octet out of range
+ --> input:1:13
+  |
+1 | ::ffff:10.0.300.1
+  |             ^^^ octet 2 must be between 0 and 255";
+        assert_eq!(err.diagnostic(input).to_string(), want);
+
+        // rendering against some other input never panics, even where the
+        // offset is past its end or inside a character.
+        let long = ParseOptions::permissive()
+            .parse("fe80::1%a-very-long-name")
+            .unwrap_err();
+        for err in [parse("1::12345").unwrap_err(), long] {
+            for input in ["", "1:", "1:é", "fe80::1%é"] {
+                err.diagnostic(input).to_string();
+            }
+        }
+    }
+
+    #[test]
+    fn test_conformance() {
+        for (s, want) in CONFORMANCE {
+            assert_eq!(
+                parse(s).map(|a| a.segments()).ok(),
+                *want,
+                "parse of {:?}",
+                s
+            );
+            check_against_std(s);
+        }
+    }
+
+    #[test]
+    fn test_parse_ascii() {
+        assert_eq!(parse_ascii(b"2001:db8::1"), Ok(addr("2001:db8::1")));
+        assert_eq!(
+            ParseOptions::permissive().parse_ascii(b"fe80::1%eth0"),
+            ParseOptions::permissive().parse("fe80::1%eth0")
+        );
+
+        // bytes that are not UTF-8 are invalid characters like any other.
+        assert_eq!(
+            parse_ascii(b"2001:db8::\xff"),
+            Err(InvalidAddrErr::InvalidChar {
+                offset: 10,
+                group: 2,
+            })
+        );
+        assert_eq!(
+            ParseOptions::permissive().parse_ascii(b"fe80::1%eth\xff"),
+            Err(InvalidAddrErr::InvalidZone {
+                offset: 11,
+                group: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_ascii_partial() {
+        let cases = Vec::from([
+            (&b"2001:db8::1"[..], Ok(("2001:db8::1", 11))),
+            (b"::1]:80", Ok(("::1", 3))),
+            (b"::]", Ok(("::", 2))),
+            (b"1:: x", Ok(("1::", 3))),
+            (b"1:2:3:4:5:6:7:8:80", Ok(("1:2:3:4:5:6:7:8", 15))),
+            (b"1::3:4:5:6:7:8:80", Ok(("1::3:4:5:6:7:8", 14))),
+            (b"2001:db8::1: ok", Ok(("2001:db8::1", 11))),
+            (b"2001:db8::1.", Ok(("2001:db8::1", 11))),
+            (b"2001:db8::1.5", Ok(("2001:db8::1", 11))),
+            (b"::ffff:192.0.2.1:443", Ok(("::ffff:192.0.2.1", 16))),
+            (b"::ffff:192.0.2.1.", Ok(("::ffff:192.0.2.1", 16))),
+            (b"fe80::1g", Ok(("fe80::1", 7))),
+            (b"fe80::1\xff", Ok(("fe80::1", 7))),
+            (
+                b"1:2:3 ",
+                Err(InvalidAddrErr::TooFewGroups {
+                    offset: 5,
+                    group: 3,
+                }),
+            ),
+            (
+                b"2001:db8::12345",
+                Err(InvalidAddrErr::GroupTooLong {
+                    offset: 10,
+                    group: 2,
+                }),
+            ),
+            (
+                b"1:2:3:4:5:6:7:1.2.3.4",
+                Err(InvalidAddrErr::TooManyGroups {
+                    offset: 14,
+                    group: 7,
+                }),
+            ),
+            (
+                b"fe80::1%eth0 up",
+                Err(InvalidAddrErr::ZoneNotAllowed {
+                    offset: 7,
+                    group: 8,
+                }),
+            ),
+            (
+                b":1",
+                Err(InvalidAddrErr::EmptyGroup {
+                    offset: 0,
+                    group: 0,
+                }),
+            ),
+            (
+                b"host",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 0,
+                    group: 0,
+                }),
+            ),
+        ]);
+
+        for (input, want) in cases {
+            let want = want.map(|(a, len)| (addr(a), len));
+            assert_eq!(
+                parse_ascii_partial(input),
+                want,
+                "parse_ascii_partial of {:?}",
+                input.escape_ascii().to_string()
+            );
+        }
+
+        // a zone ends at the first byte that is not unreserved.
+        let zoned = ParseOptions::strict().zones(Zones::Scoped);
+        let (a, len) = zoned.parse_ascii_partial(b"fe80::1%eth0 up").unwrap();
+        assert_eq!((a.to_string(), len), ("fe80::1%eth0".to_string(), 12));
+        let (a, len) = zoned.parse_ascii_partial(b"fe80::1%2]:22").unwrap();
+        assert_eq!((a.to_string(), len), ("fe80::1%2".to_string(), 9));
+        assert_eq!(
+            zoned.parse_ascii_partial(b"fe80::1% "),
+            Err(InvalidAddrErr::EmptyZone {
+                offset: 8,
+                group: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_std_exhaustive_short_strings() {
+        // every string of up to 7 characters over an alphabet of a couple
+        // of hex digits, the separators and one invalid character.
+        const ALPHABET: &[u8] = b"0fF:.g";
+        let mut buf = Vec::new();
+        for len in 0..=7u32 {
+            for mut n in 0..ALPHABET.len().pow(len) {
+                buf.clear();
+                for _ in 0..len {
+                    buf.push(ALPHABET[n % ALPHABET.len()]);
+                    n /= ALPHABET.len();
+                }
+                check_against_std(std::str::from_utf8(&buf).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_std_random() {
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+
+        // random strings biased towards hex digits and colons, so that a
+        // good share of them are close to valid.
+        const ALPHABET: &[u8] = b"0123456789abcdefABCDEF0000::::::::...1259x ";
+        let mut buf = Vec::new();
+        for _ in 0..200_000 {
+            buf.clear();
+            let len = rng.next() % 40;
+            for _ in 0..len {
+                buf.push(ALPHABET[(rng.next() % ALPHABET.len() as u64) as usize]);
+            }
+            check_against_std(std::str::from_utf8(&buf).unwrap());
+        }
+
+        // random addresses, with runs of zero groups so that compression is
+        // exercised, must round trip through Display.
+        for _ in 0..200_000 {
+            let bits = rng.next_u128();
+            let keep = rng.next();
+            let mut segments = Addr::from_bits(bits).segments();
+            for (i, segment) in segments.iter_mut().enumerate() {
+                if keep >> i & 1 == 0 {
+                    *segment = 0;
+                }
+            }
+            let addr = Addr::from_segments(segments);
+            let s = addr.to_string();
+            assert_eq!(parse(&s), Ok(addr), "round trip of {}", s);
+            check_against_std(&s);
+        }
+    }
+}
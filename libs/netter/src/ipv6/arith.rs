@@ -0,0 +1,318 @@
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+use super::Addr;
+
+// The arithmetic below treats an address as its u128, like the methods on
+// ipv4::Addr, with checked forms that return None where the result would
+// leave the address space, saturating forms that stop at :: and
+// ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff, and wrapping forms that wrap
+// around. The result keeps the zone of self, so stepping through fe80::/64
+// from fe80::1%eth0 stays on eth0.
+impl Addr {
+    // with_bits returns an address with the given bits and the zone of self.
+    const fn with_bits(self, bits: u128) -> Addr {
+        Addr {
+            octets: bits.to_be_bytes(),
+            zone: self.zone,
+        }
+    }
+
+    // checked_add returns the address n after self, or None if that is past
+    // the last address.
+    pub const fn checked_add(self, n: u128) -> Option<Addr> {
+        match self.to_bits().checked_add(n) {
+            Some(bits) => Some(self.with_bits(bits)),
+            None => None,
+        }
+    }
+
+    // checked_sub returns the address n before self, or None if that is
+    // before ::.
+    pub const fn checked_sub(self, n: u128) -> Option<Addr> {
+        match self.to_bits().checked_sub(n) {
+            Some(bits) => Some(self.with_bits(bits)),
+            None => None,
+        }
+    }
+
+    // saturating_add returns the address n after self, or the last address
+    // if that is past the end.
+    pub const fn saturating_add(self, n: u128) -> Addr {
+        self.with_bits(self.to_bits().saturating_add(n))
+    }
+
+    // saturating_sub returns the address n before self, or :: if that is
+    // before the start.
+    pub const fn saturating_sub(self, n: u128) -> Addr {
+        self.with_bits(self.to_bits().saturating_sub(n))
+    }
+
+    // wrapping_add returns the address n after self, wrapping around from
+    // the last address to ::.
+    pub const fn wrapping_add(self, n: u128) -> Addr {
+        self.with_bits(self.to_bits().wrapping_add(n))
+    }
+
+    // wrapping_sub returns the address n before self, wrapping around from
+    // :: to the last address.
+    pub const fn wrapping_sub(self, n: u128) -> Addr {
+        self.with_bits(self.to_bits().wrapping_sub(n))
+    }
+
+    // successor returns the next address, or None for the last address.
+    // With predecessor it stands in for core::iter::Step, which is unstable.
+    pub const fn successor(self) -> Option<Addr> {
+        self.checked_add(1)
+    }
+
+    // predecessor returns the previous address, or None for ::.
+    pub const fn predecessor(self) -> Option<Addr> {
+        self.checked_sub(1)
+    }
+
+    // distance returns how many addresses apart self and other are, in
+    // either order. Zones are ignored.
+    pub const fn distance(self, other: Addr) -> u128 {
+        self.to_bits().abs_diff(other.to_bits())
+    }
+
+    // leading_zeros returns the number of leading zero bits, which is 128
+    // for ::.
+    pub const fn leading_zeros(self) -> u32 {
+        self.to_bits().leading_zeros()
+    }
+
+    // trailing_zeros returns the number of trailing zero bits, which is the
+    // length of the longest host part the address could be the network of:
+    // 2001:db8:: has 99, so it starts a /29. It is 128 for ::.
+    pub const fn trailing_zeros(self) -> u32 {
+        self.to_bits().trailing_zeros()
+    }
+}
+
+// The bitwise operators apply masks to addresses, e.g. addr & mask is the
+// network of addr when mask is a netmask such as ffff:ffff:ffff:ffff::. The
+// result keeps the zone of the left-hand side.
+impl BitAnd for Addr {
+    type Output = Addr;
+
+    fn bitand(self, mask: Addr) -> Addr {
+        self.with_bits(self.to_bits() & mask.to_bits())
+    }
+}
+
+impl BitOr for Addr {
+    type Output = Addr;
+
+    fn bitor(self, mask: Addr) -> Addr {
+        self.with_bits(self.to_bits() | mask.to_bits())
+    }
+}
+
+impl BitXor for Addr {
+    type Output = Addr;
+
+    fn bitxor(self, mask: Addr) -> Addr {
+        self.with_bits(self.to_bits() ^ mask.to_bits())
+    }
+}
+
+impl Not for Addr {
+    type Output = Addr;
+
+    fn not(self) -> Addr {
+        self.with_bits(!self.to_bits())
+    }
+}
+
+impl BitAndAssign for Addr {
+    fn bitand_assign(&mut self, mask: Addr) {
+        *self = *self & mask;
+    }
+}
+
+impl BitOrAssign for Addr {
+    fn bitor_assign(&mut self, mask: Addr) {
+        *self = *self | mask;
+    }
+}
+
+impl BitXorAssign for Addr {
+    fn bitxor_assign(&mut self, mask: Addr) {
+        *self = *self ^ mask;
+    }
+}
+
+#[cfg(test)]
+mod arith_tests {
+    use crate::ipv6::Addr;
+    use crate::rng::Rng;
+    use crate::testing::ipv6::addr;
+    use crate::testing::matches_bits;
+
+    const LAST: u128 = u128::MAX;
+
+    #[test]
+    fn test_add_sub() {
+        // (addr, n, checked_add, saturating_add, wrapping_add)
+        let cases = Vec::from([
+            (
+                "2001:db8::1",
+                1,
+                Some("2001:db8::2"),
+                "2001:db8::2",
+                "2001:db8::2",
+            ),
+            (
+                "2001:db8::ffff",
+                1,
+                Some("2001:db8::1:0"),
+                "2001:db8::1:0",
+                "2001:db8::1:0",
+            ),
+            (
+                "2001:db8:0:0:ffff:ffff:ffff:ffff",
+                1,
+                Some("2001:db8:0:1::"),
+                "2001:db8:0:1::",
+                "2001:db8:0:1::",
+            ),
+            ("::", 0, Some("::"), "::", "::"),
+            (
+                "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                1,
+                None,
+                "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                "::",
+            ),
+            (
+                "fe80::1%eth0",
+                1,
+                Some("fe80::2%eth0"),
+                "fe80::2%eth0",
+                "fe80::2%eth0",
+            ),
+        ]);
+
+        for (s, n, checked, saturating, wrapping) in cases {
+            let a = addr(s);
+            assert_eq!(a.checked_add(n), checked.map(addr), "{} checked + {}", s, n);
+            assert_eq!(
+                a.saturating_add(n),
+                addr(saturating),
+                "{} saturating + {}",
+                s,
+                n
+            );
+            assert_eq!(a.wrapping_add(n), addr(wrapping), "{} wrapping + {}", s, n);
+
+            let back = addr(wrapping);
+            assert_eq!(back.wrapping_sub(n), a, "{} wrapping - {}", wrapping, n);
+            if let Some(c) = checked {
+                assert_eq!(addr(c).checked_sub(n), Some(a), "{} checked - {}", c, n);
+            } else {
+                assert_eq!(back.checked_sub(n), None, "{} checked - {}", wrapping, n);
+                assert_eq!(
+                    back.saturating_sub(n),
+                    Addr::UNSPECIFIED,
+                    "{} saturating - {}",
+                    wrapping,
+                    n
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_successor_predecessor() {
+        let cases = Vec::from([
+            ("::", Some("::1"), None),
+            (
+                "2001:db8::ffff",
+                Some("2001:db8::1:0"),
+                Some("2001:db8::fffe"),
+            ),
+            ("fe80::1%eth0", Some("fe80::2%eth0"), Some("fe80::%eth0")),
+            (
+                "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                None,
+                Some("ffff:ffff:ffff:ffff:ffff:ffff:ffff:fffe"),
+            ),
+        ]);
+
+        for (s, succ, pred) in cases {
+            assert_eq!(addr(s).successor(), succ.map(addr), "successor of {}", s);
+            assert_eq!(
+                addr(s).predecessor(),
+                pred.map(addr),
+                "predecessor of {}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        let cases = Vec::from([
+            ("2001:db8::1", "2001:db8::1", 0),
+            ("2001:db8::1", "2001:db8::1:0", 0xffff),
+            ("2001:db8::1:0", "2001:db8::1", 0xffff),
+            ("fe80::1%eth0", "fe80::1%eth1", 0),
+            ("::", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff", LAST),
+        ]);
+
+        for (a, b, want) in cases {
+            assert_eq!(addr(a).distance(addr(b)), want, "{} to {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_zeros() {
+        let cases = Vec::from([
+            ("::", 128, 128),
+            ("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff", 0, 0),
+            ("2001:db8::", 2, 99),
+            ("::1", 127, 0),
+            ("::1:0", 111, 16),
+        ]);
+
+        for (s, leading, trailing) in cases {
+            assert_eq!(addr(s).leading_zeros(), leading, "leading zeros of {}", s);
+            assert_eq!(
+                addr(s).trailing_zeros(),
+                trailing,
+                "trailing zeros of {}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_masks() {
+        let a = addr("2001:db8::1:2:3:4%eth0");
+        let netmask = addr("ffff:ffff:ffff:ffff::");
+
+        assert_eq!(a & netmask, addr("2001:db8::%eth0"), "network");
+        assert_eq!(
+            a | !netmask,
+            addr("2001:db8::ffff:ffff:ffff:ffff%eth0"),
+            "last address"
+        );
+        assert_eq!(a ^ addr("::4"), addr("2001:db8::1:2:3:0%eth0"), "xor");
+        assert_eq!(!Addr::UNSPECIFIED, Addr::from_bits(LAST), "not");
+
+        let mut b = a.without_zone();
+        b &= netmask;
+        assert_eq!(b, addr("2001:db8::"), "&=");
+        b |= addr("::1");
+        assert_eq!(b, addr("2001:db8::1"), "|=");
+        b ^= addr("2001:db8::1");
+        assert_eq!(b, Addr::UNSPECIFIED, "^=");
+    }
+
+    #[test]
+    fn test_matches_bits() {
+        // every operation must agree with the same one on the u128.
+        matches_bits!(Addr, Rng::next_u128);
+    }
+}
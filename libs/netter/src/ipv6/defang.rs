@@ -0,0 +1,237 @@
+use core::fmt;
+use core::fmt::Write;
+
+use super::Addr;
+#[cfg(feature = "alloc")]
+use super::{InvalidAddrErr, ParseOptions, Result, zone::scan_uri_host};
+use crate::defang::Defang;
+#[cfg(feature = "alloc")]
+use crate::defang::{Refanged, Url};
+
+// Defanged renders an Addr the way threat-intel reports write indicators,
+// with every ':' and '.' bracketed, e.g. "2001[:]db8[:][:]1". See
+// Addr::defanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Defanged(Addr);
+
+impl fmt::Display for Defanged {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(Defang(f), "{}", self.0)
+    }
+}
+
+impl Addr {
+    // defanged returns the address, with its zone, formatted so that it is
+    // not turned into a link when pasted into a report or a chat.
+    // parse_defanged reads it back.
+    pub fn defanged(&self) -> Defanged {
+        Defanged(*self)
+    }
+}
+
+// parse_defanged will parse an address that has been defanged for a
+// threat-intel report back into the real Addr. "[:]", "(:)" and "{:}" may
+// stand in for a ':', and the separators ipv4::parse_defanged accepts for a
+// '.' may be used in a dotted-quad tail:
+//
+//   "2001[:]db8[:][:]1", "[:][:]ffff[:]192[.]0[.]2[.]1"
+//
+// A plain ':' is fine too. The address may also be the host of a URL with
+// any scheme, in square brackets with any zone written as in RFC 6874, as in
+// "hxxp://[2001:db8::1]:8080/gate.php"; the port and path are skipped.
+//
+// Errors are reported at their offset in s. It uses the default
+// ParseOptions; see ParseOptions::parse_defanged to pick a different
+// profile.
+#[cfg(feature = "alloc")]
+pub fn parse_defanged(s: &str) -> Result<Addr> {
+    ParseOptions::default().parse_defanged(s)
+}
+
+// scan_defanged is the scanner behind parse_defanged.
+#[cfg(feature = "alloc")]
+pub(super) fn scan_defanged(s: &str, options: &ParseOptions) -> Result<Addr> {
+    let text = Refanged::new(s.as_bytes());
+    scan_refanged(&text, &text.url(), options)
+}
+
+// scan_refanged parses the host of url in text, which is shared with the
+// crate-level parse_defanged once it has picked the family.
+#[cfg(feature = "alloc")]
+pub(crate) fn scan_refanged(text: &Refanged, url: &Url, options: &ParseOptions) -> Result<Addr> {
+    let shift =
+        |err: InvalidAddrErr, start: usize| err.with_offset(text.offset(start + err.offset()));
+
+    let addr = match url.bracket {
+        // a URL host is in brackets, and its zone is percent-encoded.
+        Some(at) => {
+            let end = if url.closed {
+                url.host.end + 1
+            } else {
+                text.text().len()
+            };
+            // refanging only replaces ASCII, so the text is still UTF-8 and
+            // the brackets are on character boundaries.
+            let host = core::str::from_utf8(&text.text()[at..end]).unwrap();
+            scan_uri_host(host, options).map_err(|err| shift(err, at))?
+        }
+        None if url.scheme => {
+            return Err(InvalidAddrErr::MissingBracket {
+                offset: text.offset(url.host.start),
+                group: 0,
+            });
+        }
+        None => {
+            let start = url.host.start;
+            options
+                .parse_ascii(&text.text()[url.host.clone()])
+                .map_err(|err| shift(err, start))?
+        }
+    };
+    // the address was read in full, so junk after it is past the last group.
+    if let Some(at) = url.junk {
+        return Err(InvalidAddrErr::InvalidChar {
+            offset: text.offset(at),
+            group: 7,
+        });
+    }
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod defang_tests {
+    use crate::testing::ipv6::addr;
+
+    #[test]
+    fn test_defanged() {
+        let cases = Vec::from([
+            ("2001:db8::1", "2001[:]db8[:][:]1"),
+            ("::", "[:][:]"),
+            ("::ffff:192.0.2.1", "[:][:]ffff[:]192[.]0[.]2[.]1"),
+            ("fe80::1%eth0", "fe80[:][:]1%eth0"),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(addr(s).defanged().to_string(), want, "{}", s);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_defanged() {
+        use super::parse_defanged;
+        use crate::ipv6::{InvalidAddrErr, ParseOptions, Zones};
+
+        let cases = Vec::from([
+            ("2001[:]db8[:][:]1", Ok("2001:db8::1")),
+            ("2001(:)db8(:)(:)1", Ok("2001:db8::1")),
+            ("2001{:}db8::1", Ok("2001:db8::1")),
+            ("[:][:]", Ok("::")),
+            ("[:][:]ffff[:]192[.]0[.]2[.]1", Ok("::ffff:192.0.2.1")),
+            ("hxxp://[2001:db8::1]", Ok("2001:db8::1")),
+            ("hxxps[://][2001[:]db8[:][:]1]:8443/x", Ok("2001:db8::1")),
+            (
+                "2001[:]db8[:][:]1[:]",
+                Err(InvalidAddrErr::EmptyGroup {
+                    offset: 20,
+                    group: 3,
+                }),
+            ),
+            (
+                "2001[:]db8[:][:]1[:][:]2",
+                Err(InvalidAddrErr::MultipleCompressions {
+                    offset: 17,
+                    group: 3,
+                }),
+            ),
+            (
+                "hxxp://2001:db8::1/",
+                Err(InvalidAddrErr::MissingBracket {
+                    offset: 7,
+                    group: 0,
+                }),
+            ),
+            (
+                "hxxp://[2001:db8::1",
+                Err(InvalidAddrErr::MissingBracket {
+                    offset: 19,
+                    group: 8,
+                }),
+            ),
+            (
+                "hxxp://[2001:db8::1]x",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 20,
+                    group: 7,
+                }),
+            ),
+            // an error on the second byte of a refanged "[://]" is at the
+            // start of it.
+            (
+                "2001:db8[://]",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 8,
+                    group: 2,
+                }),
+            ),
+            (
+                "1[://]ff[://]1",
+                Err(InvalidAddrErr::InvalidChar {
+                    offset: 1,
+                    group: 1,
+                }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_defanged(s), want.map(addr), "{}", s);
+        }
+
+        // zones are written as in an address, or as in RFC 6874 in a URL.
+        let zoned = ParseOptions::strict().zones(Zones::Scoped);
+        assert_eq!(
+            zoned.parse_defanged("fe80[:][:]1%eth0"),
+            Ok(addr("fe80::1%eth0")),
+            "zone in an address"
+        );
+        assert_eq!(
+            zoned.parse_defanged("hxxp://[fe80[:][:]1%25eth0]/"),
+            Ok(addr("fe80::1%eth0")),
+            "zone in a URL"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_defang_round_trip() {
+        use crate::ipv6::{Addr, ParseOptions};
+        use crate::rng::Rng;
+
+        // every address must come back from its defanged form, including
+        // the ones written with a dotted-quad tail.
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+
+        for i in 0..10_000 {
+            // about half the groups are zero, so that "::" turns up, and
+            // every tenth address is mapped so that a dotted-quad tail does.
+            let segments: [u16; 8] = core::array::from_fn(|_| {
+                if rng.next().is_multiple_of(2) {
+                    0
+                } else {
+                    rng.next() as u16
+                }
+            });
+            let addr = match i % 10 {
+                0 => Addr::from_bits(0xffff_0000_0000 | rng.next() as u32 as u128),
+                _ => Addr::from_segments(segments),
+            };
+            let defanged = addr.defanged().to_string();
+            assert_eq!(
+                ParseOptions::strict().parse_defanged(&defanged),
+                Ok(addr),
+                "{}",
+                defanged
+            );
+        }
+    }
+}
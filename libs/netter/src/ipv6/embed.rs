@@ -0,0 +1,188 @@
+use super::{Addr, Prefix};
+use crate::ipv4;
+
+impl Addr {
+    // from_ipv4_mapped returns the IPv4-mapped address of v4, ::ffff:a.b.c.d
+    // (RFC 4291 section 2.5.5.2).
+    pub const fn from_ipv4_mapped(v4: ipv4::Addr) -> Addr {
+        Addr::from_bits(0xffff << 32 | v4.to_bits() as u128)
+    }
+
+    // to_ipv4_mapped returns the IPv4 address embedded in an IPv4-mapped
+    // address, or None if the address is not in ::ffff:0:0/96.
+    pub const fn to_ipv4_mapped(&self) -> Option<ipv4::Addr> {
+        match self.octets {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => {
+                Some(ipv4::Addr::new(a, b, c, d))
+            }
+            _ => None,
+        }
+    }
+
+    // from_ipv4_compatible returns the IPv4-compatible address of v4,
+    // ::a.b.c.d. RFC 4291 section 2.5.5.1 deprecates the form; it is here to
+    // read old configurations.
+    pub const fn from_ipv4_compatible(v4: ipv4::Addr) -> Addr {
+        Addr::from_bits(v4.to_bits() as u128)
+    }
+
+    // to_ipv4_compatible returns the IPv4 address embedded in an
+    // IPv4-compatible address, or None if the address is not in ::/96. The
+    // unspecified and loopback addresses are in ::/96 but are not
+    // IPv4-compatible, so they also return None.
+    pub const fn to_ipv4_compatible(&self) -> Option<ipv4::Addr> {
+        let bits = self.to_bits();
+        if bits >> 32 != 0 || bits <= 1 {
+            return None;
+        }
+        Some(ipv4::Addr::from_bits(bits as u32))
+    }
+}
+
+impl Prefix {
+    // NAT64_WELL_KNOWN is the well-known prefix for IPv4-embedded addresses,
+    // 64:ff9b::/96 (RFC 6052 section 2.1).
+    pub const NAT64_WELL_KNOWN: Prefix =
+        Prefix::new(Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0), 96).unwrap();
+
+    // embed_ipv4 returns the IPv4-embedded address of v4 under this prefix,
+    // as a NAT64 translator would build it (RFC 6052 section 2.2). The
+    // prefix must be a /32, /40, /48, /56, /64 or /96; for any other length
+    // embed_ipv4 returns None. Bits 64 to 71 of the address are left zero,
+    // so for prefixes shorter than /64 the IPv4 address is split around them.
+    pub const fn embed_ipv4(&self, v4: ipv4::Addr) -> Option<Addr> {
+        let Some(positions) = nat64_positions(self.prefix_len()) else {
+            return None;
+        };
+        let mut octets = self.network().octets();
+        let v4 = v4.octets();
+        let mut i = 0;
+        while i < 4 {
+            octets[positions[i]] = v4[i];
+            i += 1;
+        }
+        Some(Addr::from_octets(octets))
+    }
+
+    // extract_ipv4 returns the IPv4 address embedded in addr under this
+    // prefix, reversing embed_ipv4. It returns None if the prefix length is
+    // not one RFC 6052 allows, addr is not in the prefix, or bits 64 to 71
+    // of addr are not zero.
+    pub const fn extract_ipv4(&self, addr: Addr) -> Option<ipv4::Addr> {
+        let Some(positions) = nat64_positions(self.prefix_len()) else {
+            return None;
+        };
+        let octets = addr.octets();
+        if !self.contains(addr) || octets[8] != 0 {
+            return None;
+        }
+        Some(ipv4::Addr::new(
+            octets[positions[0]],
+            octets[positions[1]],
+            octets[positions[2]],
+            octets[positions[3]],
+        ))
+    }
+}
+
+// nat64_positions returns which octets of an IPv4-embedded address hold the
+// four octets of the IPv4 address for a prefix of length len: the ones right
+// after the prefix, skipping octet 8.
+const fn nat64_positions(len: u8) -> Option<[usize; 4]> {
+    if !matches!(len, 32 | 40 | 48 | 56 | 64 | 96) {
+        return None;
+    }
+    let mut positions = [0; 4];
+    let mut next = len as usize / 8;
+    let mut i = 0;
+    while i < 4 {
+        if next == 8 {
+            next += 1;
+        }
+        positions[i] = next;
+        next += 1;
+        i += 1;
+    }
+    Some(positions)
+}
+
+#[cfg(test)]
+mod embed_tests {
+    use crate::ipv6::{Addr, Prefix};
+    use crate::testing::ipv4;
+    use crate::testing::ipv6::addr;
+
+    #[test]
+    fn test_mapped_and_compatible() {
+        let mapped = Addr::from_ipv4_mapped(ipv4::addr("192.0.2.33"));
+        assert_eq!(mapped, addr("::ffff:192.0.2.33"));
+        assert_eq!(mapped.to_ipv4_mapped(), Some(ipv4::addr("192.0.2.33")));
+        assert_eq!(mapped.to_ipv4_compatible(), None);
+        assert_eq!(addr("::192.0.2.33").to_ipv4_mapped(), None);
+        assert_eq!(addr("1::ffff:192.0.2.33").to_ipv4_mapped(), None);
+
+        let compatible = Addr::from_ipv4_compatible(ipv4::addr("192.0.2.33"));
+        assert_eq!(compatible, addr("::192.0.2.33"));
+        assert_eq!(
+            compatible.to_ipv4_compatible(),
+            Some(ipv4::addr("192.0.2.33"))
+        );
+        assert_eq!(compatible.to_ipv4_mapped(), None);
+        assert_eq!(addr("::").to_ipv4_compatible(), None);
+        assert_eq!(addr("::1").to_ipv4_compatible(), None);
+        assert_eq!(
+            addr("::2").to_ipv4_compatible(),
+            Some(ipv4::addr("0.0.0.2"))
+        );
+
+        // the conversions agree with std.
+        let std = std::net::Ipv4Addr::new(192, 0, 2, 33);
+        assert_eq!(Addr::from(std.to_ipv6_mapped()), mapped);
+        #[allow(deprecated)]
+        let std_compatible = std.to_ipv6_compatible();
+        assert_eq!(Addr::from(std_compatible), compatible);
+    }
+
+    #[test]
+    fn test_nat64() {
+        // the examples of RFC 6052 section 2.4, all embedding 192.0.2.33.
+        let cases = Vec::from([
+            ("2001:db8::/32", "2001:db8:c000:221::"),
+            ("2001:db8:100::/40", "2001:db8:1c0:2:21::"),
+            ("2001:db8:122::/48", "2001:db8:122:c000:2:2100::"),
+            ("2001:db8:122:300::/56", "2001:db8:122:3c0:0:221::"),
+            ("2001:db8:122:344::/64", "2001:db8:122:344:c0:2:2100:0"),
+            ("2001:db8:122:344::/96", "2001:db8:122:344::192.0.2.33"),
+            ("64:ff9b::/96", "64:ff9b::192.0.2.33"),
+        ]);
+
+        for (p, want) in cases {
+            let p: Prefix = p.parse().unwrap();
+            assert_eq!(
+                p.embed_ipv4(ipv4::addr("192.0.2.33")),
+                Some(addr(want)),
+                "embed in {}",
+                p
+            );
+            assert_eq!(
+                p.extract_ipv4(addr(want)),
+                Some(ipv4::addr("192.0.2.33")),
+                "extract from {}",
+                p
+            );
+        }
+
+        let wkp = Prefix::NAT64_WELL_KNOWN;
+        assert_eq!(wkp.to_string(), "64:ff9b::/96");
+        assert!(wkp.embed_ipv4(ipv4::addr("8.8.8.8")).unwrap().is_nat64());
+
+        let p: Prefix = "2001:db8::/33".parse().unwrap();
+        assert_eq!(p.embed_ipv4(ipv4::addr("192.0.2.33")), None);
+        assert_eq!(p.extract_ipv4(addr("2001:db8::1")), None);
+
+        // not in the prefix, or with the reserved octet set.
+        let p: Prefix = "2001:db8:122::/48".parse().unwrap();
+        assert_eq!(p.extract_ipv4(addr("2001:db9:122:c000:2:2100::")), None);
+        assert_eq!(p.extract_ipv4(addr("2001:db8:122:c000:ff02:2100::")), None);
+    }
+}
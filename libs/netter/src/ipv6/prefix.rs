@@ -0,0 +1,706 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Addr, HostBits, InvalidAddrErr, ParseOptions, Zones, scan};
+use crate::ascii::find;
+
+// InvalidPrefixErr describes why a string is not a valid IPv6 prefix. Like
+// InvalidAddrErr, every variant carries the byte offset in the input where
+// the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidPrefixErr {
+    // the address before the '/' is invalid. A prefix never has a zone, so
+    // "fe80::%eth0/64" fails with InvalidAddrErr::ZoneNotAllowed.
+    Addr(InvalidAddrErr),
+    // there was no '/' after the address, e.g. "2001:db8::".
+    MissingLength { offset: usize },
+    // the prefix length is not a decimal number without leading zeros, e.g.
+    // "2001:db8::/x" or "2001:db8::/032".
+    InvalidLength { offset: usize },
+    // the prefix length is greater than 128, e.g. "2001:db8::/129".
+    LengthOutOfRange { offset: usize },
+    // the address has bits set past the prefix length and the ParseOptions
+    // reject host bits, e.g. "2001:db8::1/32". offset points at the first
+    // group with host bits in it.
+    HostBitsSet { offset: usize },
+}
+
+impl InvalidPrefixErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidPrefixErr::Addr(err) => err.offset(),
+            InvalidPrefixErr::MissingLength { offset }
+            | InvalidPrefixErr::InvalidLength { offset }
+            | InvalidPrefixErr::LengthOutOfRange { offset }
+            | InvalidPrefixErr::HostBitsSet { offset } => offset,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidPrefixErr::Addr(err) => err.reason(),
+            InvalidPrefixErr::MissingLength { .. } => "missing prefix length",
+            InvalidPrefixErr::InvalidLength { .. } => "invalid prefix length",
+            InvalidPrefixErr::LengthOutOfRange { .. } => "prefix length out of range",
+            InvalidPrefixErr::HostBitsSet { .. } => "host bits set",
+        }
+    }
+}
+
+impl fmt::Display for InvalidPrefixErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let InvalidPrefixErr::Addr(err) = self {
+            return write!(f, "{}", err);
+        }
+        write!(
+            f,
+            "invalid ipv6 prefix string: {} at byte {}",
+            self.reason(),
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidPrefixErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidPrefixErr::Addr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidAddrErr> for InvalidPrefixErr {
+    fn from(err: InvalidAddrErr) -> InvalidPrefixErr {
+        InvalidPrefixErr::Addr(err)
+    }
+}
+
+// Prefix is an IPv6 network prefix in CIDR notation, e.g. 2001:db8::/32. The
+// address is always the first address of the prefix, with no host bits set
+// and no zone. Prefixes are ordered by address and then by length, so a
+// prefix sorts right before the prefixes it contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Prefix {
+    addr: Addr,
+    len: u8,
+}
+
+impl Prefix {
+    // new builds a prefix from its first address and a length. It returns
+    // None if len is greater than 128 or addr has host bits set. A zone on
+    // addr is dropped.
+    pub const fn new(addr: Addr, len: u8) -> Option<Prefix> {
+        if len > 128 || addr.to_bits() & !mask(len) != 0 {
+            return None;
+        }
+        Some(Prefix {
+            addr: addr.without_zone(),
+            len,
+        })
+    }
+
+    // new_masked builds a prefix from any address in it and a length, by
+    // clearing the host bits of addr. It returns None if len is greater
+    // than 128.
+    pub const fn new_masked(addr: Addr, len: u8) -> Option<Prefix> {
+        if len > 128 {
+            return None;
+        }
+        Some(Prefix {
+            addr: Addr::from_bits(addr.to_bits() & mask(len)),
+            len,
+        })
+    }
+
+    // prefix_len returns the length of the prefix in bits.
+    pub const fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    // network returns the first address in the prefix.
+    pub const fn network(&self) -> Addr {
+        self.addr
+    }
+
+    // last returns the last address in the prefix. IPv6 has no broadcast
+    // address, so unlike an ipv4::Prefix this is an ordinary address.
+    pub const fn last(&self) -> Addr {
+        Addr::from_bits(self.addr.to_bits() | !mask(self.len))
+    }
+
+    // netmask returns the prefix length as a mask, e.g. ffff:ffff:: for a /32.
+    pub const fn netmask(&self) -> Addr {
+        Addr::from_bits(mask(self.len))
+    }
+
+    // hostmask returns the inverse of the netmask, e.g. ::ffff:ffff for a /96.
+    pub const fn hostmask(&self) -> Addr {
+        Addr::from_bits(!mask(self.len))
+    }
+
+    // size returns the number of addresses in the prefix. ::/0 has one more
+    // address than a u128 can hold, so its size saturates at u128::MAX.
+    pub const fn size(&self) -> u128 {
+        match self.len {
+            0 => u128::MAX,
+            _ => 1 << (128 - self.len),
+        }
+    }
+
+    // contains returns true if addr is in the prefix. The zone of addr is
+    // ignored.
+    pub const fn contains(&self, addr: Addr) -> bool {
+        addr.to_bits() & mask(self.len) == self.addr.to_bits()
+    }
+
+    // contains_prefix returns true if every address in other is also in
+    // this prefix. A prefix contains itself.
+    pub const fn contains_prefix(&self, other: &Prefix) -> bool {
+        other.len >= self.len && self.contains(other.addr)
+    }
+
+    // overlaps returns true if the two prefixes have any address in common,
+    // which for prefixes means one of them contains the other.
+    pub const fn overlaps(&self, other: &Prefix) -> bool {
+        self.contains_prefix(other) || other.contains_prefix(self)
+    }
+
+    // supernet returns the prefix one bit shorter that contains this one,
+    // or None for ::/0.
+    pub const fn supernet(&self) -> Option<Prefix> {
+        if self.len == 0 {
+            return None;
+        }
+        Prefix::new_masked(self.addr, self.len - 1)
+    }
+
+    // supernets returns an iterator over every prefix that contains this
+    // one, from the next shorter prefix up to ::/0.
+    pub fn supernets(&self) -> Supernets {
+        Supernets {
+            next: self.supernet(),
+        }
+    }
+
+    // subnets returns an iterator over the prefixes of length len that make
+    // up this prefix, in order. It returns None if len is shorter than this
+    // prefix or greater than 128. For example 2001:db8::/48 has 65536 /64
+    // subnets, from 2001:db8::/64 to 2001:db8:0:ffff::/64.
+    pub fn subnets(&self, len: u8) -> Option<Subnets> {
+        if len < self.len || len > 128 {
+            return None;
+        }
+        Some(Subnets {
+            next: Some(self.addr.to_bits()),
+            last: self.last().to_bits() & mask(len),
+            len,
+        })
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+impl FromStr for Prefix {
+    type Err = InvalidPrefixErr;
+
+    fn from_str(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+        parse_prefix(s)
+    }
+}
+
+impl From<Addr> for Prefix {
+    // an address on its own is the /128 prefix that contains only it. Its
+    // zone is dropped.
+    fn from(addr: Addr) -> Prefix {
+        Prefix {
+            addr: addr.without_zone(),
+            len: 128,
+        }
+    }
+}
+
+// Subnets iterates over the subnets of a prefix. See Prefix::subnets. A
+// short prefix can have more subnets than a usize can count, so the size
+// hint saturates.
+#[derive(Debug, Clone)]
+pub struct Subnets {
+    next: Option<u128>,
+    last: u128,
+    len: u8,
+}
+
+impl Iterator for Subnets {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        let next = self.next?;
+        self.next = match next < self.last {
+            true => Some(next + (1 << (128 - self.len))),
+            false => None,
+        };
+        Some(Prefix {
+            addr: Addr::from_bits(next),
+            len: self.len,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let Some(next) = self.next else {
+            return (0, Some(0));
+        };
+        // the number of subnets left, less one so that it fits in a u128.
+        let n = (self.last - next)
+            .checked_shr(128 - self.len as u32)
+            .unwrap_or(0);
+        match usize::try_from(n).ok().and_then(|n| n.checked_add(1)) {
+            Some(n) => (n, Some(n)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+// Supernets iterates over the prefixes that contain a prefix. See
+// Prefix::supernets.
+#[derive(Debug, Clone)]
+pub struct Supernets {
+    next: Option<Prefix>,
+}
+
+impl Iterator for Supernets {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        let prefix = self.next?;
+        self.next = prefix.supernet();
+        Some(prefix)
+    }
+}
+
+// parse_prefix will parse a string in CIDR notation, e.g. "2001:db8::/32",
+// into a Prefix using the default ParseOptions, which reject host bits.
+pub fn parse_prefix(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+    ParseOptions::default().parse_prefix(s)
+}
+
+// parse_prefix_const will parse a string in CIDR notation into a Prefix in a
+// const context, using the default ParseOptions. It is what the
+// ipv6_prefix! macro expands to.
+pub const fn parse_prefix_const(s: &str) -> Result<Prefix, InvalidPrefixErr> {
+    ParseOptions::std_compat().parse_prefix_const(s)
+}
+
+// scan_prefix is the parser behind ParseOptions::parse_prefix and
+// ParseOptions::parse_prefix_const.
+pub(super) const fn scan_prefix(
+    s: &[u8],
+    options: &ParseOptions,
+) -> Result<Prefix, InvalidPrefixErr> {
+    // the address of a prefix never has a zone, whatever the options say.
+    let options = options.zones(Zones::Reject);
+
+    let Some(slash) = find(s, b'/') else {
+        // report a bad address before a missing length, like the ipv4
+        // prefix parser does.
+        if let Err(err) = scan(s, &options, false) {
+            return Err(InvalidPrefixErr::Addr(err));
+        }
+        return Err(InvalidPrefixErr::MissingLength { offset: s.len() });
+    };
+
+    let (addr_str, rest) = s.split_at(slash);
+    let addr = match scan(addr_str, &options, false) {
+        Ok((addr, _)) => addr,
+        Err(err) => return Err(InvalidPrefixErr::Addr(err)),
+    };
+    let len = match parse_len(rest.split_at(1).1, slash + 1) {
+        Ok(len) => len,
+        Err(err) => return Err(err),
+    };
+
+    match options.get_host_bits() {
+        HostBits::Mask => Ok(Prefix::new_masked(addr, len).unwrap()),
+        HostBits::Reject => match Prefix::new(addr, len) {
+            Some(prefix) => Ok(prefix),
+            None => Err(InvalidPrefixErr::HostBitsSet {
+                offset: group_offset(addr_str, (addr.to_bits() & !mask(len)).leading_zeros() / 16),
+            }),
+        },
+    }
+}
+
+// parse_len parses the prefix length after the '/'. start is its offset in
+// the input.
+const fn parse_len(s: &[u8], start: usize) -> Result<u8, InvalidPrefixErr> {
+    if s.is_empty() {
+        return Err(InvalidPrefixErr::InvalidLength { offset: start });
+    }
+    let mut i = 0;
+    while i < s.len() {
+        if !s[i].is_ascii_digit() {
+            return Err(InvalidPrefixErr::InvalidLength { offset: start + i });
+        }
+        i += 1;
+    }
+    if s.len() > 1 && s[0] == b'0' {
+        return Err(InvalidPrefixErr::InvalidLength { offset: start });
+    }
+
+    // the length is a number at this point, but four or more digits can
+    // only be out of range, and checking that first keeps len from overflowing.
+    if s.len() > 3 {
+        return Err(InvalidPrefixErr::LengthOutOfRange { offset: start });
+    }
+    let mut len = 0u16;
+    let mut i = 0;
+    while i < s.len() {
+        len = len * 10 + (s[i] - b'0') as u16;
+        i += 1;
+    }
+    if len > 128 {
+        return Err(InvalidPrefixErr::LengthOutOfRange { offset: start });
+    }
+
+    Ok(len as u8)
+}
+
+// group_offset returns the byte offset in s, a valid address, of the group
+// with the given index. Groups inside a "::" are reported at the "::" and
+// groups of a dotted-quad tail at the start of the dotted quad.
+const fn group_offset(s: &[u8], group: u32) -> usize {
+    let group = group as usize;
+    let mut at = 0;
+    while at + 1 < s.len() && !(s[at] == b':' && s[at + 1] == b':') {
+        at += 1;
+    }
+    if at + 1 >= s.len() {
+        return match nth_group(s, 0, group) {
+            Some(offset) => offset,
+            None => 0,
+        };
+    }
+
+    // head groups are numbered from the front and tail groups from the back.
+    let (head, tail) = s.split_at(at);
+    if let Some(offset) = nth_group(head, 0, group) {
+        return offset;
+    }
+    let tail = tail.split_at(2).1;
+    match group.checked_sub(8 - count_groups(tail)) {
+        Some(n) => match nth_group(tail, at + 2, n) {
+            Some(offset) => offset,
+            None => at,
+        },
+        None => at,
+    }
+}
+
+// nth_group returns the offset of the nth group of s, a run of groups with
+// no "::", where s starts at byte start of the input.
+const fn nth_group(s: &[u8], start: usize, n: usize) -> Option<usize> {
+    if s.is_empty() {
+        return None;
+    }
+    // part is the offset in s of the group being read; a dotted quad
+    // counts as two groups.
+    let mut part = 0;
+    let mut group = 0;
+    let mut dotted = false;
+    let mut i = 0;
+    while i <= s.len() {
+        if i == s.len() || s[i] == b':' {
+            group += if dotted { 2 } else { 1 };
+            if n < group {
+                return Some(start + part);
+            }
+            part = i + 1;
+            dotted = false;
+        } else if s[i] == b'.' {
+            dotted = true;
+        }
+        i += 1;
+    }
+    None
+}
+
+// count_groups returns the number of groups in s, a run of groups with no "::".
+const fn count_groups(s: &[u8]) -> usize {
+    if s.is_empty() {
+        return 0;
+    }
+    // only the last group can be a dotted quad, which counts as two.
+    let mut groups = 1;
+    let mut dotted = false;
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b':' => groups += 1,
+            b'.' => dotted = true,
+            _ => {}
+        }
+        i += 1;
+    }
+    if dotted {
+        groups += 1;
+    }
+    groups
+}
+
+// mask returns the netmask for a prefix length as a u128. len must be at
+// most 128.
+pub(super) const fn mask(len: u8) -> u128 {
+    match len {
+        0 => 0,
+        _ => u128::MAX << (128 - len),
+    }
+}
+
+#[cfg(test)]
+mod prefix_tests {
+    use super::{InvalidPrefixErr, Prefix, group_offset, parse_prefix};
+    use crate::ipv6::{HostBits, InvalidAddrErr, ParseOptions};
+    use crate::testing::ipv6::{addr, prefix};
+
+    #[test]
+    fn test_parse_prefix() {
+        let valids = Vec::from([
+            "::/0",
+            "2001:db8::/32",
+            "2001:db8:1234::/48",
+            "2001:db8:1234:5678::/64",
+            "fe80::/10",
+            "::ffff:0.0.0.0/96",
+            "::1/128",
+            "2001:db8::1/128",
+        ]);
+        for s in valids {
+            assert_eq!(prefix(s).to_string(), s, "round trip of {}", s);
+        }
+
+        let invalids = Vec::from([
+            ("2001:db8::", InvalidPrefixErr::MissingLength { offset: 10 }),
+            (
+                "2001:db8:/32",
+                InvalidPrefixErr::Addr(InvalidAddrErr::EmptyGroup {
+                    offset: 9,
+                    group: 2,
+                }),
+            ),
+            (
+                "fe80::%eth0/64",
+                InvalidPrefixErr::Addr(InvalidAddrErr::ZoneNotAllowed {
+                    offset: 6,
+                    group: 8,
+                }),
+            ),
+            (
+                "2001:db8::/",
+                InvalidPrefixErr::InvalidLength { offset: 11 },
+            ),
+            (
+                "2001:db8::/x",
+                InvalidPrefixErr::InvalidLength { offset: 11 },
+            ),
+            (
+                "2001:db8::/32x",
+                InvalidPrefixErr::InvalidLength { offset: 13 },
+            ),
+            (
+                "2001:db8::/032",
+                InvalidPrefixErr::InvalidLength { offset: 11 },
+            ),
+            (
+                "2001:db8::/129",
+                InvalidPrefixErr::LengthOutOfRange { offset: 11 },
+            ),
+            (
+                "2001:db8::/1000",
+                InvalidPrefixErr::LengthOutOfRange { offset: 11 },
+            ),
+            (
+                "2001:db8::1/32",
+                InvalidPrefixErr::HostBitsSet { offset: 10 },
+            ),
+            (
+                "2001:db8:0:1::/48",
+                InvalidPrefixErr::HostBitsSet { offset: 11 },
+            ),
+            ("2001:db8::/16", InvalidPrefixErr::HostBitsSet { offset: 5 }),
+            ("8000::/0", InvalidPrefixErr::HostBitsSet { offset: 0 }),
+            ("::1.2.3.4/96", InvalidPrefixErr::HostBitsSet { offset: 2 }),
+            (
+                "1:2:3:4:5:6:7:8/120",
+                InvalidPrefixErr::HostBitsSet { offset: 14 },
+            ),
+        ]);
+        for (s, want) in invalids {
+            assert_eq!(parse_prefix(s), Err(want), "parse_prefix({:?})", s);
+        }
+
+        let masked = ParseOptions::default().host_bits(HostBits::Mask);
+        assert_eq!(
+            masked.parse_prefix("2001:db8::1/32"),
+            Ok(prefix("2001:db8::/32"))
+        );
+        assert_eq!(
+            ParseOptions::permissive().parse_prefix("2001:db8:1:2:3::/48"),
+            Ok(prefix("2001:db8:1::/48"))
+        );
+        assert_eq!(
+            ParseOptions::permissive().parse_prefix("fe80::%eth0/64"),
+            Err(InvalidPrefixErr::Addr(InvalidAddrErr::ZoneNotAllowed {
+                offset: 6,
+                group: 8,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_group_offset() {
+        let cases = Vec::from([
+            ("1:2:3:4:5:6:7:8", [0, 2, 4, 6, 8, 10, 12, 14]),
+            ("1::8", [0, 1, 1, 1, 1, 1, 1, 3]),
+            ("::7:8", [0, 0, 0, 0, 0, 0, 2, 4]),
+            ("1:2::", [0, 2, 3, 3, 3, 3, 3, 3]),
+            ("::ffff:1.2.3.4", [0, 0, 0, 0, 0, 2, 7, 7]),
+            ("1:2:3:4:5:6:1.2.3.4", [0, 2, 4, 6, 8, 10, 12, 12]),
+        ]);
+        for (s, want) in cases {
+            for (group, want) in want.into_iter().enumerate() {
+                assert_eq!(
+                    group_offset(s.as_bytes(), group as u32),
+                    want,
+                    "group {} of {:?}",
+                    group,
+                    s
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_network_math() {
+        let cases = Vec::from([
+            // prefix, last, netmask, hostmask, size
+            (
+                "2001:db8::/32",
+                "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff",
+                "ffff:ffff::",
+                "::ffff:ffff:ffff:ffff:ffff:ffff",
+                1 << 96,
+            ),
+            (
+                "2001:db8:1:2::/64",
+                "2001:db8:1:2:ffff:ffff:ffff:ffff",
+                "ffff:ffff:ffff:ffff::",
+                "::ffff:ffff:ffff:ffff",
+                1 << 64,
+            ),
+            (
+                "fe80::/10",
+                "febf:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                "ffc0::",
+                "3f:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                1 << 118,
+            ),
+            (
+                "2001:db8::1/128",
+                "2001:db8::1",
+                "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                "::",
+                1,
+            ),
+            (
+                "::/0",
+                "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                "::",
+                "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+                u128::MAX,
+            ),
+        ]);
+
+        for (p, last, netmask, hostmask, size) in cases {
+            let p = prefix(p);
+            assert_eq!(p.last(), addr(last), "last of {}", p);
+            assert_eq!(p.netmask(), addr(netmask), "netmask of {}", p);
+            assert_eq!(p.hostmask(), addr(hostmask), "hostmask of {}", p);
+            assert_eq!(p.size(), size, "size of {}", p);
+        }
+
+        assert_eq!(Prefix::new(addr("2001:db8::1"), 64), None);
+        assert_eq!(Prefix::new(addr("2001:db8::"), 129), None);
+        assert_eq!(
+            Prefix::new_masked(addr("2001:db8::1"), 64),
+            Some(prefix("2001:db8::/64"))
+        );
+        assert_eq!(Prefix::from(addr("2001:db8::1")), prefix("2001:db8::1/128"));
+    }
+
+    #[test]
+    fn test_containment() {
+        let p = prefix("2001:db8::/32");
+        assert!(p.contains(addr("2001:db8::")));
+        assert!(p.contains(addr("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff")));
+        assert!(!p.contains(addr("2001:db9::")));
+        assert!(!p.contains(addr("2001:db7:ffff:ffff:ffff:ffff:ffff:ffff")));
+        assert!(prefix("::/0").contains(addr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")));
+
+        let zoned = ParseOptions::permissive().parse("fe80::1%eth0").unwrap();
+        assert!(prefix("fe80::/10").contains(zoned));
+        assert_eq!(Prefix::from(zoned), prefix("fe80::1/128"));
+
+        assert!(p.contains_prefix(&p));
+        assert!(p.contains_prefix(&prefix("2001:db8:1::/48")));
+        assert!(!p.contains_prefix(&prefix("::/0")));
+        assert!(!p.contains_prefix(&prefix("2001:db9::/48")));
+
+        assert!(p.overlaps(&prefix("2001:db8:1::/48")));
+        assert!(prefix("2001:db8:1::/48").overlaps(&p));
+        assert!(!prefix("2001:db8::/48").overlaps(&prefix("2001:db8:1::/48")));
+    }
+
+    #[test]
+    fn test_supernets_and_subnets() {
+        let p = prefix("2001:db8:1234::/48");
+        assert_eq!(p.supernet(), Some(prefix("2001:db8:1234::/47")));
+        assert_eq!(prefix("::/0").supernet(), None);
+        let supernets: Vec<Prefix> = p.supernets().collect();
+        assert_eq!(supernets.len(), 48);
+        assert_eq!(supernets[0], prefix("2001:db8:1234::/47"));
+        assert_eq!(supernets[47], prefix("::/0"));
+
+        // a /48 splits into 65536 /64s.
+        let subnets = p.subnets(64).unwrap();
+        assert_eq!(subnets.size_hint(), (65536, Some(65536)));
+        let subnets: Vec<Prefix> = subnets.collect();
+        assert_eq!(subnets.len(), 65536);
+        assert_eq!(subnets[0], prefix("2001:db8:1234::/64"));
+        assert_eq!(subnets[1], prefix("2001:db8:1234:1::/64"));
+        assert_eq!(subnets[65535], prefix("2001:db8:1234:ffff::/64"));
+
+        let subnets: Vec<Prefix> = prefix("2001:db8::/31").subnets(32).unwrap().collect();
+        assert_eq!(subnets, [prefix("2001:db8::/32"), prefix("2001:db9::/32")]);
+        let subnets: Vec<Prefix> = p.subnets(48).unwrap().collect();
+        assert_eq!(subnets, [p]);
+        assert!(p.subnets(47).is_none());
+        assert!(p.subnets(129).is_none());
+
+        // the ends of the address space.
+        let all = prefix("::/0");
+        let mut halves = all.subnets(1).unwrap();
+        assert_eq!(halves.next(), Some(prefix("::/1")));
+        assert_eq!(halves.next(), Some(prefix("8000::/1")));
+        assert_eq!(halves.next(), None);
+        assert_eq!(all.subnets(0).unwrap().collect::<Vec<_>>(), [all]);
+        assert_eq!(all.subnets(128).unwrap().size_hint(), (usize::MAX, None));
+        let last = prefix("ffff:ffff:ffff:ffff:ffff:ffff:ffff:fffe/127");
+        assert_eq!(last.subnets(128).unwrap().count(), 2);
+    }
+}
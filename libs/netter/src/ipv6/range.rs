@@ -0,0 +1,445 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Addr, InvalidAddrErr, ParseOptions, Prefix, Zones};
+
+// InvalidRangeErr describes why a string is not a valid IPv6 address range.
+// Like InvalidAddrErr, every variant carries the byte offset in the input
+// where the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidRangeErr {
+    // the first or the last address is invalid. A range never has a zone,
+    // so "fe80::1%eth0-fe80::9" fails with InvalidAddrErr::ZoneNotAllowed.
+    Addr(InvalidAddrErr),
+    // there was no '-' after the first address, e.g. "2001:db8::1".
+    MissingEnd { offset: usize },
+    // the last address is before the first, e.g. "2001:db8::9-2001:db8::1".
+    // offset points at the last address.
+    Reversed { offset: usize },
+}
+
+impl InvalidRangeErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidRangeErr::Addr(err) => err.offset(),
+            InvalidRangeErr::MissingEnd { offset } | InvalidRangeErr::Reversed { offset } => offset,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidRangeErr::Addr(err) => err.reason(),
+            InvalidRangeErr::MissingEnd { .. } => "missing end of range",
+            InvalidRangeErr::Reversed { .. } => "end of range before start",
+        }
+    }
+}
+
+impl fmt::Display for InvalidRangeErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let InvalidRangeErr::Addr(err) = self {
+            return write!(f, "{}", err);
+        }
+        write!(
+            f,
+            "invalid ipv6 range string: {} at byte {}",
+            self.reason(),
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidRangeErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidRangeErr::Addr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidAddrErr> for InvalidRangeErr {
+    fn from(err: InvalidAddrErr) -> InvalidRangeErr {
+        InvalidRangeErr::Addr(err)
+    }
+}
+
+// AddrRange is an inclusive range of IPv6 addresses, e.g.
+// 2001:db8::5-2001:db8::3:17. Like a Prefix it never has a zone. Ranges are
+// ordered by start and then by end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AddrRange {
+    start: Addr,
+    end: Addr,
+}
+
+impl AddrRange {
+    // new builds the range from start to end, both included, dropping any
+    // zones. It returns None if end is before start.
+    pub const fn new(start: Addr, end: Addr) -> Option<AddrRange> {
+        if end.to_bits() < start.to_bits() {
+            return None;
+        }
+        Some(AddrRange {
+            start: start.without_zone(),
+            end: end.without_zone(),
+        })
+    }
+
+    // start returns the first address in the range.
+    pub const fn start(&self) -> Addr {
+        self.start
+    }
+
+    // end returns the last address in the range.
+    pub const fn end(&self) -> Addr {
+        self.end
+    }
+
+    // size returns the number of addresses in the range. The range of every
+    // address has one more address than a u128 can hold, so its size
+    // saturates at u128::MAX, like Prefix::size.
+    pub const fn size(&self) -> u128 {
+        (self.end.to_bits() - self.start.to_bits()).saturating_add(1)
+    }
+
+    // contains returns true if addr is in the range. The zone of addr is
+    // ignored.
+    pub const fn contains(&self, addr: Addr) -> bool {
+        self.start.to_bits() <= addr.to_bits() && addr.to_bits() <= self.end.to_bits()
+    }
+
+    // contains_range returns true if every address in other is also in this
+    // range. A range contains itself.
+    pub const fn contains_range(&self, other: &AddrRange) -> bool {
+        self.contains(other.start) && self.contains(other.end)
+    }
+
+    // overlaps returns true if the two ranges have any address in common.
+    pub const fn overlaps(&self, other: &AddrRange) -> bool {
+        self.start.to_bits() <= other.end.to_bits() && other.start.to_bits() <= self.end.to_bits()
+    }
+
+    // iter returns an iterator over the addresses in the range, in order.
+    pub fn iter(&self) -> Addrs {
+        Addrs {
+            next: Some(self.start.to_bits()),
+            last: self.end.to_bits(),
+        }
+    }
+
+    // prefixes returns an iterator over the fewest prefixes that together
+    // cover exactly the range, in order. For example 2001:db8::5-2001:db8::11
+    // is 2001:db8::5/128, 2001:db8::6/127, 2001:db8::8/125 and
+    // 2001:db8::10/127.
+    pub fn prefixes(&self) -> Prefixes {
+        Prefixes {
+            next: Some(self.start.to_bits()),
+            last: self.end.to_bits(),
+        }
+    }
+}
+
+impl fmt::Display for AddrRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl FromStr for AddrRange {
+    type Err = InvalidRangeErr;
+
+    fn from_str(s: &str) -> Result<AddrRange, InvalidRangeErr> {
+        parse_range(s)
+    }
+}
+
+impl From<Prefix> for AddrRange {
+    // a prefix is the range from its first address to its last.
+    fn from(prefix: Prefix) -> AddrRange {
+        AddrRange {
+            start: prefix.network(),
+            end: prefix.last(),
+        }
+    }
+}
+
+impl From<Addr> for AddrRange {
+    // an address on its own is the range that contains only it.
+    fn from(addr: Addr) -> AddrRange {
+        AddrRange::new(addr, addr).unwrap()
+    }
+}
+
+impl IntoIterator for AddrRange {
+    type Item = Addr;
+    type IntoIter = Addrs;
+
+    fn into_iter(self) -> Addrs {
+        self.iter()
+    }
+}
+
+// Addrs iterates over the addresses in a range. See AddrRange::iter. A
+// range can have more addresses than a usize can count, so the size hint
+// saturates.
+#[derive(Debug, Clone)]
+pub struct Addrs {
+    next: Option<u128>,
+    last: u128,
+}
+
+impl Iterator for Addrs {
+    type Item = Addr;
+
+    fn next(&mut self) -> Option<Addr> {
+        let next = self.next?;
+        self.next = match next < self.last {
+            true => Some(next + 1),
+            false => None,
+        };
+        Some(Addr::from_bits(next))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let Some(next) = self.next else {
+            return (0, Some(0));
+        };
+        match usize::try_from(self.last - next)
+            .ok()
+            .and_then(|n| n.checked_add(1))
+        {
+            Some(n) => (n, Some(n)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+// Prefixes iterates over the prefixes that make up a range. See
+// AddrRange::prefixes.
+#[derive(Debug, Clone)]
+pub struct Prefixes {
+    next: Option<u128>,
+    last: u128,
+}
+
+impl Iterator for Prefixes {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        let next = self.next?;
+        // the largest prefix that starts at next is limited by how next is
+        // aligned and by how many addresses are left; taking it every time
+        // gives the fewest prefixes. span is the size of the prefix less
+        // one, which always fits in a u128.
+        let left = self.last - next;
+        let fits = match left.checked_add(1) {
+            Some(n) => 127 - n.leading_zeros(),
+            None => 128,
+        };
+        let bits = next.trailing_zeros().min(fits);
+        let span = u128::MAX.checked_shr(128 - bits).unwrap_or(0);
+        self.next = match span < left {
+            true => Some(next + span + 1),
+            false => None,
+        };
+        Some(Prefix::new(Addr::from_bits(next), (128 - bits) as u8).unwrap())
+    }
+}
+
+// parse_range will parse a range of addresses written as the first and the
+// last address with a '-' between them, e.g. "2001:db8::5-2001:db8::3:17",
+// into an AddrRange. Spaces around the '-' are allowed, as in the ipv4
+// parse_range. It uses the default ParseOptions.
+pub fn parse_range(s: &str) -> Result<AddrRange, InvalidRangeErr> {
+    ParseOptions::default().parse_range(s)
+}
+
+// scan_range is the parser behind ParseOptions::parse_range.
+pub(super) fn scan_range(s: &str, options: &ParseOptions) -> Result<AddrRange, InvalidRangeErr> {
+    // the addresses of a range never have a zone, whatever the options say.
+    let options = options.zones(Zones::Reject);
+
+    let Some(dash) = s.find('-') else {
+        // report a bad address before a missing end, like the prefix parser.
+        options.parse(s)?;
+        return Err(InvalidRangeErr::MissingEnd { offset: s.len() });
+    };
+
+    let first = s[..dash].trim_end_matches(' ');
+    let rest = &s[dash + 1..];
+    let last_start = dash + 1 + rest.len() - rest.trim_start_matches(' ').len();
+    let start = options.parse(first)?;
+    let end = options
+        .parse(&s[last_start..])
+        .map_err(|err| err.shifted(last_start))?;
+
+    AddrRange::new(start, end).ok_or(InvalidRangeErr::Reversed { offset: last_start })
+}
+
+// collapse_prefixes merges prefixes into the fewest ranges that cover the
+// same addresses, in order. Prefixes that overlap or are next to each other
+// become one range; the input does not have to be sorted.
+#[cfg(feature = "alloc")]
+pub fn collapse_prefixes(prefixes: impl IntoIterator<Item = Prefix>) -> alloc::vec::Vec<AddrRange> {
+    let mut ranges: alloc::vec::Vec<AddrRange> =
+        prefixes.into_iter().map(AddrRange::from).collect();
+    ranges.sort_unstable();
+
+    let mut merged: alloc::vec::Vec<AddrRange> = alloc::vec::Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start.to_bits() <= last.end.to_bits().saturating_add(1) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod range_tests {
+    use super::{AddrRange, InvalidRangeErr, parse_range};
+    use crate::ipv6::{Addr, InvalidAddrErr, ParseOptions, Prefix};
+    use crate::testing::ipv6::{addr, prefix, range};
+
+    #[test]
+    fn test_parse_range() {
+        let cases = Vec::from([
+            (
+                "2001:db8::5-2001:db8::3:17",
+                Ok(range("2001:db8::5", "2001:db8::3:17")),
+            ),
+            (
+                "2001:db8:: - 2001:db8::ff",
+                Ok(range("2001:db8::", "2001:db8::ff")),
+            ),
+            ("::-::", Ok(range("::", "::"))),
+            ("::9-::1", Err(InvalidRangeErr::Reversed { offset: 4 })),
+            (
+                "2001:db8::1",
+                Err(InvalidRangeErr::MissingEnd { offset: 11 }),
+            ),
+            (
+                "fe80::1%eth0-fe80::9",
+                Err(InvalidRangeErr::Addr(InvalidAddrErr::ZoneNotAllowed {
+                    offset: 7,
+                    group: 8,
+                })),
+            ),
+            (
+                "::1 - ::g",
+                Err(InvalidRangeErr::Addr(InvalidAddrErr::InvalidChar {
+                    offset: 8,
+                    group: 0,
+                })),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_range(s), want, "parse_range({:?})", s);
+        }
+
+        let r = range("2001:db8::5", "2001:db8::3:17");
+        assert_eq!(r.to_string(), "2001:db8::5-2001:db8::3:17");
+        assert_eq!(r.to_string().parse::<AddrRange>(), Ok(r));
+    }
+
+    #[test]
+    fn test_range_membership() {
+        let r = range("2001:db8::5", "2001:db8::3:17");
+        assert_eq!(r.size(), 0x3_0013);
+        assert!(r.contains(addr("2001:db8::5")));
+        assert!(r.contains(addr("2001:db8::3:17")));
+        assert!(!r.contains(addr("2001:db8::4")));
+        assert!(r.contains_range(&range("2001:db8::1:0", "2001:db8::1:ffff")));
+        assert!(r.overlaps(&range("2001:db8::3:0", "2001:db9::")));
+        assert!(!r.overlaps(&range("2001:db8::3:18", "2001:db9::")));
+
+        let all = range("::", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff");
+        assert_eq!(all.size(), u128::MAX);
+        assert_eq!(all.iter().size_hint(), (usize::MAX, None));
+        assert_eq!(
+            AddrRange::from(prefix("2001:db8::/32")),
+            range("2001:db8::", "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff")
+        );
+        let scoped = ParseOptions::permissive().parse("fe80::1%eth0").unwrap();
+        assert_eq!(AddrRange::from(scoped).start().zone(), None);
+
+        let addrs: Vec<Addr> = range("::fffe", "::1:1").into_iter().collect();
+        assert_eq!(addrs, ["::fffe", "::ffff", "::1:0", "::1:1"].map(addr));
+        let last = addr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff");
+        assert_eq!(AddrRange::from(last).iter().count(), 1);
+    }
+
+    #[test]
+    fn test_prefixes() {
+        let cases = Vec::from([
+            (
+                range("2001:db8::5", "2001:db8::11"),
+                Vec::from([
+                    "2001:db8::5/128",
+                    "2001:db8::6/127",
+                    "2001:db8::8/125",
+                    "2001:db8::10/127",
+                ]),
+            ),
+            (
+                range("::", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+                Vec::from(["::/0"]),
+            ),
+            (
+                range("2001:db8::", "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"),
+                Vec::from(["2001:db8::/32"]),
+            ),
+        ]);
+
+        for (r, want) in cases {
+            let got: Vec<Prefix> = r.prefixes().collect();
+            let want: Vec<Prefix> = want.into_iter().map(prefix).collect();
+            assert_eq!(got, want, "prefixes of {}", r);
+        }
+
+        // everything but :: is one prefix of every length from /128 down to
+        // /1: ::1/128, ::2/127, and so on up to 8000::/1.
+        let r = range("::1", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff");
+        let got: Vec<Prefix> = r.prefixes().collect();
+        assert_eq!(got.len(), 128);
+        for (i, p) in got.iter().enumerate() {
+            assert_eq!(p.prefix_len() as usize, 128 - i, "prefix {} of {}", i, r);
+        }
+        assert_eq!(got[127], prefix("8000::/1"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_collapse_prefixes() {
+        use super::collapse_prefixes;
+
+        let cases = Vec::from([
+            (
+                Vec::from(["2001:db8:1::/48", "2001:db8::/48", "2001:db8:3::/48"]),
+                Vec::from([
+                    range("2001:db8::", "2001:db8:1:ffff:ffff:ffff:ffff:ffff"),
+                    range("2001:db8:3::", "2001:db8:3:ffff:ffff:ffff:ffff:ffff"),
+                ]),
+            ),
+            (
+                Vec::from(["::/0", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff/128"]),
+                Vec::from([range("::", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")]),
+            ),
+        ]);
+
+        for (prefixes, want) in cases {
+            let got = collapse_prefixes(prefixes.iter().map(|p| prefix(p)));
+            assert_eq!(got, want, "collapse of {:?}", prefixes);
+        }
+
+        let r = range("2001:db8::5", "2001:db8::3:17");
+        assert_eq!(collapse_prefixes(r.prefixes()), Vec::from([r]));
+    }
+}
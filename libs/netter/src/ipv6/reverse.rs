@@ -0,0 +1,292 @@
+use core::fmt;
+
+use super::{Addr, Prefix};
+use crate::reverse::labels;
+
+// InvalidReverseNameErr describes why a string is not a valid ip6.arpa
+// name. Like InvalidAddrErr, every variant carries the byte offset in the
+// input where the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidReverseNameErr {
+    // the name does not end in "ip6.arpa", e.g. "1.0.0.2.in-addr.arpa".
+    // offset is the end of the name.
+    MissingSuffix { offset: usize },
+    // a label is empty, e.g. "1..0.2.ip6.arpa".
+    EmptyLabel { offset: usize },
+    // a label is not a single hex digit, e.g. "10.0.0.2.ip6.arpa".
+    InvalidLabel { offset: usize },
+    // there were more than 32 labels.
+    TooManyLabels { offset: usize },
+    // there were fewer than 32 labels where a whole address was expected,
+    // e.g. "8.b.d.0.1.0.0.2.ip6.arpa". offset is the start of the suffix.
+    TooFewLabels { offset: usize },
+}
+
+impl InvalidReverseNameErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidReverseNameErr::MissingSuffix { offset }
+            | InvalidReverseNameErr::EmptyLabel { offset }
+            | InvalidReverseNameErr::InvalidLabel { offset }
+            | InvalidReverseNameErr::TooManyLabels { offset }
+            | InvalidReverseNameErr::TooFewLabels { offset } => offset,
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidReverseNameErr::MissingSuffix { .. } => "missing ip6.arpa suffix",
+            InvalidReverseNameErr::EmptyLabel { .. } => "empty label",
+            InvalidReverseNameErr::InvalidLabel { .. } => "invalid label",
+            InvalidReverseNameErr::TooManyLabels { .. } => "too many labels",
+            InvalidReverseNameErr::TooFewLabels { .. } => "too few labels",
+        }
+    }
+}
+
+impl fmt::Display for InvalidReverseNameErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid ipv6 reverse name: {} at byte {}",
+            self.reason(),
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidReverseNameErr {}
+
+// ReverseName renders an Addr as the ip6.arpa name its PTR record lives at.
+// See Addr::reverse_name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseName(Addr);
+
+impl fmt::Display for ReverseName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_nibbles(f, self.0.to_bits(), 32)
+    }
+}
+
+// ReverseZone renders a Prefix as the name of its ip6.arpa zone. See
+// Prefix::reverse_zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseZone(Prefix);
+
+impl fmt::Display for ReverseZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.0.prefix_len() as usize / 4;
+        write_nibbles(f, self.0.network().to_bits(), n)
+    }
+}
+
+// write_nibbles writes the first n nibbles of bits as ip6.arpa labels, the
+// last of them first, followed by the suffix.
+fn write_nibbles(f: &mut fmt::Formatter, bits: u128, n: usize) -> fmt::Result {
+    for i in (0..n).rev() {
+        write!(f, "{:x}.", bits >> (124 - 4 * i) & 0xf)?;
+    }
+    write!(f, "ip6.arpa")
+}
+
+impl Addr {
+    // reverse_name returns the ip6.arpa name for the address, as used for
+    // its PTR record: its 32 nibbles in reverse order, as RFC 3596 section
+    // 2.5 specifies, e.g. "1.0.0.0. ... .8.b.d.0.1.0.0.2.ip6.arpa" for
+    // 2001:db8::1. Any zone is left out. Like ipv4::Addr::reverse_name, it
+    // is written without a trailing dot.
+    pub fn reverse_name(&self) -> ReverseName {
+        ReverseName(*self)
+    }
+}
+
+impl Prefix {
+    // reverse_zone returns the ip6.arpa zone that holds the PTR records for
+    // the prefix, which is one label per nibble of the network:
+    // 2001:db8::/32 is "8.b.d.0.1.0.0.2.ip6.arpa". A prefix whose length is
+    // not a multiple of 4 has no zone of its own, and None is returned; its
+    // records are spread over the zones of subnets(len rounded up to a
+    // multiple of 4).
+    pub fn reverse_zone(&self) -> Option<ReverseZone> {
+        self.prefix_len()
+            .is_multiple_of(4)
+            .then_some(ReverseZone(*self))
+    }
+}
+
+// parse_reverse_name will parse the ip6.arpa name of a PTR record, as in a
+// PTR query, back into an Addr. The name must have all 32 nibble labels;
+// the hex digits and the suffix may be in any case, and the name may end in
+// a dot.
+pub fn parse_reverse_name(s: &str) -> Result<Addr, InvalidReverseNameErr> {
+    let (bits, n, end) = scan_reverse(s.as_bytes())?;
+    if n < 32 {
+        return Err(InvalidReverseNameErr::TooFewLabels { offset: end });
+    }
+    Ok(Addr::from_bits(bits))
+}
+
+// parse_reverse_zone will parse an ip6.arpa zone name back into a Prefix
+// with a length of 4 bits per label, so "8.b.d.0.1.0.0.2.ip6.arpa" is
+// 2001:db8::/32 and "ip6.arpa" is ::/0.
+pub fn parse_reverse_zone(s: &str) -> Result<Prefix, InvalidReverseNameErr> {
+    let (bits, n, _) = scan_reverse(s.as_bytes())?;
+    Ok(Prefix::new(Addr::from_bits(bits), 4 * n as u8).unwrap())
+}
+
+// scan_reverse reads the labels of an ip6.arpa name into the leading bits
+// of an address, and returns them with the number of labels and the offset
+// of the suffix.
+fn scan_reverse(bytes: &[u8]) -> Result<(u128, usize, usize), InvalidReverseNameErr> {
+    let Some((labels, end)) = labels(bytes, b"ip6.arpa") else {
+        return Err(InvalidReverseNameErr::MissingSuffix {
+            offset: bytes.len(),
+        });
+    };
+
+    // the labels are read from the last nibble to the first, so each one is
+    // shifted in at the top of what has been read so far.
+    let mut bits: u128 = 0;
+    let mut n = 0;
+    for (start, label) in labels {
+        if n == 32 {
+            return Err(InvalidReverseNameErr::TooManyLabels { offset: start });
+        }
+        let nibble = match label {
+            [] => return Err(InvalidReverseNameErr::EmptyLabel { offset: start }),
+            [b] => (*b as char).to_digit(16),
+            _ => None,
+        };
+        let Some(nibble) = nibble else {
+            return Err(InvalidReverseNameErr::InvalidLabel { offset: start });
+        };
+        bits = bits >> 4 | (nibble as u128) << 124;
+        n += 1;
+    }
+    Ok((bits, n, end))
+}
+
+#[cfg(test)]
+mod reverse_tests {
+    use super::{InvalidReverseNameErr, parse_reverse_name, parse_reverse_zone};
+    use crate::ipv6::{Addr, Prefix};
+    use crate::rng::Rng;
+    use crate::testing::ipv6::{addr, prefix};
+
+    #[test]
+    fn test_reverse_name() {
+        let cases = Vec::from([
+            (
+                "2001:db8::567:89ab",
+                "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+            ),
+            (
+                "::",
+                "0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.ip6.arpa",
+            ),
+            (
+                "fe80::1%eth0",
+                "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.e.f.ip6.arpa",
+            ),
+        ]);
+
+        for (s, want) in cases {
+            let addr = addr(s);
+            assert_eq!(addr.reverse_name().to_string(), want, "{}", s);
+            assert_eq!(
+                parse_reverse_name(want),
+                Ok(addr.without_zone()),
+                "{}",
+                want
+            );
+        }
+    }
+
+    #[test]
+    fn test_reverse_zone() {
+        let cases = Vec::from([
+            ("::/0", Some("ip6.arpa")),
+            ("2000::/4", Some("2.ip6.arpa")),
+            ("2001:db8::/32", Some("8.b.d.0.1.0.0.2.ip6.arpa")),
+            ("2001:db8:1230::/44", Some("3.2.1.8.b.d.0.1.0.0.2.ip6.arpa")),
+            ("2001:db8::/31", None),
+            ("2001:db8::/126", None),
+        ]);
+
+        for (s, want) in cases {
+            let prefix = prefix(s);
+            let got = prefix.reverse_zone().map(|zone| zone.to_string());
+            assert_eq!(got.as_deref(), want, "{}", s);
+            if let Some(name) = want {
+                assert_eq!(parse_reverse_zone(name), Ok(prefix), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reverse() {
+        let full = "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2";
+        let cases = Vec::from([
+            (format!("{}.ip6.arpa.", full), Ok(addr("2001:db8::1"))),
+            (
+                format!("{}.IP6.ARPA", full.to_uppercase()),
+                Ok(addr("2001:db8::1")),
+            ),
+            (
+                format!("{}.in-addr.arpa", full),
+                Err(InvalidReverseNameErr::MissingSuffix { offset: 76 }),
+            ),
+            (
+                format!("0.{}.ip6.arpa", full),
+                Err(InvalidReverseNameErr::TooManyLabels { offset: 64 }),
+            ),
+            (
+                "8.b.d.0.1.0.0.2.ip6.arpa".to_string(),
+                Err(InvalidReverseNameErr::TooFewLabels { offset: 16 }),
+            ),
+            (
+                "8.b..0.1.0.0.2.ip6.arpa".to_string(),
+                Err(InvalidReverseNameErr::EmptyLabel { offset: 4 }),
+            ),
+            (
+                "8.b.d.01.0.0.2.ip6.arpa".to_string(),
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 6 }),
+            ),
+            (
+                "8.b.g.0.1.0.0.2.ip6.arpa".to_string(),
+                Err(InvalidReverseNameErr::InvalidLabel { offset: 4 }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_reverse_name(&s), want, "{}", s);
+        }
+
+        assert_eq!(
+            parse_reverse_zone("8.B.D.0.1.0.0.2.ip6.arpa."),
+            Ok(prefix("2001:db8::/32")),
+            "upper case zone"
+        );
+    }
+
+    #[test]
+    fn test_reverse_round_trip() {
+        // every nibble-aligned prefix must come back from its name.
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+
+        for _ in 0..10_000 {
+            let addr = Addr::from_bits(rng.next_u128());
+            let name = addr.reverse_name().to_string();
+            assert_eq!(parse_reverse_name(&name), Ok(addr), "{}", name);
+
+            let prefix = Prefix::new_masked(addr, (rng.next() % 129) as u8).unwrap();
+            if let Some(zone) = prefix.reverse_zone() {
+                let name = zone.to_string();
+                assert_eq!(parse_reverse_zone(&name), Ok(prefix), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use core::fmt;
+use core::net::SocketAddrV6;
+use core::str::FromStr;
+
+use super::{Addr, InvalidAddrErr, ParseOptions, Zone};
+
+// InvalidSocketAddrErr describes why a string is not a valid IPv6 socket
+// address. Like InvalidAddrErr, every variant carries the byte offset in the
+// input where the problem was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidSocketAddrErr {
+    // the address between the brackets is invalid.
+    Addr(InvalidAddrErr),
+    // the address was not enclosed in square brackets, e.g. "::1:80".
+    MissingBracket { offset: usize },
+    // there was no ':' and port after the closing bracket, e.g. "[::1]".
+    MissingPort { offset: usize },
+    // the port is not a decimal number from 0 to 65535, e.g. "[::1]:http".
+    InvalidPort { offset: usize },
+}
+
+impl InvalidSocketAddrErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match *self {
+            InvalidSocketAddrErr::Addr(err) => err.offset(),
+            InvalidSocketAddrErr::MissingBracket { offset }
+            | InvalidSocketAddrErr::MissingPort { offset }
+            | InvalidSocketAddrErr::InvalidPort { offset } => offset,
+        }
+    }
+}
+
+impl fmt::Display for InvalidSocketAddrErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            InvalidSocketAddrErr::Addr(err) => return write!(f, "{}", err),
+            InvalidSocketAddrErr::MissingBracket { .. } => "missing bracket",
+            InvalidSocketAddrErr::MissingPort { .. } => "missing port",
+            InvalidSocketAddrErr::InvalidPort { .. } => "invalid port",
+        };
+        write!(
+            f,
+            "invalid ipv6 socket address string: {} at byte {}",
+            reason,
+            self.offset()
+        )
+    }
+}
+
+impl core::error::Error for InvalidSocketAddrErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidSocketAddrErr::Addr(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidAddrErr> for InvalidSocketAddrErr {
+    fn from(err: InvalidAddrErr) -> InvalidSocketAddrErr {
+        InvalidSocketAddrErr::Addr(err)
+    }
+}
+
+// SocketAddr is an IPv6 address, with its zone if it has one, and a port,
+// e.g. [fe80::1%eth0]:8080. Unlike std::net::SocketAddrV6 the zone can be an
+// interface name as well as an index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SocketAddr {
+    addr: Addr,
+    port: u16,
+}
+
+impl SocketAddr {
+    // new builds a socket address from an address and a port.
+    pub const fn new(addr: Addr, port: u16) -> SocketAddr {
+        SocketAddr { addr, port }
+    }
+
+    // addr returns the address, with its zone.
+    pub const fn addr(&self) -> Addr {
+        self.addr
+    }
+
+    // port returns the port.
+    pub const fn port(&self) -> u16 {
+        self.port
+    }
+
+    // to_std returns the socket address as a std::net::SocketAddrV6, with a
+    // Zone::Index as its scope_id. It returns None if the zone is a
+    // Zone::Name, which only the operating system can turn into an index.
+    pub fn to_std(&self) -> Option<SocketAddrV6> {
+        let scope_id = match self.addr.zone() {
+            None => 0,
+            Some(Zone::Index(index)) => index,
+            Some(Zone::Name(_)) => return None,
+        };
+        Some(SocketAddrV6::new(self.addr.into(), self.port, 0, scope_id))
+    }
+}
+
+impl fmt::Display for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}]:{}", self.addr, self.port)
+    }
+}
+
+impl FromStr for SocketAddr {
+    type Err = InvalidSocketAddrErr;
+
+    fn from_str(s: &str) -> Result<SocketAddr, InvalidSocketAddrErr> {
+        parse_socket(s)
+    }
+}
+
+impl From<SocketAddrV6> for SocketAddr {
+    // from converts a std socket address. A scope_id of 0 means no zone;
+    // the flow label is dropped.
+    fn from(sa: SocketAddrV6) -> SocketAddr {
+        let addr = Addr::from(*sa.ip());
+        let addr = match sa.scope_id() {
+            0 => addr,
+            index => addr.with_zone(Zone::Index(index)),
+        };
+        SocketAddr::new(addr, sa.port())
+    }
+}
+
+// parse_socket will parse an address in square brackets followed by a colon
+// and a decimal port into a SocketAddr, e.g. "[2001:db8::1]:443" or
+// "[fe80::1%eth0]:22". The zone, if any, is written as in an address, not
+// percent-encoded as in a URI. It uses the default ParseOptions, which do not
+// allow zones; see ParseOptions::parse_socket to pick a different profile.
+pub fn parse_socket(s: &str) -> Result<SocketAddr, InvalidSocketAddrErr> {
+    ParseOptions::default().parse_socket(s)
+}
+
+// scan_socket is the scanner behind parse_socket.
+pub(super) fn scan_socket(
+    s: &str,
+    options: &ParseOptions,
+) -> Result<SocketAddr, InvalidSocketAddrErr> {
+    if !s.starts_with('[') {
+        return Err(InvalidSocketAddrErr::MissingBracket { offset: 0 });
+    }
+    let Some(end) = s.find(']') else {
+        return Err(InvalidSocketAddrErr::MissingBracket { offset: s.len() });
+    };
+    let addr = options.parse(&s[1..end]).map_err(|err| err.shifted(1))?;
+
+    let Some(port) = s[end + 1..].strip_prefix(':') else {
+        return Err(InvalidSocketAddrErr::MissingPort { offset: end + 1 });
+    };
+    let start = end + 2;
+    if let Some(i) = port.bytes().position(|b| !b.is_ascii_digit()) {
+        return Err(InvalidSocketAddrErr::InvalidPort { offset: start + i });
+    }
+    let port = port
+        .parse()
+        .map_err(|_| InvalidSocketAddrErr::InvalidPort { offset: start })?;
+
+    Ok(SocketAddr::new(addr, port))
+}
+
+#[cfg(test)]
+mod socket_tests {
+    use super::{InvalidSocketAddrErr, SocketAddr, parse_socket};
+    use crate::ipv6::{Addr, InvalidAddrErr, ParseOptions, Zone, ZoneName, Zones};
+    use crate::testing::ipv6::addr;
+    use std::net::SocketAddrV6;
+
+    #[test]
+    fn test_parse_socket() {
+        let zoned = ParseOptions::strict().zones(Zones::Scoped);
+        let cases = Vec::from([
+            ("[::1]:80", Some(("::1", 80))),
+            ("[2001:db8::1]:443", Some(("2001:db8::1", 443))),
+            ("[::]:0", Some(("::", 0))),
+            ("[::1]:65535", Some(("::1", 65535))),
+            ("[::1]:00080", Some(("::1", 80))),
+            ("[fe80::1%eth0]:22", Some(("fe80::1%eth0", 22))),
+            ("[fe80::1%2]:22", Some(("fe80::1%2", 22))),
+            ("[2001:db8::1%eth0]:22", None),
+            ("[::1]:65536", None),
+            ("[::1]:", None),
+            ("[::1]", None),
+            ("[::1]80", None),
+            ("[::1]:+80", None),
+            ("::1:80", None),
+            ("[::1:80", None),
+            ("[1.2.3.4]:80", None),
+        ]);
+
+        for (s, want) in cases {
+            let want = want.map(|(a, port)| SocketAddr::new(addr(a), port));
+            assert_eq!(zoned.parse_socket(s).ok(), want, "parse_socket of {:?}", s);
+            if let Some(sa) = want {
+                assert_eq!(
+                    zoned.parse_socket(&sa.to_string()),
+                    Ok(sa),
+                    "round trip of {}",
+                    sa
+                );
+            }
+
+            // std agrees wherever the zone is absent or numeric.
+            if !s.contains("eth0") {
+                let std = s.parse::<SocketAddrV6>().ok().map(SocketAddr::from);
+                assert_eq!(zoned.parse_socket(s).ok(), std, "std parse of {:?}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_socket_errors() {
+        let cases = Vec::from([
+            ("::1:80", InvalidSocketAddrErr::MissingBracket { offset: 0 }),
+            (
+                "[::1:80",
+                InvalidSocketAddrErr::MissingBracket { offset: 7 },
+            ),
+            ("[::1]", InvalidSocketAddrErr::MissingPort { offset: 5 }),
+            ("[::1]80", InvalidSocketAddrErr::MissingPort { offset: 5 }),
+            ("[::1]:", InvalidSocketAddrErr::InvalidPort { offset: 6 }),
+            ("[::1]:8x", InvalidSocketAddrErr::InvalidPort { offset: 7 }),
+            (
+                "[::1]:65536",
+                InvalidSocketAddrErr::InvalidPort { offset: 6 },
+            ),
+            (
+                "[::1%eth0]:80",
+                InvalidSocketAddrErr::Addr(InvalidAddrErr::ZoneNotAllowed {
+                    offset: 4,
+                    group: 8,
+                }),
+            ),
+            (
+                "[::g]:80",
+                InvalidSocketAddrErr::Addr(InvalidAddrErr::InvalidChar {
+                    offset: 3,
+                    group: 0,
+                }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_socket(s), Err(want), "parse_socket of {:?}", s);
+        }
+
+        assert_eq!(
+            parse_socket("[::1]:x").unwrap_err().to_string(),
+            "invalid ipv6 socket address string: invalid port at byte 6"
+        );
+    }
+
+    #[test]
+    fn test_socket_std() {
+        let sa = SocketAddr::new(addr("fe80::1%7"), 8080);
+        let std = sa.to_std().unwrap();
+        assert_eq!(std.scope_id(), 7);
+        assert_eq!(std.to_string(), sa.to_string());
+        assert_eq!(SocketAddr::from(std), sa);
+
+        let sa = SocketAddr::new(addr("2001:db8::1"), 443);
+        assert_eq!(sa.to_std().unwrap().scope_id(), 0);
+        assert_eq!(SocketAddr::from(sa.to_std().unwrap()), sa);
+
+        let name = Zone::Name(ZoneName::new("eth0").unwrap());
+        let sa = SocketAddr::new(Addr::LOCALHOST.with_zone(name), 22);
+        assert_eq!(sa.to_std(), None);
+        assert_eq!(sa.to_string(), "[::1%eth0]:22");
+    }
+}
@@ -0,0 +1,480 @@
+use super::{Addr, Prefix};
+
+// Classification is the kind of special-purpose block an address belongs
+// to. Most variants correspond to an entry of the IANA IPv6 Special-Purpose
+// Address Registry (see SpecialPurpose); multicast addresses are classified
+// by scope instead, and everything else is Global.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Classification {
+    // the loopback address, ::1/128 (RFC 4291).
+    Loopback,
+    // the unspecified address, ::/128 (RFC 4291).
+    Unspecified,
+    // IPv4-mapped addresses, ::ffff:0:0/96 (RFC 4291).
+    Ipv4Mapped,
+    // the NAT64 well-known prefix, 64:ff9b::/96 (RFC 6052).
+    Nat64WellKnown,
+    // the NAT64 local-use prefix, 64:ff9b:1::/48 (RFC 8215).
+    Nat64LocalUse,
+    // the discard-only block, 100::/64 (RFC 6666).
+    DiscardOnly,
+    // IETF protocol assignments, 2001::/23 (RFC 2928), other than the more
+    // specific assignments below.
+    ProtocolAssignments,
+    // Teredo, 2001::/32 (RFC 4380).
+    Teredo,
+    // Port Control Protocol anycast, 2001:1::1/128 (RFC 7723).
+    PcpAnycast,
+    // TURN anycast, 2001:1::2/128 (RFC 8155).
+    TurnAnycast,
+    // DNS-SD service registration protocol anycast, 2001:1::3/128
+    // (RFC 9665).
+    DnsSdSrpAnycast,
+    // benchmarking, 2001:2::/48 (RFC 5180).
+    Benchmarking,
+    // automatic multicast tunneling, 2001:3::/32 (RFC 7450).
+    Amt,
+    // AS112 DNS service, 2001:4:112::/48 (RFC 7535) and 2620:4f:8000::/48
+    // (RFC 7534).
+    As112,
+    // the deprecated ORCHID block, 2001:10::/28 (RFC 4843).
+    Orchid,
+    // ORCHIDv2, 2001:20::/28 (RFC 7343).
+    OrchidV2,
+    // drone remote ID entity tags, 2001:30::/28 (RFC 9374).
+    DroneRemoteId,
+    // documentation, 2001:db8::/32 (RFC 3849) and 3fff::/20 (RFC 9637).
+    Documentation,
+    // 6to4, 2002::/16 (RFC 3056).
+    SixToFour,
+    // segment routing (SRv6) SIDs, 5f00::/16 (RFC 9602).
+    Srv6Sids,
+    // unique local addresses, fc00::/7 (RFC 4193).
+    UniqueLocal,
+    // link-local unicast, fe80::/10 (RFC 4291).
+    LinkLocal,
+    // multicast, ff00::/8 (RFC 4291), with its scope.
+    Multicast(MulticastScope),
+    // an address with no special purpose.
+    Global,
+}
+
+// MulticastScope is the scope of a multicast address, the fourth nibble of
+// the address (RFC 4291 section 2.7, RFC 7346).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MulticastScope {
+    // interface-local scope, ff01::/16. Loopback only.
+    InterfaceLocal,
+    // link-local scope, ff02::/16.
+    LinkLocal,
+    // realm-local scope, ff03::/16 (RFC 7346).
+    RealmLocal,
+    // admin-local scope, ff04::/16.
+    AdminLocal,
+    // site-local scope, ff05::/16.
+    SiteLocal,
+    // organization-local scope, ff08::/16.
+    OrganizationLocal,
+    // global scope, ff0e::/16.
+    Global,
+    // the reserved scopes 0 and 0xf.
+    Reserved(u8),
+    // any other scope, which is unassigned.
+    Unassigned(u8),
+}
+
+impl MulticastScope {
+    // is_global returns true if multicast traffic in this scope may be
+    // routed on the public internet.
+    pub const fn is_global(&self) -> bool {
+        matches!(self, MulticastScope::Global)
+    }
+}
+
+// SpecialPurpose is an entry of the IANA IPv6 Special-Purpose Address
+// Registry (RFC 6890). The flags are None where the registry says "N/A" or
+// leaves them blank, as it does for deprecated entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecialPurpose {
+    // prefix is the address block of the entry.
+    pub prefix: Prefix,
+    // name is the name of the entry in the registry.
+    pub name: &'static str,
+    // rfc is the reference of the entry, e.g. "[RFC4193][RFC8190]".
+    pub rfc: &'static str,
+    // allocated is the allocation date, e.g. "2005-10".
+    pub allocated: &'static str,
+    // terminated is the termination date of deprecated entries.
+    pub terminated: Option<&'static str>,
+    // source is true if an address from the block is valid as a source.
+    pub source: Option<bool>,
+    // destination is true if an address from the block is valid as a
+    // destination.
+    pub destination: Option<bool>,
+    // forwardable is true if routers may forward packets with an address
+    // from the block.
+    pub forwardable: Option<bool>,
+    // globally_reachable is true if an address from the block is reachable
+    // from the public internet.
+    pub globally_reachable: Option<bool>,
+    // reserved_by_protocol is true if the block is reserved by the IP
+    // protocol itself rather than by a later assignment.
+    pub reserved_by_protocol: Option<bool>,
+    // classification is the Classification of addresses in the block.
+    pub classification: Classification,
+}
+
+impl SpecialPurpose {
+    // entry builds a registry entry. It is only used by the generated
+    // table, and fails the build if a block in the registry is invalid.
+    #[allow(clippy::too_many_arguments)]
+    const fn entry(
+        octets: [u8; 16],
+        len: u8,
+        name: &'static str,
+        rfc: &'static str,
+        allocated: &'static str,
+        terminated: Option<&'static str>,
+        source: Option<bool>,
+        destination: Option<bool>,
+        forwardable: Option<bool>,
+        globally_reachable: Option<bool>,
+        reserved_by_protocol: Option<bool>,
+        classification: Classification,
+    ) -> SpecialPurpose {
+        let prefix = match Prefix::new(Addr::from_octets(octets), len) {
+            Some(prefix) => prefix,
+            None => panic!("invalid address block in the special-purpose registry"),
+        };
+        SpecialPurpose {
+            prefix,
+            name,
+            rfc,
+            allocated,
+            terminated,
+            source,
+            destination,
+            forwardable,
+            globally_reachable,
+            reserved_by_protocol,
+            classification,
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/ipv6_special.rs"));
+
+// special_purpose_registry returns every entry of the vendored IANA IPv6
+// Special-Purpose Address Registry, in registry order.
+pub fn special_purpose_registry() -> &'static [SpecialPurpose] {
+    REGISTRY
+}
+
+impl Addr {
+    // special_purpose returns the most specific entry of the IANA IPv6
+    // Special-Purpose Address Registry that contains the address, if any.
+    pub fn special_purpose(&self) -> Option<&'static SpecialPurpose> {
+        REGISTRY
+            .iter()
+            .filter(|entry| entry.prefix.contains(*self))
+            .max_by_key(|entry| entry.prefix.prefix_len())
+    }
+
+    // classification returns what kind of address this is. See
+    // Classification.
+    pub fn classification(&self) -> Classification {
+        if let Some(entry) = self.special_purpose() {
+            return entry.classification;
+        }
+        match self.multicast_scope() {
+            Some(scope) => Classification::Multicast(scope),
+            None => Classification::Global,
+        }
+    }
+
+    // multicast_scope returns the scope of a multicast address, or None if
+    // the address is not multicast.
+    pub const fn multicast_scope(&self) -> Option<MulticastScope> {
+        if !self.is_multicast() {
+            return None;
+        }
+        let scope = match self.octets[1] & 0x0f {
+            0x1 => MulticastScope::InterfaceLocal,
+            0x2 => MulticastScope::LinkLocal,
+            0x3 => MulticastScope::RealmLocal,
+            0x4 => MulticastScope::AdminLocal,
+            0x5 => MulticastScope::SiteLocal,
+            0x8 => MulticastScope::OrganizationLocal,
+            0xe => MulticastScope::Global,
+            scope @ (0x0 | 0xf) => MulticastScope::Reserved(scope),
+            scope => MulticastScope::Unassigned(scope),
+        };
+        Some(scope)
+    }
+
+    // is_global returns true if the address is globally reachable: the most
+    // specific special-purpose entry containing it says so, or there is no
+    // such entry and it is not a multicast address of a non-global scope.
+    // Entries whose reachability the registry leaves open, like Teredo and
+    // 6to4, are treated as not reachable.
+    pub fn is_global(&self) -> bool {
+        match self.special_purpose() {
+            Some(entry) => entry.globally_reachable == Some(true),
+            None => self.multicast_scope().is_none_or(|scope| scope.is_global()),
+        }
+    }
+
+    // is_unspecified returns true for ::.
+    pub const fn is_unspecified(&self) -> bool {
+        self.to_bits() == 0
+    }
+
+    // is_loopback returns true for ::1.
+    pub const fn is_loopback(&self) -> bool {
+        self.to_bits() == 1
+    }
+
+    // is_ipv4_mapped returns true for addresses in ::ffff:0:0/96.
+    pub const fn is_ipv4_mapped(&self) -> bool {
+        self.to_bits() >> 32 == 0xffff
+    }
+
+    // is_unique_local returns true for addresses in fc00::/7.
+    pub const fn is_unique_local(&self) -> bool {
+        self.octets[0] & 0xfe == 0xfc
+    }
+
+    // is_link_local returns true for link-local unicast addresses,
+    // fe80::/10.
+    pub const fn is_link_local(&self) -> bool {
+        self.octets[0] == 0xfe && self.octets[1] & 0xc0 == 0x80
+    }
+
+    // is_documentation returns true for 2001:db8::/32 and 3fff::/20.
+    pub fn is_documentation(&self) -> bool {
+        self.classification() == Classification::Documentation
+    }
+
+    // is_benchmarking returns true for addresses in 2001:2::/48.
+    pub fn is_benchmarking(&self) -> bool {
+        self.classification() == Classification::Benchmarking
+    }
+
+    // is_teredo returns true for addresses in 2001::/32.
+    pub fn is_teredo(&self) -> bool {
+        self.classification() == Classification::Teredo
+    }
+
+    // is_6to4 returns true for addresses in 2002::/16.
+    pub const fn is_6to4(&self) -> bool {
+        self.octets[0] == 0x20 && self.octets[1] == 0x02
+    }
+
+    // is_nat64 returns true for addresses in the NAT64 well-known prefix,
+    // 64:ff9b::/96.
+    pub fn is_nat64(&self) -> bool {
+        self.classification() == Classification::Nat64WellKnown
+    }
+
+    // is_orchid returns true for addresses in the ORCHID blocks, both the
+    // deprecated 2001:10::/28 and ORCHIDv2, 2001:20::/28.
+    pub fn is_orchid(&self) -> bool {
+        matches!(
+            self.classification(),
+            Classification::Orchid | Classification::OrchidV2
+        )
+    }
+
+    // is_multicast returns true for addresses in ff00::/8.
+    pub const fn is_multicast(&self) -> bool {
+        self.octets[0] == 0xff
+    }
+}
+
+#[cfg(test)]
+mod special_tests {
+    use super::{Classification, MulticastScope, special_purpose_registry};
+    use crate::ipv6::ParseOptions;
+    use crate::testing::ipv6::addr;
+
+    #[test]
+    fn test_registry() {
+        let registry = special_purpose_registry();
+        assert_eq!(registry.len(), 24);
+
+        let documentation: Vec<String> = registry
+            .iter()
+            .filter(|entry| entry.classification == Classification::Documentation)
+            .map(|entry| entry.prefix.to_string())
+            .collect();
+        assert_eq!(documentation, ["2001:db8::/32", "3fff::/20"]);
+
+        let teredo = addr("2001::1").special_purpose().unwrap();
+        assert_eq!(teredo.name, "TEREDO");
+        assert_eq!(teredo.rfc, "[RFC4380][RFC8190]");
+        assert_eq!(teredo.allocated, "2006-01");
+        assert_eq!(teredo.globally_reachable, None);
+        assert_eq!(teredo.source, Some(true));
+
+        let orchid = addr("2001:10::1").special_purpose().unwrap();
+        assert_eq!(orchid.terminated, Some("2014-03"));
+        assert_eq!(orchid.forwardable, None);
+
+        // footnote markers are dropped from blocks and flags.
+        let six_to_four = addr("2002::1").special_purpose().unwrap();
+        assert_eq!(six_to_four.prefix.to_string(), "2002::/16");
+        let assignments = addr("2001:1ff::").special_purpose().unwrap();
+        assert_eq!(assignments.name, "IETF Protocol Assignments");
+        assert_eq!(assignments.globally_reachable, Some(false));
+    }
+
+    #[test]
+    fn test_classification() {
+        let cases = Vec::from([
+            ("::", Classification::Unspecified),
+            ("::1", Classification::Loopback),
+            ("::2", Classification::Global),
+            ("::ffff:192.0.2.1", Classification::Ipv4Mapped),
+            ("64:ff9b::192.0.2.1", Classification::Nat64WellKnown),
+            ("64:ff9b:1::1", Classification::Nat64LocalUse),
+            ("100::1", Classification::DiscardOnly),
+            ("2001:4::", Classification::ProtocolAssignments),
+            ("2001:0:4136:e378::1", Classification::Teredo),
+            ("2001:1::1", Classification::PcpAnycast),
+            ("2001:1::2", Classification::TurnAnycast),
+            ("2001:1::3", Classification::DnsSdSrpAnycast),
+            ("2001:1::4", Classification::ProtocolAssignments),
+            ("2001:2::1", Classification::Benchmarking),
+            ("2001:3::1", Classification::Amt),
+            ("2001:4:112::1", Classification::As112),
+            ("2620:4f:8000::1", Classification::As112),
+            ("2001:10::1", Classification::Orchid),
+            ("2001:20::1", Classification::OrchidV2),
+            ("2001:30::1", Classification::DroneRemoteId),
+            ("2001:db8::1", Classification::Documentation),
+            ("3fff:fff::1", Classification::Documentation),
+            ("3fff:1000::1", Classification::Global),
+            ("2002:c000:201::1", Classification::SixToFour),
+            ("5f00::1", Classification::Srv6Sids),
+            ("fc00::1", Classification::UniqueLocal),
+            ("fd12:3456:789a::1", Classification::UniqueLocal),
+            ("fe80::1", Classification::LinkLocal),
+            ("febf::1", Classification::LinkLocal),
+            ("fec0::1", Classification::Global),
+            ("2606:4700::1111", Classification::Global),
+            (
+                "ff01::1",
+                Classification::Multicast(MulticastScope::InterfaceLocal),
+            ),
+            (
+                "ff02::1",
+                Classification::Multicast(MulticastScope::LinkLocal),
+            ),
+            (
+                "ff03::1",
+                Classification::Multicast(MulticastScope::RealmLocal),
+            ),
+            (
+                "ff04::1",
+                Classification::Multicast(MulticastScope::AdminLocal),
+            ),
+            (
+                "ff05::1:3",
+                Classification::Multicast(MulticastScope::SiteLocal),
+            ),
+            (
+                "ff08::1",
+                Classification::Multicast(MulticastScope::OrganizationLocal),
+            ),
+            ("ff0e::1", Classification::Multicast(MulticastScope::Global)),
+            ("ff1e::1", Classification::Multicast(MulticastScope::Global)),
+            (
+                "ff00::1",
+                Classification::Multicast(MulticastScope::Reserved(0)),
+            ),
+            (
+                "ff0f::1",
+                Classification::Multicast(MulticastScope::Reserved(0xf)),
+            ),
+            (
+                "ff06::1",
+                Classification::Multicast(MulticastScope::Unassigned(6)),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(addr(s).classification(), want, "classification of {}", s);
+        }
+
+        // the zone does not change the classification.
+        let zoned = ParseOptions::permissive().parse("fe80::1%eth0").unwrap();
+        assert_eq!(zoned.classification(), Classification::LinkLocal);
+    }
+
+    #[test]
+    fn test_is_global() {
+        let cases = Vec::from([
+            ("2606:4700::1111", true),
+            ("2001:4860:4860::8888", true),
+            ("64:ff9b::192.0.2.1", true),
+            ("2001:1::1", true),
+            ("2001:3::1", true),
+            ("2001:20::1", true),
+            ("ff0e::1", true),
+            ("::", false),
+            ("::1", false),
+            ("::ffff:8.8.8.8", false),
+            ("64:ff9b:1::1", false),
+            ("100::1", false),
+            ("2001:4::", false),
+            ("2001::1", false),
+            ("2001:2::1", false),
+            ("2001:10::1", false),
+            ("2001:db8::1", false),
+            ("3fff::1", false),
+            ("2002::1", false),
+            ("5f00::1", false),
+            ("fd00::1", false),
+            ("fe80::1", false),
+            ("ff02::1", false),
+            ("ff05::1", false),
+            ("ff08::1", false),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(addr(s).is_global(), want, "is_global of {}", s);
+        }
+    }
+
+    #[test]
+    fn test_predicates() {
+        assert!(addr("::").is_unspecified());
+        assert!(!addr("::1").is_unspecified());
+        assert!(addr("::1").is_loopback());
+        assert!(!addr("::2").is_loopback());
+        assert!(addr("::ffff:10.0.0.1").is_ipv4_mapped());
+        assert!(!addr("::fffe:10.0.0.1").is_ipv4_mapped());
+        assert!(!addr("1::ffff:10.0.0.1").is_ipv4_mapped());
+        assert!(addr("fc00::").is_unique_local());
+        assert!(addr("fdff:ffff::").is_unique_local());
+        assert!(!addr("fe00::").is_unique_local());
+        assert!(addr("fe80::1").is_link_local());
+        assert!(addr("febf:ffff::1").is_link_local());
+        assert!(!addr("fec0::1").is_link_local());
+        assert!(addr("2001:db8::1").is_documentation());
+        assert!(addr("3fff::1").is_documentation());
+        assert!(addr("2001:2::1").is_benchmarking());
+        assert!(addr("2001:0:4136:e378:8000:63bf:3fff:fdd2").is_teredo());
+        assert!(!addr("2001:1::1").is_teredo());
+        assert!(addr("2002:c000:201::").is_6to4());
+        assert!(!addr("2003::").is_6to4());
+        assert!(addr("64:ff9b::808:808").is_nat64());
+        assert!(!addr("64:ff9b:1::808:808").is_nat64());
+        assert!(addr("2001:10::1").is_orchid());
+        assert!(addr("2001:2f::1").is_orchid());
+        assert!(!addr("2001:30::1").is_orchid());
+        assert!(addr("ff02::1").is_multicast());
+        assert!(!addr("fe80::1").is_multicast());
+        assert_eq!(addr("fe80::1").multicast_scope(), None);
+    }
+}
@@ -0,0 +1,623 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Addr, InvalidAddrErr, ParseOptions, scan_groups};
+
+// Zone is the zone of a scoped address (RFC 4007 section 11), the "eth0" in
+// "fe80::1%eth0". It names the link or site the address belongs to, either
+// by interface name or by numeric interface index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Zone {
+    // Index is a numeric interface index, as in "fe80::1%2". It is the same
+    // number as the scope_id of a std::net::SocketAddrV6.
+    Index(u32),
+    // Name is an interface name, as in "fe80::1%eth0".
+    Name(ZoneName),
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Zone::Index(index) => write!(f, "{}", index),
+            Zone::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FromStr for Zone {
+    type Err = InvalidAddrErr;
+
+    // from_str reads a zone without its '%'. A zone of only digits is an
+    // interface index; anything else is an interface name.
+    fn from_str(s: &str) -> Result<Zone, InvalidAddrErr> {
+        parse_zone(s.as_bytes(), 0)
+    }
+}
+
+// ZoneName is an interface name used as a zone. It is stored inline, so that
+// an Addr with a zone is still Copy, and is limited to ZoneName::MAX_LEN
+// bytes of the RFC 3986 unreserved characters: letters, digits, '-', '.',
+// '_' and '~'. Those never need percent-encoding in a URI.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZoneName {
+    // bytes is zero-padded past len, so comparing it compares the names.
+    bytes: [u8; ZoneName::MAX_LEN],
+    len: u8,
+}
+
+impl ZoneName {
+    // MAX_LEN is the longest name a zone can have. It is IFNAMSIZ on Linux
+    // less the terminating NUL.
+    pub const MAX_LEN: usize = 15;
+
+    // new returns name as a ZoneName. It returns None if name is empty,
+    // longer than MAX_LEN, has characters outside the unreserved set, or is
+    // all digits, which would read back as a Zone::Index.
+    pub fn new(name: &str) -> Option<ZoneName> {
+        ZoneName::from_ascii(name.as_bytes())
+    }
+
+    // from_ascii is new for a name that has not been checked to be UTF-8.
+    const fn from_ascii(src: &[u8]) -> Option<ZoneName> {
+        if src.is_empty() || src.len() > ZoneName::MAX_LEN {
+            return None;
+        }
+
+        let mut bytes = [0; ZoneName::MAX_LEN];
+        let mut digits = true;
+        let mut i = 0;
+        while i < src.len() {
+            if !is_unreserved(src[i]) {
+                return None;
+            }
+            digits &= src[i].is_ascii_digit();
+            bytes[i] = src[i];
+            i += 1;
+        }
+        if digits {
+            return None;
+        }
+        Some(ZoneName {
+            bytes,
+            len: src.len() as u8,
+        })
+    }
+
+    // as_str returns the name.
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len as usize]).expect("zone names are ascii")
+    }
+}
+
+impl fmt::Debug for ZoneName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ZoneName").field(&self.as_str()).finish()
+    }
+}
+
+impl fmt::Display for ZoneName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Zones decides which addresses may have a zone when parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zones {
+    // Reject fails the parse with InvalidAddrErr::ZoneNotAllowed whenever
+    // there is a zone. This is what std::net::Ipv6Addr does.
+    Reject,
+    // Scoped allows a zone only on addresses with a scope smaller than
+    // global, where RFC 4007 needs one to say which link or site is meant:
+    // the loopback address, link-local and site-local unicast, and multicast
+    // below global scope.
+    Scoped,
+    // Any allows a zone on any address, like getaddrinfo(3) does.
+    Any,
+}
+
+impl Zones {
+    // allows reports whether addr may have a zone.
+    const fn allows(self, addr: &Addr) -> bool {
+        match self {
+            Zones::Reject => false,
+            Zones::Scoped => is_scoped(addr),
+            Zones::Any => true,
+        }
+    }
+}
+
+// is_scoped reports whether addr has a scope smaller than global.
+const fn is_scoped(addr: &Addr) -> bool {
+    match addr.octets() {
+        // ::1 is link-local in scope (RFC 4007 section 4).
+        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1] => true,
+        // fe80::/10 link-local and fec0::/10 site-local unicast.
+        [0xfe, b, ..] => b & 0x80 != 0,
+        // multicast scopes 1 (interface-local) up to but not including 0xe
+        // (global). Scope 0 is reserved.
+        [0xff, b, ..] => matches!(b & 0x0f, 1..0xe),
+        _ => false,
+    }
+}
+
+// is_unreserved reports whether b is one of the RFC 3986 unreserved
+// characters.
+pub(super) const fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')
+}
+
+// UriHost renders an Addr as an RFC 6874 URI host: in square brackets, with
+// the '%' before the zone percent-encoded, e.g. "[fe80::1%25eth0]". See
+// Addr::uri_host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UriHost(Addr);
+
+impl fmt::Display for UriHost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        self.0.write_addr(f)?;
+        if let Some(zone) = self.0.zone {
+            write!(f, "%25{}", zone)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl Addr {
+    // uri_host returns the address formatted as a URI host, for building
+    // URLs like "http://[fe80::1%25eth0]:8080/".
+    pub fn uri_host(&self) -> UriHost {
+        UriHost(*self)
+    }
+}
+
+// parse_uri_host will parse an RFC 6874 URI host, an address in square
+// brackets with any zone introduced by "%25", into an Addr:
+//
+//   "[2001:db8::1]"
+//   "[fe80::1%25eth0]"
+//
+// It uses the default ParseOptions, which do not allow zones; see
+// ParseOptions::parse_uri_host to pick a different profile.
+pub fn parse_uri_host(s: &str) -> Result<Addr, InvalidAddrErr> {
+    ParseOptions::default().parse_uri_host(s)
+}
+
+// scan_uri_host is the scanner behind parse_uri_host.
+pub(super) fn scan_uri_host(s: &str, options: &ParseOptions) -> Result<Addr, InvalidAddrErr> {
+    if !s.starts_with('[') {
+        return Err(InvalidAddrErr::MissingBracket {
+            offset: 0,
+            group: 0,
+        });
+    }
+    if s.len() < 2 || !s.ends_with(']') {
+        return Err(InvalidAddrErr::MissingBracket {
+            offset: s.len(),
+            group: 8,
+        });
+    }
+    let inner = &s.as_bytes()[1..s.len() - 1];
+
+    let Some(at) = inner.iter().position(|&b| b == b'%') else {
+        return scan_groups(inner, false)
+            .map(|(groups, _)| Addr::from_segments(groups))
+            .map_err(|err| err.shifted(1));
+    };
+    let (groups, _) = scan_groups(&inner[..at], false).map_err(|err| err.shifted(1))?;
+    let addr = Addr::from_segments(groups);
+    if !inner[at..].starts_with(b"%25") {
+        return Err(InvalidAddrErr::UnescapedZone {
+            offset: at + 1,
+            group: 8,
+        });
+    }
+    scan_zone(addr, &inner[at + 3..], at + 1, at + 4, options)
+}
+
+// scan_zone checks that addr may have a zone under options and reads the
+// zone s. delim is the byte offset in the input of the '%' that introduced
+// the zone and start is the offset of s.
+pub(super) const fn scan_zone(
+    addr: Addr,
+    s: &[u8],
+    delim: usize,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Addr, InvalidAddrErr> {
+    if !options.get_zones().allows(&addr) {
+        return Err(InvalidAddrErr::ZoneNotAllowed {
+            offset: delim,
+            group: 8,
+        });
+    }
+    match parse_zone(s, start) {
+        Ok(zone) => Ok(addr.with_zone(zone)),
+        Err(err) => Err(err),
+    }
+}
+
+// parse_zone reads a zone that starts at byte start of the input.
+const fn parse_zone(bytes: &[u8], start: usize) -> Result<Zone, InvalidAddrErr> {
+    if bytes.is_empty() {
+        return Err(InvalidAddrErr::EmptyZone {
+            offset: start,
+            group: 8,
+        });
+    }
+
+    // index is the value of the zone as an interface index for as long as
+    // it is all digits, and None once it overflows.
+    let mut index = Some(0u32);
+    let mut digits = true;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if !is_unreserved(b) {
+            return Err(InvalidAddrErr::InvalidZone {
+                offset: start + i,
+                group: 8,
+            });
+        }
+        digits &= b.is_ascii_digit();
+        if let (true, Some(n)) = (digits, index) {
+            index = match n.checked_mul(10) {
+                Some(n) => n.checked_add((b - b'0') as u32),
+                None => None,
+            };
+        }
+        i += 1;
+    }
+
+    if digits {
+        return match index {
+            Some(index) => Ok(Zone::Index(index)),
+            None => Err(InvalidAddrErr::InvalidZone {
+                offset: start,
+                group: 8,
+            }),
+        };
+    }
+    match ZoneName::from_ascii(bytes) {
+        Some(name) => Ok(Zone::Name(name)),
+        None => Err(InvalidAddrErr::ZoneTooLong {
+            offset: start,
+            group: 8,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod zone_tests {
+    use super::{Zone, ZoneName, Zones, parse_uri_host};
+    use crate::ipv6::{Addr, InvalidAddrErr, ParseOptions, parse};
+
+    fn name(s: &str) -> Zone {
+        Zone::Name(ZoneName::new(s).unwrap())
+    }
+
+    #[test]
+    fn test_parse_zone() {
+        let opts = ParseOptions::permissive();
+        let cases = Vec::from([
+            (
+                "fe80::1%eth0",
+                Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                name("eth0"),
+            ),
+            (
+                "fe80::1%2",
+                Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                Zone::Index(2),
+            ),
+            (
+                "fe80::1%02",
+                Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                Zone::Index(2),
+            ),
+            (
+                "ff02::1%en0",
+                Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1),
+                name("en0"),
+            ),
+            (
+                "::ffff:10.0.0.1%lo",
+                Addr::new(0, 0, 0, 0, 0, 0xffff, 0x0a00, 1),
+                name("lo"),
+            ),
+            (
+                "fe80::1%br-lan.10_a~",
+                Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                name("br-lan.10_a~"),
+            ),
+            (
+                "fe80::1%4294967295",
+                Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                Zone::Index(u32::MAX),
+            ),
+        ]);
+
+        for (s, want, zone) in cases {
+            let got = opts.parse(s).unwrap();
+            assert_eq!(got, want.with_zone(zone), "parse of {:?}", s);
+            assert_eq!(got.zone(), Some(zone), "zone of {:?}", s);
+            assert_eq!(got.without_zone(), want, "without_zone of {:?}", s);
+            assert_eq!(
+                opts.parse(&got.to_string()),
+                Ok(got),
+                "round trip of {}",
+                got
+            );
+        }
+
+        // the zone is part of the address's identity.
+        let a = opts.parse("fe80::1%eth0").unwrap();
+        let b = opts.parse("fe80::1%eth1").unwrap();
+        assert_ne!(a, b);
+        assert!(a.without_zone() < a && a < b);
+        assert_eq!(a.to_bits(), b.to_bits());
+    }
+
+    #[test]
+    fn test_zone_policy() {
+        let cases = Vec::from([
+            // (input, Reject, Scoped, Any)
+            ("fe80::1%eth0", false, true, true),
+            ("febf::1%eth0", false, true, true),
+            ("fec0::1%eth0", false, true, true),
+            ("::1%lo", false, true, true),
+            ("ff01::1%1", false, true, true),
+            ("ff02::1%1", false, true, true),
+            ("ff05::1%1", false, true, true),
+            ("ff08::1%1", false, true, true),
+            ("ff0e::1%1", false, false, true),
+            ("ff00::1%1", false, false, true),
+            ("fe7f::1%eth0", false, false, true),
+            ("2001:db8::1%eth0", false, false, true),
+            ("::%eth0", false, false, true),
+        ]);
+
+        for (s, reject, scoped, any) in cases {
+            for (zones, want) in [
+                (Zones::Reject, reject),
+                (Zones::Scoped, scoped),
+                (Zones::Any, any),
+            ] {
+                let got = ParseOptions::strict().zones(zones).parse(s);
+                assert_eq!(
+                    got.is_ok(),
+                    want,
+                    "parse of {:?} with {:?}: {:?}",
+                    s,
+                    zones,
+                    got
+                );
+                if !want {
+                    let at = s.find('%').unwrap();
+                    assert_eq!(
+                        got,
+                        Err(InvalidAddrErr::ZoneNotAllowed {
+                            offset: at,
+                            group: 8
+                        })
+                    );
+                }
+            }
+        }
+
+        assert_eq!(ParseOptions::default().get_zones(), Zones::Reject);
+        assert_eq!(ParseOptions::strict().get_zones(), Zones::Reject);
+        assert_eq!(ParseOptions::strict(), ParseOptions::std_compat());
+        assert_eq!(ParseOptions::permissive().get_zones(), Zones::Any);
+    }
+
+    #[test]
+    fn test_zone_errors() {
+        let opts = ParseOptions::permissive();
+        let cases = Vec::from([
+            (
+                "fe80::1%",
+                InvalidAddrErr::EmptyZone {
+                    offset: 8,
+                    group: 8,
+                },
+            ),
+            (
+                "fe80::1%eth 0",
+                InvalidAddrErr::InvalidZone {
+                    offset: 11,
+                    group: 8,
+                },
+            ),
+            (
+                "fe80::1%eth0%1",
+                InvalidAddrErr::InvalidZone {
+                    offset: 12,
+                    group: 8,
+                },
+            ),
+            (
+                "fe80::1%é",
+                InvalidAddrErr::InvalidZone {
+                    offset: 8,
+                    group: 8,
+                },
+            ),
+            (
+                "fe80::1%4294967296",
+                InvalidAddrErr::InvalidZone {
+                    offset: 8,
+                    group: 8,
+                },
+            ),
+            (
+                "fe80::1%a-very-long-name",
+                InvalidAddrErr::ZoneTooLong {
+                    offset: 8,
+                    group: 8,
+                },
+            ),
+            (
+                "fe80::g%eth0",
+                InvalidAddrErr::InvalidChar {
+                    offset: 6,
+                    group: 1,
+                },
+            ),
+            (
+                "::1.2.3.256%eth0",
+                InvalidAddrErr::Ipv4(crate::ipv4::InvalidAddrErr::OctetOutOfRange {
+                    offset: 8,
+                    octet: 3,
+                }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(opts.parse(s), Err(want), "parse of {:?}", s);
+        }
+
+        // the default options reject the zone before looking at it.
+        assert_eq!(
+            parse("fe80::1%"),
+            Err(InvalidAddrErr::ZoneNotAllowed {
+                offset: 7,
+                group: 8
+            })
+        );
+
+        assert_eq!("eth0".parse(), Ok(name("eth0")));
+        assert_eq!("7".parse(), Ok(Zone::Index(7)));
+        assert_eq!(
+            "".parse::<Zone>(),
+            Err(InvalidAddrErr::EmptyZone {
+                offset: 0,
+                group: 8
+            })
+        );
+        assert_eq!(ZoneName::new("123"), None);
+        assert_eq!(ZoneName::new("eth/0"), None);
+        assert_eq!(
+            ZoneName::new(&"x".repeat(ZoneName::MAX_LEN)).map(|n| n.as_str().len()),
+            Some(15)
+        );
+        assert_eq!(ZoneName::new(&"x".repeat(ZoneName::MAX_LEN + 1)), None);
+        assert_eq!(
+            format!("{:?}", ZoneName::new("eth0").unwrap()),
+            "ZoneName(\"eth0\")"
+        );
+    }
+
+    #[test]
+    fn test_uri_host() {
+        let opts = ParseOptions::permissive();
+        let cases = Vec::from([
+            ("[2001:db8::1]", "2001:db8::1"),
+            ("[fe80::1%25eth0]", "fe80::1%eth0"),
+            ("[fe80::1%252]", "fe80::1%2"),
+            ("[::ffff:192.0.2.1]", "::ffff:192.0.2.1"),
+        ]);
+
+        for (uri, plain) in cases {
+            let want = opts.parse(plain).unwrap();
+            assert_eq!(
+                opts.parse_uri_host(uri),
+                Ok(want),
+                "parse_uri_host of {:?}",
+                uri
+            );
+            assert_eq!(want.uri_host().to_string(), uri, "uri_host of {}", plain);
+        }
+
+        let cases = Vec::from([
+            (
+                "::1",
+                InvalidAddrErr::MissingBracket {
+                    offset: 0,
+                    group: 0,
+                },
+            ),
+            (
+                "[::1",
+                InvalidAddrErr::MissingBracket {
+                    offset: 4,
+                    group: 8,
+                },
+            ),
+            (
+                "[",
+                InvalidAddrErr::MissingBracket {
+                    offset: 1,
+                    group: 8,
+                },
+            ),
+            (
+                "[::g]",
+                InvalidAddrErr::InvalidChar {
+                    offset: 3,
+                    group: 0,
+                },
+            ),
+            (
+                "[fe80::1%eth0]",
+                InvalidAddrErr::UnescapedZone {
+                    offset: 8,
+                    group: 8,
+                },
+            ),
+            (
+                "[fe80::1%2]",
+                InvalidAddrErr::UnescapedZone {
+                    offset: 8,
+                    group: 8,
+                },
+            ),
+            (
+                "[fe80::1%25]",
+                InvalidAddrErr::EmptyZone {
+                    offset: 11,
+                    group: 8,
+                },
+            ),
+            (
+                "[fe80::1%25eth%0]",
+                InvalidAddrErr::InvalidZone {
+                    offset: 14,
+                    group: 8,
+                },
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(
+                opts.parse_uri_host(s),
+                Err(want),
+                "parse_uri_host of {:?}",
+                s
+            );
+        }
+
+        assert_eq!(parse_uri_host("[::1]"), Ok(Addr::LOCALHOST));
+        assert_eq!(
+            parse_uri_host("[fe80::1%25eth0]"),
+            Err(InvalidAddrErr::ZoneNotAllowed {
+                offset: 8,
+                group: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_zone_diagnostic() {
+        let input = "fe80::1%a-very-long-name";
+        let err = ParseOptions::permissive().parse(input).unwrap_err();
+        let want = "\
+error: invalid ipv6 address: zone name too long
+ --> input:1:9
+  |
+1 | fe80::1%a-very-long-name
+  |         ^^^^^^^^^^^^^^^^ a zone name is at most 15 bytes";
+        assert_eq!(err.diagnostic(input).to_string(), want);
+    }
+}
@@ -0,0 +1,318 @@
+// literal.rs has the macros for address and prefix literals. Each one parses
+// its string with the const parser of its type inside a const item, so a
+// literal is checked when the crate using it is compiled and costs nothing
+// at run time:
+//
+//   static RESOLVERS: [ipv4::Addr; 2] = [ipv4!("192.0.2.53"), ipv4!("198.51.100.53")];
+//
+// A malformed literal fails the build, and the error names the literal and
+// says what is wrong with it:
+//
+//   error[E0080]: evaluation panicked: invalid ipv4 address literal
+//                 "10.0.0.256": octet out of range at byte 7
+//
+// Rust does not allow a macro call or a block in a pattern, so to match on
+// a literal it has to be given a name first:
+//
+//   const GATEWAY: ipv4::Addr = ipv4!("192.0.2.1");
+//   match addr {
+//       GATEWAY => ...,
+//       _ => ...,
+//   }
+//
+// The literals use the default ParseOptions. Other options, such as a zone
+// on an IPv6 address, need a const that calls parse_const on ParseOptions.
+
+// ipv4! turns a string literal into an ipv4::Addr at compile time.
+#[macro_export]
+macro_rules! ipv4 {
+    ($s:expr) => {{
+        const VALUE: $crate::ipv4::Addr = match $crate::ipv4::parse_const($s) {
+            Ok(addr) => addr,
+            Err(err) => $crate::__invalid_literal("ipv4 address", $s, err.reason(), err.offset()),
+        };
+        VALUE
+    }};
+}
+
+// ipv6! turns a string literal into an ipv6::Addr at compile time.
+#[macro_export]
+macro_rules! ipv6 {
+    ($s:expr) => {{
+        const VALUE: $crate::ipv6::Addr = match $crate::ipv6::parse_const($s) {
+            Ok(addr) => addr,
+            Err(err) => $crate::__invalid_literal("ipv6 address", $s, err.reason(), err.offset()),
+        };
+        VALUE
+    }};
+}
+
+// ip! turns a string literal into an IpAddr at compile time, picking the
+// family the same way as parse_addr.
+#[macro_export]
+macro_rules! ip {
+    ($s:expr) => {{
+        const VALUE: $crate::IpAddr = match $crate::parse_addr_const($s) {
+            Ok(addr) => addr,
+            Err(err) => $crate::__invalid_literal("ip address", $s, err.reason(), err.offset()),
+        };
+        VALUE
+    }};
+}
+
+// prefix! turns a string literal in CIDR notation into an IpNet at compile
+// time, picking the family the same way as parse_net. Host bits are an
+// error, as with parse_net.
+#[macro_export]
+macro_rules! prefix {
+    ($s:expr) => {{
+        const VALUE: $crate::IpNet = match $crate::parse_net_const($s) {
+            Ok(net) => net,
+            Err(err) => $crate::__invalid_literal("prefix", $s, err.reason(), err.offset()),
+        };
+        VALUE
+    }};
+}
+
+// ipv4_prefix! turns a string literal in CIDR notation into an ipv4::Prefix
+// at compile time.
+#[macro_export]
+macro_rules! ipv4_prefix {
+    ($s:expr) => {{
+        const VALUE: $crate::ipv4::Prefix = match $crate::ipv4::parse_prefix_const($s) {
+            Ok(prefix) => prefix,
+            Err(err) => $crate::__invalid_literal("ipv4 prefix", $s, err.reason(), err.offset()),
+        };
+        VALUE
+    }};
+}
+
+// ipv6_prefix! turns a string literal in CIDR notation into an ipv6::Prefix
+// at compile time.
+#[macro_export]
+macro_rules! ipv6_prefix {
+    ($s:expr) => {{
+        const VALUE: $crate::ipv6::Prefix = match $crate::ipv6::parse_prefix_const($s) {
+            Ok(prefix) => prefix,
+            Err(err) => $crate::__invalid_literal("ipv6 prefix", $s, err.reason(), err.offset()),
+        };
+        VALUE
+    }};
+}
+
+// MAX_INPUT is how much of a malformed literal the error message quotes.
+const MAX_INPUT: usize = 128;
+
+// invalid_literal panics with the message for a malformed literal. It is
+// called by the macros, where the panic happens during const evaluation and
+// becomes a compile error. A const panic can only print a single &str, so
+// the message is put together in a buffer first.
+pub const fn invalid_literal(what: &str, input: &str, reason: &str, offset: usize) -> ! {
+    let mut msg = Message {
+        buf: [0; 256],
+        len: 0,
+    };
+    msg.push(b"invalid ");
+    msg.push(what.as_bytes());
+    msg.push(b" literal \"");
+    if input.len() > MAX_INPUT {
+        // cut at a character boundary: continuation bytes are 0b10xxxxxx.
+        let mut end = MAX_INPUT;
+        while input.as_bytes()[end] & 0xc0 == 0x80 {
+            end -= 1;
+        }
+        msg.push(input.as_bytes().split_at(end).0);
+        msg.push(b"...");
+    } else {
+        msg.push(input.as_bytes());
+    }
+    msg.push(b"\": ");
+    msg.push(reason.as_bytes());
+    msg.push(b" at byte ");
+    msg.push_usize(offset);
+
+    match core::str::from_utf8(msg.buf.split_at(msg.len).0) {
+        Ok(s) => panic!("{}", s),
+        Err(_) => panic!("invalid literal"),
+    }
+}
+
+// Message is a fixed size buffer that silently drops what does not fit.
+struct Message {
+    buf: [u8; 256],
+    len: usize,
+}
+
+impl Message {
+    const fn push(&mut self, bytes: &[u8]) {
+        let mut i = 0;
+        while i < bytes.len() && self.len < self.buf.len() {
+            self.buf[self.len] = bytes[i];
+            self.len += 1;
+            i += 1;
+        }
+    }
+
+    const fn push_usize(&mut self, mut n: usize) {
+        let mut digits = [0u8; 20];
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        self.push(digits.split_at(i).1);
+    }
+}
+
+#[cfg(test)]
+mod literal_tests {
+    use super::invalid_literal;
+    use crate::{IpAddr, IpNet, ipv4, ipv6};
+
+    static RESOLVERS: [ipv4::Addr; 2] = [crate::ipv4!("192.0.2.53"), crate::ipv4!("198.51.100.53")];
+    static LINK_LOCAL: ipv6::Prefix = crate::ipv6_prefix!("fe80::/10");
+    const GATEWAY: ipv4::Addr = crate::ipv4!("192.0.2.1");
+    const ROUTER: ipv6::Addr = crate::ipv6!("2001:db8::1");
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(RESOLVERS[0], ipv4::Addr::new(192, 0, 2, 53));
+        assert_eq!(RESOLVERS[1], ipv4::Addr::new(198, 51, 100, 53));
+        assert_eq!(LINK_LOCAL, "fe80::/10".parse().unwrap());
+
+        // every macro gives what the runtime parser gives.
+        assert_eq!(crate::ipv4!("10.0.0.1"), ipv4::parse("10.0.0.1").unwrap());
+        assert_eq!(
+            crate::ipv6!("::ffff:192.0.2.1"),
+            ipv6::parse("::ffff:192.0.2.1").unwrap()
+        );
+        assert_eq!(
+            crate::ip!("192.0.2.1"),
+            "192.0.2.1".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            crate::ip!("2001:db8::1"),
+            "2001:db8::1".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            crate::prefix!("10.0.0.0/8"),
+            "10.0.0.0/8".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(
+            crate::prefix!("2001:db8::/32"),
+            "2001:db8::/32".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(
+            crate::ipv4_prefix!("192.168.0.0/16"),
+            ipv4::parse_prefix("192.168.0.0/16").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_match() {
+        let name = |addr: ipv4::Addr| match addr {
+            GATEWAY => "gateway",
+            _ => "other",
+        };
+        assert_eq!(name(ipv4::Addr::new(192, 0, 2, 1)), "gateway");
+        assert_eq!(name(ipv4::Addr::new(192, 0, 2, 2)), "other");
+
+        let name = |addr: ipv6::Addr| match addr {
+            ROUTER => "router",
+            _ => "other",
+        };
+        assert_eq!(name("2001:db8::1".parse().unwrap()), "router");
+        assert_eq!(name("2001:db8::2".parse().unwrap()), "other");
+    }
+
+    #[test]
+    fn test_const_parsers() {
+        // the const parsers give the same results and errors as the runtime
+        // ones, including where the runtime ones take a fast path.
+        let cases = Vec::from([
+            "10.0.0.1",
+            "255.255.255.255",
+            "10.0.0.256",
+            "010.0.0.1",
+            "1.2.3",
+            "1.2.3.4.5",
+            "",
+        ]);
+        for s in cases {
+            assert_eq!(ipv4::parse_const(s), ipv4::parse(s), "ipv4 {:?}", s);
+        }
+
+        let cases = Vec::from([
+            "::",
+            "2001:db8::1",
+            "1:2:3:4:5:6:7:8",
+            "::ffff:192.0.2.1",
+            "1::2::3",
+            "fe80::1%eth0",
+            "12345::",
+        ]);
+        for s in cases {
+            assert_eq!(ipv6::parse_const(s), ipv6::parse(s), "ipv6 {:?}", s);
+            let options = ipv6::ParseOptions::permissive();
+            assert_eq!(
+                options.parse_const(s),
+                options.parse(s),
+                "permissive ipv6 {:?}",
+                s
+            );
+        }
+
+        let cases = Vec::from([
+            "10.0.0.0/8",
+            "10.0.0.1/8",
+            "10.0.0.0/33",
+            "10.0.0.0",
+            "10.0.0.0/08",
+        ]);
+        for s in cases {
+            assert_eq!(
+                ipv4::parse_prefix_const(s),
+                ipv4::parse_prefix(s),
+                "ipv4 prefix {:?}",
+                s
+            );
+        }
+
+        let cases = Vec::from(["2001:db8::/32", "2001:db8::1/32", "::/129", "::/032", "::"]);
+        for s in cases {
+            assert_eq!(
+                ipv6::parse_prefix_const(s),
+                ipv6::parse_prefix(s),
+                "ipv6 prefix {:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_literal() {
+        let message = |input: &'static str| {
+            let err = std::panic::catch_unwind(|| {
+                invalid_literal("ipv4 address", input, "octet out of range", 7)
+            })
+            .unwrap_err();
+            err.downcast_ref::<String>().cloned().unwrap()
+        };
+
+        assert_eq!(
+            message("10.0.0.256"),
+            "invalid ipv4 address literal \"10.0.0.256\": octet out of range at byte 7"
+        );
+        // long input is cut at a character boundary.
+        let long: &'static str = "\u{e9}".repeat(100).leak();
+        let want = format!(
+            "invalid ipv4 address literal \"{}...\": octet out of range at byte 7",
+            "\u{e9}".repeat(64)
+        );
+        assert_eq!(message(long), want);
+    }
+}
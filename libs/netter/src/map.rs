@@ -0,0 +1,700 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::bits::Bits;
+use crate::{IpAddr, IpNet};
+
+// PrefixMap maps prefixes of both families to values, and looks up which of
+// them hold an address: the longest one, as a router picks a next hop, or
+// all of them.
+//
+// Each family is a path-compressed binary trie (a Patricia trie). A node
+// holds a whole prefix rather than one bit of it, and there are only nodes
+// for the prefixes in the map and for the points where two of them part
+// ways, so n prefixes take at most 2n - 1 nodes however long they are. The
+// nodes live in one Vec and refer to their children by index, which keeps
+// them small and close together.
+//
+// A lookup walks down from the root, doing a masked compare and reading one
+// bit at each node, and each node it visits is longer than the last, so it
+// touches at most 33 nodes for IPv4 and 129 for IPv6, and never more than
+// the number of prefixes that cover the address plus the branch points
+// between them. get, insert, remove and longest_match are all O(W) with W
+// the address width, and do not depend on how many prefixes are in the map;
+// matches and subtree add the cost of each entry they return.
+#[derive(Clone)]
+pub struct PrefixMap<V> {
+    v4: Trie<u32, V>,
+    v6: Trie<u128, V>,
+}
+
+impl<V> PrefixMap<V> {
+    // new returns an empty map.
+    pub const fn new() -> PrefixMap<V> {
+        PrefixMap {
+            v4: Trie::new(),
+            v6: Trie::new(),
+        }
+    }
+
+    // len returns the number of prefixes in the map.
+    pub fn len(&self) -> usize {
+        self.v4.len + self.v6.len
+    }
+
+    // is_empty returns true if the map has no prefixes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // insert maps net to value, and returns the value it was mapped to
+    // before, if any.
+    pub fn insert(&mut self, net: IpNet, value: V) -> Option<V> {
+        match net {
+            IpNet::V4(p) => self.v4.insert(p.network().to_bits(), p.prefix_len(), value),
+            IpNet::V6(p) => self.v6.insert(p.network().to_bits(), p.prefix_len(), value),
+        }
+    }
+
+    // remove takes net out of the map, and returns the value it was mapped
+    // to, if any.
+    pub fn remove(&mut self, net: IpNet) -> Option<V> {
+        match net {
+            IpNet::V4(p) => self.v4.remove(p.network().to_bits(), p.prefix_len()),
+            IpNet::V6(p) => self.v6.remove(p.network().to_bits(), p.prefix_len()),
+        }
+    }
+
+    // get returns the value net is mapped to. Only net itself matches; see
+    // longest_match to find the prefixes that contain it.
+    pub fn get(&self, net: IpNet) -> Option<&V> {
+        match net {
+            IpNet::V4(p) => {
+                let node = self.v4.find(p.network().to_bits(), p.prefix_len())?;
+                self.v4.nodes[node].value.as_ref()
+            }
+            IpNet::V6(p) => {
+                let node = self.v6.find(p.network().to_bits(), p.prefix_len())?;
+                self.v6.nodes[node].value.as_ref()
+            }
+        }
+    }
+
+    // get_mut returns a mutable reference to the value net is mapped to.
+    pub fn get_mut(&mut self, net: IpNet) -> Option<&mut V> {
+        match net {
+            IpNet::V4(p) => {
+                let node = self.v4.find(p.network().to_bits(), p.prefix_len())?;
+                self.v4.nodes[node].value.as_mut()
+            }
+            IpNet::V6(p) => {
+                let node = self.v6.find(p.network().to_bits(), p.prefix_len())?;
+                self.v6.nodes[node].value.as_mut()
+            }
+        }
+    }
+
+    // contains_key returns true if net is in the map.
+    pub fn contains_key(&self, net: IpNet) -> bool {
+        self.get(net).is_some()
+    }
+
+    // longest_match returns the longest prefix in the map that contains
+    // addr, and its value. The zone of an IPv6 address is ignored, and an
+    // IPv4-mapped address only matches IPv6 prefixes.
+    pub fn longest_match(&self, addr: IpAddr) -> Option<(IpNet, &V)> {
+        match addr {
+            IpAddr::V4(a) => self.v4.longest_match(a.to_bits()),
+            IpAddr::V6(a) => self.v6.longest_match(a.to_bits()),
+        }
+    }
+
+    // matches returns an iterator over every prefix in the map that
+    // contains addr, and their values, from the shortest to the longest.
+    pub fn matches(&self, addr: IpAddr) -> Matches<'_, V> {
+        let (v4, v6) = match addr {
+            IpAddr::V4(a) => (self.v4.climb(a.to_bits()), Climb::empty(&self.v6)),
+            IpAddr::V6(a) => (Climb::empty(&self.v4), self.v6.climb(a.to_bits())),
+        };
+        Matches { v4, v6 }
+    }
+
+    // iter returns an iterator over the prefixes in the map and their
+    // values, in the order of IpNet.
+    pub fn iter(&self) -> Entries<'_, V> {
+        Entries {
+            v4: Walk::new(&self.v4, self.v4.root),
+            v6: Walk::new(&self.v6, self.v6.root),
+        }
+    }
+
+    // subtree returns an iterator over the prefixes in the map that net
+    // contains, including net itself, and their values, in the order of
+    // IpNet.
+    pub fn subtree(&self, net: IpNet) -> Entries<'_, V> {
+        let (v4, v6) = match net {
+            IpNet::V4(p) => {
+                let top = self.v4.top(p.network().to_bits(), p.prefix_len());
+                (top, NIL)
+            }
+            IpNet::V6(p) => {
+                let top = self.v6.top(p.network().to_bits(), p.prefix_len());
+                (NIL, top)
+            }
+        };
+        Entries {
+            v4: Walk::new(&self.v4, v4),
+            v6: Walk::new(&self.v6, v6),
+        }
+    }
+}
+
+impl<V> Default for PrefixMap<V> {
+    fn default() -> PrefixMap<V> {
+        PrefixMap::new()
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for PrefixMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> FromIterator<(IpNet, V)> for PrefixMap<V> {
+    fn from_iter<I: IntoIterator<Item = (IpNet, V)>>(iter: I) -> PrefixMap<V> {
+        let mut map = PrefixMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(IpNet, V)> for PrefixMap<V> {
+    fn extend<I: IntoIterator<Item = (IpNet, V)>>(&mut self, iter: I) {
+        for (net, value) in iter {
+            self.insert(net, value);
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a PrefixMap<V> {
+    type Item = (IpNet, &'a V);
+    type IntoIter = Entries<'a, V>;
+
+    fn into_iter(self) -> Entries<'a, V> {
+        self.iter()
+    }
+}
+
+// Entries iterates over the prefixes of a map. See PrefixMap::iter and
+// PrefixMap::subtree.
+pub struct Entries<'a, V> {
+    v4: Walk<'a, u32, V>,
+    v6: Walk<'a, u128, V>,
+}
+
+impl<'a, V> Iterator for Entries<'a, V> {
+    type Item = (IpNet, &'a V);
+
+    fn next(&mut self) -> Option<(IpNet, &'a V)> {
+        self.v4.next().or_else(|| self.v6.next())
+    }
+}
+
+// Matches iterates over the prefixes of a map that contain an address. See
+// PrefixMap::matches.
+pub struct Matches<'a, V> {
+    v4: Climb<'a, u32, V>,
+    v6: Climb<'a, u128, V>,
+}
+
+impl<'a, V> Iterator for Matches<'a, V> {
+    type Item = (IpNet, &'a V);
+
+    fn next(&mut self) -> Option<(IpNet, &'a V)> {
+        self.v4.next().or_else(|| self.v6.next())
+    }
+}
+
+// NIL is the index of a missing node.
+const NIL: u32 = u32::MAX;
+
+// Node is a prefix in the trie. A node without a value is a branch point,
+// and always has both children. The children of a node are longer than it,
+// and child[b] is the one whose next bit is b.
+#[derive(Clone)]
+struct Node<K, V> {
+    bits: K,
+    len: u8,
+    value: Option<V>,
+    child: [u32; 2],
+}
+
+// Trie is the trie for one family. The nodes of removed prefixes are kept
+// on a free list for the next insert.
+#[derive(Clone)]
+struct Trie<K, V> {
+    nodes: Vec<Node<K, V>>,
+    free: Vec<u32>,
+    root: u32,
+    len: usize,
+}
+
+// Link is where a node hangs from: the root, or a child of another node.
+type Link = Option<(u32, usize)>;
+
+impl<K: Bits, V> Trie<K, V> {
+    const fn new() -> Trie<K, V> {
+        Trie {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            len: 0,
+        }
+    }
+
+    // matches reports whether node i is a prefix of bits.
+    fn matches(&self, i: u32, bits: K) -> bool {
+        let node = &self.nodes[i as usize];
+        bits.masked(node.len) == node.bits
+    }
+
+    fn alloc(&mut self, node: Node<K, V>) -> u32 {
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i as usize] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                (self.nodes.len() - 1) as u32
+            }
+        }
+    }
+
+    fn link(&mut self, at: Link, to: u32) {
+        match at {
+            Some((parent, side)) => self.nodes[parent as usize].child[side] = to,
+            None => self.root = to,
+        }
+    }
+
+    fn insert(&mut self, bits: K, len: u8, value: V) -> Option<V> {
+        let mut at: Link = None;
+        let mut cur = self.root;
+        while cur != NIL {
+            let node = &mut self.nodes[cur as usize];
+            let common = node.len.min(len).min(node.bits.common(bits));
+            if common == node.len {
+                if node.len == len {
+                    let old = node.value.replace(value);
+                    self.len += old.is_none() as usize;
+                    return old;
+                }
+                at = Some((cur, bits.bit(node.len)));
+                cur = node.child[bits.bit(node.len)];
+                continue;
+            }
+
+            // the new prefix leaves the path to cur before reaching it:
+            // either it covers cur, or a branch point is needed where the
+            // two part ways.
+            let side = node.bits.bit(common);
+            let new = if common == len {
+                let mut child = [NIL; 2];
+                child[side] = cur;
+                self.alloc(Node {
+                    bits,
+                    len,
+                    value: Some(value),
+                    child,
+                })
+            } else {
+                let leaf = self.alloc(Node {
+                    bits,
+                    len,
+                    value: Some(value),
+                    child: [NIL; 2],
+                });
+                let mut child = [NIL; 2];
+                child[side] = cur;
+                child[1 - side] = leaf;
+                self.alloc(Node {
+                    bits: bits.masked(common),
+                    len: common,
+                    value: None,
+                    child,
+                })
+            };
+            self.link(at, new);
+            self.len += 1;
+            return None;
+        }
+
+        let leaf = self.alloc(Node {
+            bits,
+            len,
+            value: Some(value),
+            child: [NIL; 2],
+        });
+        self.link(at, leaf);
+        self.len += 1;
+        None
+    }
+
+    fn remove(&mut self, bits: K, len: u8) -> Option<V> {
+        let mut up: Link = None;
+        let mut at: Link = None;
+        let mut cur = self.root;
+        loop {
+            if cur == NIL || !self.matches(cur, bits) {
+                return None;
+            }
+            let node = &self.nodes[cur as usize];
+            if node.len == len {
+                break;
+            }
+            if node.len > len {
+                return None;
+            }
+            up = at;
+            at = Some((cur, bits.bit(node.len)));
+            cur = node.child[bits.bit(node.len)];
+        }
+
+        let node = &mut self.nodes[cur as usize];
+        let value = node.value.take()?;
+        self.len -= 1;
+        match node.child {
+            // a node with two children stays as a branch point.
+            [a, b] if a != NIL && b != NIL => {}
+            [a, NIL] | [NIL, a] if a != NIL => {
+                self.link(at, a);
+                self.free.push(cur);
+            }
+            _ => {
+                self.link(at, NIL);
+                self.free.push(cur);
+                // a branch point left with one child is not needed.
+                if let Some((parent, side)) = at
+                    && self.nodes[parent as usize].value.is_none()
+                {
+                    let other = self.nodes[parent as usize].child[1 - side];
+                    self.link(up, other);
+                    self.free.push(parent);
+                }
+            }
+        }
+        Some(value)
+    }
+
+    // find returns the node for exactly this prefix, if there is one.
+    fn find(&self, bits: K, len: u8) -> Option<usize> {
+        let mut cur = self.root;
+        while cur != NIL && self.matches(cur, bits) {
+            let node = &self.nodes[cur as usize];
+            if node.len >= len {
+                return (node.len == len).then_some(cur as usize);
+            }
+            cur = node.child[bits.bit(node.len)];
+        }
+        None
+    }
+
+    fn longest_match(&self, bits: K) -> Option<(IpNet, &V)> {
+        let mut best = None;
+        let mut cur = self.root;
+        while cur != NIL && self.matches(cur, bits) {
+            let node = &self.nodes[cur as usize];
+            if let Some(value) = &node.value {
+                best = Some((node.bits, node.len, value));
+            }
+            if node.len == K::BITS {
+                break;
+            }
+            cur = node.child[bits.bit(node.len)];
+        }
+        best.map(|(bits, len, value)| (bits.net(len), value))
+    }
+
+    // top returns the shortest node inside the prefix, which is the root of
+    // the subtree of everything inside it.
+    fn top(&self, bits: K, len: u8) -> u32 {
+        let mut cur = self.root;
+        while cur != NIL {
+            let node = &self.nodes[cur as usize];
+            if node.len >= len {
+                return if node.bits.masked(len) == bits {
+                    cur
+                } else {
+                    NIL
+                };
+            }
+            if !self.matches(cur, bits) {
+                return NIL;
+            }
+            cur = node.child[bits.bit(node.len)];
+        }
+        NIL
+    }
+
+    fn climb(&self, bits: K) -> Climb<'_, K, V> {
+        Climb {
+            nodes: &self.nodes,
+            cur: self.root,
+            bits,
+        }
+    }
+}
+
+// Walk visits the nodes under one, in order: each node before its children,
+// and child[0] before child[1].
+struct Walk<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    stack: Vec<u32>,
+}
+
+impl<'a, K: Bits, V> Walk<'a, K, V> {
+    fn new(trie: &'a Trie<K, V>, top: u32) -> Walk<'a, K, V> {
+        let mut stack = Vec::new();
+        if top != NIL {
+            stack.push(top);
+        }
+        Walk {
+            nodes: &trie.nodes,
+            stack,
+        }
+    }
+}
+
+impl<'a, K: Bits, V> Iterator for Walk<'a, K, V> {
+    type Item = (IpNet, &'a V);
+
+    fn next(&mut self) -> Option<(IpNet, &'a V)> {
+        while let Some(i) = self.stack.pop() {
+            let node = &self.nodes[i as usize];
+            for child in [node.child[1], node.child[0]] {
+                if child != NIL {
+                    self.stack.push(child);
+                }
+            }
+            if let Some(value) = &node.value {
+                return Some((node.bits.net(node.len), value));
+            }
+        }
+        None
+    }
+}
+
+// Climb visits the nodes that are prefixes of an address, from the root
+// down.
+struct Climb<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    cur: u32,
+    bits: K,
+}
+
+impl<'a, K: Bits, V> Climb<'a, K, V> {
+    fn empty(trie: &'a Trie<K, V>) -> Climb<'a, K, V> {
+        Climb {
+            nodes: &trie.nodes,
+            cur: NIL,
+            bits: K::default(),
+        }
+    }
+}
+
+impl<'a, K: Bits, V> Iterator for Climb<'a, K, V> {
+    type Item = (IpNet, &'a V);
+
+    fn next(&mut self) -> Option<(IpNet, &'a V)> {
+        while self.cur != NIL {
+            let node = &self.nodes[self.cur as usize];
+            if self.bits.masked(node.len) != node.bits {
+                self.cur = NIL;
+                return None;
+            }
+            self.cur = if node.len == K::BITS {
+                NIL
+            } else {
+                node.child[self.bits.bit(node.len)]
+            };
+            if let Some(value) = &node.value {
+                return Some((node.bits.net(node.len), value));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod map_tests {
+    use super::{NIL, PrefixMap};
+    use crate::rng::Rng;
+    use crate::testing::{addr, net};
+    use crate::{IpNet, ParseOptions, ipv4};
+
+    fn routes() -> PrefixMap<&'static str> {
+        [
+            ("0.0.0.0/0", "default"),
+            ("10.0.0.0/8", "corp"),
+            ("10.1.0.0/16", "lab"),
+            ("10.1.2.0/24", "rack"),
+            ("10.1.2.3/32", "host"),
+            ("10.128.0.0/9", "dc"),
+            ("192.168.0.0/16", "home"),
+            ("2001:db8::/32", "doc"),
+            ("2001:db8:1::/48", "site"),
+            ("::/0", "default6"),
+        ]
+        .into_iter()
+        .map(|(s, v)| (net(s), v))
+        .collect()
+    }
+
+    #[test]
+    fn test_get_insert_remove() {
+        let mut map = routes();
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(net("10.1.0.0/16")), Some(&"lab"));
+        assert_eq!(map.get(net("10.1.0.0/17")), None);
+        assert_eq!(map.get(net("10.0.0.0/16")), None);
+        assert_eq!(map.get(net("2001:db8::/32")), Some(&"doc"));
+        assert!(!map.contains_key(net("10.1.2.0/23")));
+
+        assert_eq!(map.insert(net("10.1.0.0/16"), "lab2"), Some("lab"));
+        *map.get_mut(net("10.1.0.0/16")).unwrap() = "lab3";
+        assert_eq!(map.get(net("10.1.0.0/16")), Some(&"lab3"));
+        assert_eq!(map.len(), 10);
+
+        assert_eq!(map.remove(net("10.1.0.0/16")), Some("lab3"));
+        assert_eq!(map.remove(net("10.1.0.0/16")), None);
+        assert_eq!(map.remove(net("10.0.0.0/15")), None);
+        assert_eq!(map.len(), 9);
+        // the prefixes under a removed one are still there.
+        assert_eq!(map.get(net("10.1.2.0/24")), Some(&"rack"));
+
+        // removing everything frees every node.
+        let nets: Vec<IpNet> = map.iter().map(|(net, _)| net).collect();
+        for net in nets {
+            assert!(map.remove(net).is_some(), "remove({})", net);
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.v4.root, NIL);
+        assert_eq!(map.v6.root, NIL);
+        assert_eq!(map.v4.free.len(), map.v4.nodes.len());
+    }
+
+    #[test]
+    fn test_longest_match() {
+        let map = routes();
+        let cases = Vec::from([
+            ("10.1.2.3", Some(("10.1.2.3/32", "host"))),
+            ("10.1.2.4", Some(("10.1.2.0/24", "rack"))),
+            ("10.1.3.1", Some(("10.1.0.0/16", "lab"))),
+            ("10.2.0.1", Some(("10.0.0.0/8", "corp"))),
+            ("10.200.0.1", Some(("10.128.0.0/9", "dc"))),
+            ("8.8.8.8", Some(("0.0.0.0/0", "default"))),
+            ("2001:db8:1::1", Some(("2001:db8:1::/48", "site"))),
+            ("2001:db8:2::1", Some(("2001:db8::/32", "doc"))),
+            ("::ffff:10.1.2.3", Some(("::/0", "default6"))),
+        ]);
+        for (s, want) in cases {
+            let want = want.map(|(n, v)| (net(n), v));
+            let got = map.longest_match(addr(s)).map(|(n, &v)| (n, v));
+            assert_eq!(got, want, "longest_match({})", s);
+        }
+
+        let zoned = ParseOptions::permissive()
+            .parse("2001:db8:1::1%eth0")
+            .unwrap();
+        assert_eq!(
+            map.longest_match(zoned),
+            Some((net("2001:db8:1::/48"), &"site"))
+        );
+
+        let mut map = map;
+        map.remove(net("0.0.0.0/0"));
+        assert_eq!(map.longest_match(addr("8.8.8.8")), None);
+    }
+
+    #[test]
+    fn test_matches_and_subtree() {
+        let map = routes();
+        let got: Vec<&str> = map.matches(addr("10.1.2.3")).map(|(_, v)| *v).collect();
+        assert_eq!(got, ["default", "corp", "lab", "rack", "host"]);
+        let got: Vec<&str> = map
+            .matches(addr("2001:db8:1::1"))
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(got, ["default6", "doc", "site"]);
+
+        let got: Vec<IpNet> = map.subtree(net("10.0.0.0/8")).map(|(n, _)| n).collect();
+        let want = [
+            "10.0.0.0/8",
+            "10.1.0.0/16",
+            "10.1.2.0/24",
+            "10.1.2.3/32",
+            "10.128.0.0/9",
+        ];
+        assert_eq!(got, want.map(net));
+        // a subtree need not be rooted at a prefix in the map.
+        let got: Vec<IpNet> = map.subtree(net("10.1.2.0/23")).map(|(n, _)| n).collect();
+        assert_eq!(got, ["10.1.2.0/24", "10.1.2.3/32"].map(net));
+        assert_eq!(map.subtree(net("10.2.0.0/16")).count(), 0);
+        assert_eq!(map.subtree(net("::/0")).count(), 3);
+
+        // iter is in the order of IpNet.
+        let got: Vec<IpNet> = map.iter().map(|(n, _)| n).collect();
+        let mut want = got.clone();
+        want.sort();
+        assert_eq!(got, want);
+        assert_eq!(got.len(), map.len());
+    }
+
+    #[test]
+    fn test_against_model() {
+        // random inserts and removes of prefixes inside 10.0.0.0/16, checked
+        // against a list searched one entry at a time.
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        let base = ipv4::Addr::new(10, 0, 0, 0).to_bits();
+
+        let mut map = PrefixMap::new();
+        let mut model: Vec<(ipv4::Prefix, u64)> = Vec::new();
+        for round in 0..4000u64 {
+            let len = 8 + (rng.next() % 25) as u8;
+            let bits = base | (rng.next() as u32 & 0xffff);
+            let p = ipv4::Prefix::new_masked(ipv4::Addr::from_bits(bits), len).unwrap();
+            let i = model.iter().position(|&(q, _)| q == p);
+            if rng.next().is_multiple_of(3) {
+                let want = i.map(|i| model.swap_remove(i).1);
+                assert_eq!(map.remove(p.into()), want, "remove({})", p);
+            } else {
+                let want = match i {
+                    Some(i) => Some(core::mem::replace(&mut model[i].1, round)),
+                    None => {
+                        model.push((p, round));
+                        None
+                    }
+                };
+                assert_eq!(map.insert(p.into(), round), want, "insert({})", p);
+            }
+            assert_eq!(map.len(), model.len());
+
+            let a = ipv4::Addr::from_bits(base | (rng.next() as u32 & 0xffff));
+            let want = model
+                .iter()
+                .filter(|(q, _)| q.contains(a))
+                .max_by_key(|(q, _)| q.prefix_len())
+                .map(|&(q, v)| (IpNet::from(q), v));
+            let got = map.longest_match(a.into()).map(|(n, &v)| (n, v));
+            assert_eq!(got, want, "longest_match({})", a);
+            assert_eq!(map.matches(a.into()).last().map(|(n, &v)| (n, v)), want);
+        }
+
+        model.sort();
+        let got: Vec<(IpNet, u64)> = map.iter().map(|(n, &v)| (n, v)).collect();
+        let want: Vec<(IpNet, u64)> = model.iter().map(|&(p, v)| (p.into(), v)).collect();
+        assert_eq!(got, want);
+    }
+}
@@ -0,0 +1,380 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::ip::is_ipv6;
+use crate::{IpAddr, IpNet, ParseOptions, ipv4, ipv6};
+
+// InvalidRangeErr describes why a string is not a valid address range of
+// either family. It wraps the error of the family the string was parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidRangeErr {
+    V4(ipv4::InvalidRangeErr),
+    V6(ipv6::InvalidRangeErr),
+}
+
+impl InvalidRangeErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match self {
+            InvalidRangeErr::V4(err) => err.offset(),
+            InvalidRangeErr::V6(err) => err.offset(),
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidRangeErr::V4(err) => err.reason(),
+            InvalidRangeErr::V6(err) => err.reason(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidRangeErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidRangeErr::V4(err) => write!(f, "{}", err),
+            InvalidRangeErr::V6(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for InvalidRangeErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidRangeErr::V4(err) => Some(err),
+            InvalidRangeErr::V6(err) => Some(err),
+        }
+    }
+}
+
+impl From<ipv4::InvalidRangeErr> for InvalidRangeErr {
+    fn from(err: ipv4::InvalidRangeErr) -> InvalidRangeErr {
+        InvalidRangeErr::V4(err)
+    }
+}
+
+impl From<ipv6::InvalidRangeErr> for InvalidRangeErr {
+    fn from(err: ipv6::InvalidRangeErr) -> InvalidRangeErr {
+        InvalidRangeErr::V6(err)
+    }
+}
+
+// AddrRange is an address range of either family. Both ends of a range are
+// of the same family. Like IpNet, the derived ordering puts every IPv4 range
+// before every IPv6 range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddrRange {
+    V4(ipv4::AddrRange),
+    V6(ipv6::AddrRange),
+}
+
+impl AddrRange {
+    // new builds the range from start to end, both included. It returns
+    // None if the two are of different families or end is before start.
+    pub const fn new(start: IpAddr, end: IpAddr) -> Option<AddrRange> {
+        match (start, end) {
+            (IpAddr::V4(start), IpAddr::V4(end)) => match ipv4::AddrRange::new(start, end) {
+                Some(r) => Some(AddrRange::V4(r)),
+                None => None,
+            },
+            (IpAddr::V6(start), IpAddr::V6(end)) => match ipv6::AddrRange::new(start, end) {
+                Some(r) => Some(AddrRange::V6(r)),
+                None => None,
+            },
+            _ => None,
+        }
+    }
+
+    // is_ipv4 returns true for an IPv4 range.
+    pub const fn is_ipv4(&self) -> bool {
+        matches!(self, AddrRange::V4(_))
+    }
+
+    // is_ipv6 returns true for an IPv6 range.
+    pub const fn is_ipv6(&self) -> bool {
+        matches!(self, AddrRange::V6(_))
+    }
+
+    // start returns the first address in the range.
+    pub const fn start(&self) -> IpAddr {
+        match self {
+            AddrRange::V4(r) => IpAddr::V4(r.start()),
+            AddrRange::V6(r) => IpAddr::V6(r.start()),
+        }
+    }
+
+    // end returns the last address in the range.
+    pub const fn end(&self) -> IpAddr {
+        match self {
+            AddrRange::V4(r) => IpAddr::V4(r.end()),
+            AddrRange::V6(r) => IpAddr::V6(r.end()),
+        }
+    }
+
+    // contains returns true if addr is in the range. An address of the
+    // other family is never in the range.
+    pub const fn contains(&self, addr: IpAddr) -> bool {
+        match (self, addr) {
+            (AddrRange::V4(r), IpAddr::V4(a)) => r.contains(a),
+            (AddrRange::V6(r), IpAddr::V6(a)) => r.contains(a),
+            _ => false,
+        }
+    }
+
+    // contains_range returns true if every address in other is also in this
+    // range. Ranges of different families never contain each other.
+    pub const fn contains_range(&self, other: &AddrRange) -> bool {
+        match (self, other) {
+            (AddrRange::V4(r), AddrRange::V4(o)) => r.contains_range(o),
+            (AddrRange::V6(r), AddrRange::V6(o)) => r.contains_range(o),
+            _ => false,
+        }
+    }
+
+    // overlaps returns true if the two ranges have any address in common.
+    // Ranges of different families never overlap.
+    pub const fn overlaps(&self, other: &AddrRange) -> bool {
+        match (self, other) {
+            (AddrRange::V4(r), AddrRange::V4(o)) => r.overlaps(o),
+            (AddrRange::V6(r), AddrRange::V6(o)) => r.overlaps(o),
+            _ => false,
+        }
+    }
+
+    // nets returns an iterator over the fewest prefixes that together cover
+    // exactly the range, in order. See ipv4::AddrRange::prefixes.
+    pub fn nets(&self) -> Nets {
+        match self {
+            AddrRange::V4(r) => Nets::V4(r.prefixes()),
+            AddrRange::V6(r) => Nets::V6(r.prefixes()),
+        }
+    }
+}
+
+impl fmt::Display for AddrRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddrRange::V4(r) => write!(f, "{}", r),
+            AddrRange::V6(r) => write!(f, "{}", r),
+        }
+    }
+}
+
+impl FromStr for AddrRange {
+    type Err = InvalidRangeErr;
+
+    fn from_str(s: &str) -> Result<AddrRange, InvalidRangeErr> {
+        parse_range(s)
+    }
+}
+
+impl From<ipv4::AddrRange> for AddrRange {
+    fn from(r: ipv4::AddrRange) -> AddrRange {
+        AddrRange::V4(r)
+    }
+}
+
+impl From<ipv6::AddrRange> for AddrRange {
+    fn from(r: ipv6::AddrRange) -> AddrRange {
+        AddrRange::V6(r)
+    }
+}
+
+impl From<ipv4::Prefix> for AddrRange {
+    fn from(prefix: ipv4::Prefix) -> AddrRange {
+        AddrRange::V4(prefix.into())
+    }
+}
+
+impl From<ipv6::Prefix> for AddrRange {
+    fn from(prefix: ipv6::Prefix) -> AddrRange {
+        AddrRange::V6(prefix.into())
+    }
+}
+
+impl From<IpNet> for AddrRange {
+    fn from(net: IpNet) -> AddrRange {
+        match net {
+            IpNet::V4(p) => AddrRange::V4(p.into()),
+            IpNet::V6(p) => AddrRange::V6(p.into()),
+        }
+    }
+}
+
+impl From<IpAddr> for AddrRange {
+    // an address on its own is the range that contains only it.
+    fn from(addr: IpAddr) -> AddrRange {
+        match addr {
+            IpAddr::V4(a) => AddrRange::V4(a.into()),
+            IpAddr::V6(a) => AddrRange::V6(a.into()),
+        }
+    }
+}
+
+impl From<ipv4::Addr> for AddrRange {
+    fn from(addr: ipv4::Addr) -> AddrRange {
+        AddrRange::V4(addr.into())
+    }
+}
+
+impl From<ipv6::Addr> for AddrRange {
+    fn from(addr: ipv6::Addr) -> AddrRange {
+        AddrRange::V6(addr.into())
+    }
+}
+
+// Nets iterates over the prefixes that make up a range. See AddrRange::nets.
+#[derive(Debug, Clone)]
+pub enum Nets {
+    V4(ipv4::Prefixes),
+    V6(ipv6::Prefixes),
+}
+
+impl Iterator for Nets {
+    type Item = IpNet;
+
+    fn next(&mut self) -> Option<IpNet> {
+        match self {
+            Nets::V4(it) => it.next().map(IpNet::V4),
+            Nets::V6(it) => it.next().map(IpNet::V6),
+        }
+    }
+}
+
+impl ParseOptions {
+    // parse_range will parse a range of either family into an AddrRange
+    // using these options. See the parse_range function.
+    pub fn parse_range(&self, s: &str) -> Result<AddrRange, InvalidRangeErr> {
+        if is_ipv6(s) {
+            Ok(AddrRange::V6(self.get_v6().parse_range(s)?))
+        } else {
+            Ok(AddrRange::V4(self.get_v4().parse_range(s)?))
+        }
+    }
+}
+
+// parse_range will parse an IPv4 or IPv6 address range, e.g.
+// "10.0.0.5-10.0.3.17" or "2001:db8::5 - 2001:db8::3:17", into an
+// AddrRange, picking the family the same way as parse_addr.
+pub fn parse_range(s: &str) -> Result<AddrRange, InvalidRangeErr> {
+    ParseOptions::default().parse_range(s)
+}
+
+// collapse_nets merges prefixes of either family into the fewest ranges
+// that cover the same addresses: the IPv4 ranges in order, then the IPv6
+// ones. See ipv4::collapse_prefixes.
+#[cfg(feature = "alloc")]
+pub fn collapse_nets(nets: impl IntoIterator<Item = IpNet>) -> alloc::vec::Vec<AddrRange> {
+    let mut v4 = alloc::vec::Vec::new();
+    let mut v6 = alloc::vec::Vec::new();
+    for net in nets {
+        match net {
+            IpNet::V4(p) => v4.push(p),
+            IpNet::V6(p) => v6.push(p),
+        }
+    }
+
+    let v4 = ipv4::collapse_prefixes(v4).into_iter().map(AddrRange::V4);
+    let v6 = ipv6::collapse_prefixes(v6).into_iter().map(AddrRange::V6);
+    v4.chain(v6).collect()
+}
+
+#[cfg(test)]
+mod range_tests {
+    use super::{AddrRange, InvalidRangeErr, parse_range};
+    use crate::testing::{addr, net, range};
+    use crate::{IpNet, ipv4, ipv6};
+
+    #[test]
+    fn test_parse_range() {
+        let cases = Vec::from([
+            ("10.0.0.5-10.0.3.17", Ok(range("10.0.0.5", "10.0.3.17"))),
+            (
+                "2001:db8::5 - 2001:db8::3:17",
+                Ok(range("2001:db8::5", "2001:db8::3:17")),
+            ),
+            (
+                "10.0.0.9-10.0.0.1",
+                Err(InvalidRangeErr::V4(ipv4::InvalidRangeErr::Reversed {
+                    offset: 9,
+                })),
+            ),
+            (
+                "::1",
+                Err(InvalidRangeErr::V6(ipv6::InvalidRangeErr::MissingEnd {
+                    offset: 3,
+                })),
+            ),
+            // a range of mixed families is read as IPv6 and fails at the
+            // IPv4 address.
+            (
+                "10.0.0.1-::1",
+                Err(InvalidRangeErr::V6(ipv6::InvalidRangeErr::Addr(
+                    ipv6::InvalidAddrErr::TooFewGroups {
+                        offset: 8,
+                        group: 2,
+                    },
+                ))),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_range(s), want, "parse_range({:?})", s);
+        }
+
+        assert_eq!(AddrRange::new(addr("10.0.0.1"), addr("::1")), None);
+        assert_eq!(
+            range("10.0.0.5", "10.0.3.17").to_string(),
+            "10.0.0.5-10.0.3.17"
+        );
+    }
+
+    #[test]
+    fn test_range_membership() {
+        let r = range("10.0.0.5", "10.0.3.17");
+        assert!(r.is_ipv4());
+        assert_eq!(r.start(), addr("10.0.0.5"));
+        assert_eq!(r.end(), addr("10.0.3.17"));
+        assert!(r.contains(addr("10.0.1.1")));
+        assert!(!r.contains(addr("::ffff:10.0.1.1")));
+        assert!(r.contains_range(&range("10.0.1.0", "10.0.1.255")));
+        assert!(!r.overlaps(&range("::", "::1")));
+        assert_eq!(
+            AddrRange::from(net("2001:db8::/127")),
+            range("2001:db8::", "2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn test_nets() {
+        let got: Vec<IpNet> = range("10.0.0.5", "10.0.0.17").nets().collect();
+        let want = ["10.0.0.5/32", "10.0.0.6/31", "10.0.0.8/29", "10.0.0.16/31"].map(net);
+        assert_eq!(got, want);
+
+        let got: Vec<IpNet> = range("2001:db8::", "2001:db8::2").nets().collect();
+        assert_eq!(got, ["2001:db8::/127", "2001:db8::2/128"].map(net));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_collapse_nets() {
+        use super::collapse_nets;
+
+        let nets = [
+            "2001:db8::1/128",
+            "10.0.1.0/24",
+            "2001:db8::/128",
+            "10.0.0.0/24",
+        ]
+        .map(net);
+        assert_eq!(
+            collapse_nets(nets),
+            Vec::from([
+                range("10.0.0.0", "10.0.1.255"),
+                range("2001:db8::", "2001:db8::1"),
+            ])
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use core::fmt;
+
+use crate::{IpAddr, IpNet, ipv4, ipv6};
+
+// InvalidReverseNameErr describes why a string is not a valid reverse-DNS
+// name of either family. It wraps the error of the family the string was
+// parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidReverseNameErr {
+    V4(ipv4::InvalidReverseNameErr),
+    V6(ipv6::InvalidReverseNameErr),
+}
+
+impl InvalidReverseNameErr {
+    // offset returns the byte offset in the input at which the error was found.
+    pub const fn offset(&self) -> usize {
+        match self {
+            InvalidReverseNameErr::V4(err) => err.offset(),
+            InvalidReverseNameErr::V6(err) => err.offset(),
+        }
+    }
+
+    // reason returns a short description of the error, without position.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            InvalidReverseNameErr::V4(err) => err.reason(),
+            InvalidReverseNameErr::V6(err) => err.reason(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidReverseNameErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidReverseNameErr::V4(err) => write!(f, "{}", err),
+            InvalidReverseNameErr::V6(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for InvalidReverseNameErr {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            InvalidReverseNameErr::V4(err) => Some(err),
+            InvalidReverseNameErr::V6(err) => Some(err),
+        }
+    }
+}
+
+impl From<ipv4::InvalidReverseNameErr> for InvalidReverseNameErr {
+    fn from(err: ipv4::InvalidReverseNameErr) -> InvalidReverseNameErr {
+        InvalidReverseNameErr::V4(err)
+    }
+}
+
+impl From<ipv6::InvalidReverseNameErr> for InvalidReverseNameErr {
+    fn from(err: ipv6::InvalidReverseNameErr) -> InvalidReverseNameErr {
+        InvalidReverseNameErr::V6(err)
+    }
+}
+
+// ReverseName renders an address of either family as its reverse-DNS name.
+// See IpAddr::reverse_name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseName(IpAddr);
+
+impl fmt::Display for ReverseName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            IpAddr::V4(v4) => write!(f, "{}", v4.reverse_name()),
+            IpAddr::V6(v6) => write!(f, "{}", v6.reverse_name()),
+        }
+    }
+}
+
+// ReverseZone renders a prefix of either family as the name of its
+// reverse-DNS zone. See IpNet::reverse_zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseZone(IpNet);
+
+impl fmt::Display for ReverseZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            IpNet::V4(p) => write!(f, "{}", p.reverse_zone().unwrap()),
+            IpNet::V6(p) => write!(f, "{}", p.reverse_zone().unwrap()),
+        }
+    }
+}
+
+impl IpAddr {
+    // reverse_name returns the in-addr.arpa or ip6.arpa name of the address,
+    // as used for its PTR record. See ipv4::Addr::reverse_name and
+    // ipv6::Addr::reverse_name.
+    pub fn reverse_name(&self) -> ReverseName {
+        ReverseName(*self)
+    }
+}
+
+impl IpNet {
+    // reverse_zone returns the in-addr.arpa or ip6.arpa zone that holds the
+    // PTR records for the prefix, or None if the prefix has no zone of its
+    // own. See ipv4::Prefix::reverse_zone and ipv6::Prefix::reverse_zone.
+    pub fn reverse_zone(&self) -> Option<ReverseZone> {
+        let zoned = match self {
+            IpNet::V4(p) => p.reverse_zone().is_some(),
+            IpNet::V6(p) => p.reverse_zone().is_some(),
+        };
+        zoned.then_some(ReverseZone(*self))
+    }
+}
+
+// parse_reverse_name will parse the in-addr.arpa or ip6.arpa name of a PTR
+// record back into an IpAddr, picking the family from the suffix: anything
+// that does not end in "ip6.arpa" is read as IPv4.
+pub fn parse_reverse_name(s: &str) -> Result<IpAddr, InvalidReverseNameErr> {
+    if is_ip6_arpa(s) {
+        Ok(IpAddr::V6(ipv6::parse_reverse_name(s)?))
+    } else {
+        Ok(IpAddr::V4(ipv4::parse_reverse_name(s)?))
+    }
+}
+
+// parse_reverse_zone will parse an in-addr.arpa or ip6.arpa zone name back
+// into an IpNet, picking the family the same way as parse_reverse_name.
+pub fn parse_reverse_zone(s: &str) -> Result<IpNet, InvalidReverseNameErr> {
+    if is_ip6_arpa(s) {
+        Ok(IpNet::V6(ipv6::parse_reverse_zone(s)?))
+    } else {
+        Ok(IpNet::V4(ipv4::parse_reverse_zone(s)?))
+    }
+}
+
+// is_ip6_arpa decides which family a reverse name is parsed as.
+fn is_ip6_arpa(s: &str) -> bool {
+    labels(s.as_bytes(), b"ip6.arpa").is_some()
+}
+
+// labels splits a reverse name into the labels before suffix, which is
+// matched in any case, and returns them with the offset at which the suffix
+// starts. The name may end in a dot. It returns None if the name does not
+// end in suffix.
+pub(crate) fn labels<'a>(bytes: &'a [u8], suffix: &[u8]) -> Option<(Labels<'a>, usize)> {
+    let name = bytes.strip_suffix(b".").unwrap_or(bytes);
+    let end = name.len().checked_sub(suffix.len())?;
+    if !name[end..].eq_ignore_ascii_case(suffix) {
+        return None;
+    }
+    if end == 0 {
+        return Some((Labels { rest: None, at: 0 }, 0));
+    }
+    if name[end - 1] != b'.' {
+        return None;
+    }
+    let labels = Labels {
+        rest: Some(&name[..end - 1]),
+        at: 0,
+    };
+    Some((labels, end))
+}
+
+// Labels is an iterator over the labels of a reverse name, with the offset
+// of each. Empty labels are yielded too, so that they can be reported.
+pub(crate) struct Labels<'a> {
+    rest: Option<&'a [u8]>,
+    at: usize,
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = (usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<(usize, &'a [u8])> {
+        let rest = self.rest?;
+        let start = self.at;
+        match rest.iter().position(|&b| b == b'.') {
+            Some(i) => {
+                self.rest = Some(&rest[i + 1..]);
+                self.at += i + 1;
+                Some((start, &rest[..i]))
+            }
+            None => {
+                self.rest = None;
+                Some((start, rest))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod reverse_tests {
+    use super::{InvalidReverseNameErr, parse_reverse_name, parse_reverse_zone};
+    use crate::{IpAddr, IpNet, ipv4, ipv6};
+
+    #[test]
+    fn test_reverse_name() {
+        let cases = Vec::from([
+            ("192.0.2.1", "1.2.0.192.in-addr.arpa"),
+            (
+                "2001:db8::1",
+                "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+            ),
+        ]);
+
+        for (s, want) in cases {
+            let addr: IpAddr = s.parse().unwrap();
+            assert_eq!(addr.reverse_name().to_string(), want, "{}", s);
+            assert_eq!(parse_reverse_name(want), Ok(addr), "{}", want);
+        }
+    }
+
+    #[test]
+    fn test_reverse_zone() {
+        let cases = Vec::from([
+            ("192.0.2.0/24", Some("2.0.192.in-addr.arpa")),
+            ("192.0.2.64/26", Some("64/26.2.0.192.in-addr.arpa")),
+            ("2001:db8::/32", Some("8.b.d.0.1.0.0.2.ip6.arpa")),
+            ("172.16.0.0/12", None),
+            ("2001:db8::/30", None),
+        ]);
+
+        for (s, want) in cases {
+            let net: IpNet = s.parse().unwrap();
+            let got = net.reverse_zone().map(|zone| zone.to_string());
+            assert_eq!(got.as_deref(), want, "{}", s);
+            if let Some(name) = want {
+                assert_eq!(parse_reverse_zone(name), Ok(net), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reverse_errors() {
+        let cases = Vec::from([
+            (
+                "1.2.0.192.in-addr.arpa.example",
+                InvalidReverseNameErr::V4(ipv4::InvalidReverseNameErr::MissingSuffix {
+                    offset: 30,
+                }),
+            ),
+            (
+                "g.ip6.arpa",
+                InvalidReverseNameErr::V6(ipv6::InvalidReverseNameErr::InvalidLabel { offset: 0 }),
+            ),
+        ]);
+
+        for (s, want) in cases {
+            assert_eq!(parse_reverse_zone(s), Err(want), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// Rng is a xorshift64 generator, which is plenty for generating test inputs.
+// The property tests seed it with a constant so that a failure can be
+// reproduced.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    // new returns a generator seeded with seed, which must not be zero.
+    pub(crate) const fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    // next returns the next 64 random bits.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // next_u128 returns the next 128 random bits, from two calls to next.
+    pub(crate) fn next_u128(&mut self) -> u128 {
+        (self.next() as u128) << 64 | self.next() as u128
+    }
+}
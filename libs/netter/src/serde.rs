@@ -0,0 +1,423 @@
+// serde.rs has the Serialize and Deserialize impls for the address and
+// prefix types, behind the "serde" feature. They follow std::net's impls:
+// in human-readable formats like JSON, YAML and TOML a value is its
+// canonical string, "192.0.2.1" or "2001:db8::/32", and in binary formats it
+// is its octets, with a prefix length after them for a prefix. IpAddr and
+// IpNet are enums with "V4" and "V6" variants in binary formats, so an
+// IpAddr is encoded the same way as a std::net::IpAddr.
+//
+// Strings are parsed with the default ParseOptions of each type, so a
+// config value is held to what valid_ipv4 and valid_ipv6 accept, except that
+// ipv6::Addr takes a zone on any address so that every address it writes
+// can be read back. A string that does not parse fails with the Display of
+// the parse error, which has the reason and the byte offset.
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, EnumAccess, VariantAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::{IpAddr, IpNet, ipv4, ipv6};
+
+// Parse is a Visitor for the string form of a value, which it parses with
+// parse.
+struct Parse<T, E> {
+    expecting: &'static str,
+    parse: fn(&str) -> Result<T, E>,
+}
+
+impl<T, E: fmt::Display> Visitor<'_> for Parse<T, E> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<F: de::Error>(self, v: &str) -> Result<T, F> {
+        (self.parse)(v).map_err(F::custom)
+    }
+}
+
+// deserialize_str reads the string form of a value with parse.
+fn deserialize_str<'de, D, T, E>(
+    deserializer: D,
+    expecting: &'static str,
+    parse: fn(&str) -> Result<T, E>,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    E: fmt::Display,
+{
+    deserializer.deserialize_str(Parse { expecting, parse })
+}
+
+impl Serialize for ipv4::Addr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            self.octets().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ipv4::Addr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ipv4::Addr, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer, "an IPv4 address", ipv4::parse)
+        } else {
+            <[u8; 4]>::deserialize(deserializer).map(ipv4::Addr::from)
+        }
+    }
+}
+
+impl Serialize for ipv6::Addr {
+    // serialize fails for an address with a zone in a binary format, which
+    // has only the 16 octets.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else if self.zone().is_some() {
+            Err(ser::Error::custom(format_args!(
+                "ipv6 address {} has a zone, which binary formats cannot hold",
+                self
+            )))
+        } else {
+            self.octets().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ipv6::Addr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ipv6::Addr, D::Error> {
+        if deserializer.is_human_readable() {
+            deserialize_str(deserializer, "an IPv6 address", |s| {
+                ipv6::ParseOptions::default()
+                    .zones(ipv6::Zones::Any)
+                    .parse(s)
+            })
+        } else {
+            <[u8; 16]>::deserialize(deserializer).map(ipv6::Addr::from_octets)
+        }
+    }
+}
+
+impl Serialize for ipv4::Prefix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            (self.network(), self.prefix_len()).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ipv4::Prefix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ipv4::Prefix, D::Error> {
+        if deserializer.is_human_readable() {
+            return deserialize_str(deserializer, "an IPv4 prefix", ipv4::parse_prefix);
+        }
+        let (addr, len) = <(ipv4::Addr, u8)>::deserialize(deserializer)?;
+        ipv4::Prefix::new(addr, len).ok_or_else(|| invalid_prefix(addr, len, 32))
+    }
+}
+
+impl Serialize for ipv6::Prefix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            (self.network(), self.prefix_len()).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ipv6::Prefix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ipv6::Prefix, D::Error> {
+        if deserializer.is_human_readable() {
+            return deserialize_str(deserializer, "an IPv6 prefix", ipv6::parse_prefix);
+        }
+        let (addr, len) = <(ipv6::Addr, u8)>::deserialize(deserializer)?;
+        ipv6::Prefix::new(addr, len).ok_or_else(|| invalid_prefix(addr, len, 128))
+    }
+}
+
+// invalid_prefix is the error for the binary form of a prefix whose length
+// is more than max or whose address has host bits set.
+fn invalid_prefix<E: de::Error>(addr: impl fmt::Display, len: u8, max: u8) -> E {
+    if len > max {
+        E::invalid_value(
+            de::Unexpected::Unsigned(len as u64),
+            &"a prefix length in range",
+        )
+    } else {
+        E::custom(format_args!("host bits set in prefix {}/{}", addr, len))
+    }
+}
+
+// Family is the variant of an IpAddr or IpNet in a binary format.
+enum Family {
+    V4,
+    V6,
+}
+
+const VARIANTS: &[&str] = &["V4", "V6"];
+
+impl<'de> Deserialize<'de> for Family {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Family, D::Error> {
+        struct FamilyVisitor;
+
+        impl Visitor<'_> for FamilyVisitor {
+            type Value = Family;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("`V4` or `V6`")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Family, E> {
+                match v {
+                    0 => Ok(Family::V4),
+                    1 => Ok(Family::V6),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Family, E> {
+                match v {
+                    "V4" => Ok(Family::V4),
+                    "V6" => Ok(Family::V6),
+                    _ => Err(E::unknown_variant(v, VARIANTS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FamilyVisitor)
+    }
+}
+
+// Either is a Visitor for the binary form of IpAddr and IpNet: a V4 or V6
+// variant holding a value of the family, which wrap puts together.
+struct Either<T, V4, V6> {
+    expecting: &'static str,
+    wrap: fn(Result<V4, V6>) -> T,
+    family: PhantomData<(V4, V6)>,
+}
+
+impl<'de, T, V4, V6> Visitor<'de> for Either<T, V4, V6>
+where
+    V4: Deserialize<'de>,
+    V6: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<T, A::Error> {
+        match data.variant()? {
+            (Family::V4, v) => v.newtype_variant().map(|v4| (self.wrap)(Ok(v4))),
+            (Family::V6, v) => v.newtype_variant().map(|v6| (self.wrap)(Err(v6))),
+        }
+    }
+}
+
+impl Serialize for IpAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.collect_str(self);
+        }
+        match self {
+            IpAddr::V4(v4) => serializer.serialize_newtype_variant("IpAddr", 0, "V4", v4),
+            IpAddr::V6(v6) => serializer.serialize_newtype_variant("IpAddr", 1, "V6", v6),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IpAddr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IpAddr, D::Error> {
+        if deserializer.is_human_readable() {
+            return deserialize_str(deserializer, "an IP address", |s| {
+                let v6 = ipv6::ParseOptions::default().zones(ipv6::Zones::Any);
+                crate::ParseOptions::default().v6(v6).parse(s)
+            });
+        }
+        let visitor = Either {
+            expecting: "an IP address",
+            wrap: |addr| addr.map_or_else(IpAddr::V6, IpAddr::V4),
+            family: PhantomData,
+        };
+        deserializer.deserialize_enum("IpAddr", VARIANTS, visitor)
+    }
+}
+
+impl Serialize for IpNet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.collect_str(self);
+        }
+        match self {
+            IpNet::V4(v4) => serializer.serialize_newtype_variant("IpNet", 0, "V4", v4),
+            IpNet::V6(v6) => serializer.serialize_newtype_variant("IpNet", 1, "V6", v6),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IpNet, D::Error> {
+        if deserializer.is_human_readable() {
+            return deserialize_str(deserializer, "an IP prefix", crate::parse_net);
+        }
+        let visitor = Either {
+            expecting: "an IP prefix",
+            wrap: |net| net.map_or_else(IpNet::V6, IpNet::V4),
+            family: PhantomData,
+        };
+        deserializer.deserialize_enum("IpNet", VARIANTS, visitor)
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use std::fmt::{Debug, Display};
+
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use serde_test::{
+        Compact, Configure, Readable, Token, assert_de_tokens_error, assert_ser_tokens_error,
+        assert_tokens,
+    };
+
+    use crate::{IpAddr, IpNet, ipv4, ipv6};
+
+    // octets is the binary form of an address.
+    fn octets(octets: &[u8]) -> Vec<Token> {
+        let mut tokens = Vec::from([Token::Tuple { len: octets.len() }]);
+        tokens.extend(octets.iter().map(|&b| Token::U8(b)));
+        tokens.push(Token::TupleEnd);
+        tokens
+    }
+
+    // prefix is the binary form of a prefix.
+    fn prefix(addr: &[u8], len: u8) -> Vec<Token> {
+        let mut tokens = Vec::from([Token::Tuple { len: 2 }]);
+        tokens.extend(octets(addr));
+        tokens.extend([Token::U8(len), Token::TupleEnd]);
+        tokens
+    }
+
+    fn variant(name: &'static str, family: &'static str, tokens: Vec<Token>) -> Vec<Token> {
+        let mut all = Vec::from([Token::NewtypeVariant {
+            name,
+            variant: family,
+        }]);
+        all.extend(tokens);
+        all
+    }
+
+    // round_trip checks that value is written as readable and as binary,
+    // and that both are read back as value.
+    fn round_trip<T>(value: T, readable: &'static str, binary: Vec<Token>)
+    where
+        T: Serialize + DeserializeOwned + Copy + PartialEq + Debug,
+    {
+        assert_tokens(&value.readable(), &[Token::Str(readable)]);
+        assert_tokens(&value.compact(), &binary);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let v4: ipv4::Addr = "192.0.2.1".parse().unwrap();
+        let v6: ipv6::Addr = "2001:db8::1".parse().unwrap();
+        let v6_octets = v6.octets();
+        let net4: ipv4::Prefix = "10.0.0.0/8".parse().unwrap();
+        let net6: ipv6::Prefix = "2001:db8::/32".parse().unwrap();
+        let net6_octets = net6.network().octets();
+
+        round_trip(v4, "192.0.2.1", octets(&[192, 0, 2, 1]));
+        round_trip(v6, "2001:db8::1", octets(&v6_octets));
+        round_trip(net4, "10.0.0.0/8", prefix(&[10, 0, 0, 0], 8));
+        round_trip(net6, "2001:db8::/32", prefix(&net6_octets, 32));
+        round_trip(
+            IpAddr::V4(v4),
+            "192.0.2.1",
+            variant("IpAddr", "V4", octets(&[192, 0, 2, 1])),
+        );
+        round_trip(
+            IpAddr::V6(v6),
+            "2001:db8::1",
+            variant("IpAddr", "V6", octets(&v6_octets)),
+        );
+        round_trip(
+            IpNet::V4(net4),
+            "10.0.0.0/8",
+            variant("IpNet", "V4", prefix(&[10, 0, 0, 0], 8)),
+        );
+        round_trip(
+            IpNet::V6(net6),
+            "2001:db8::/32",
+            variant("IpNet", "V6", prefix(&net6_octets, 32)),
+        );
+    }
+
+    #[test]
+    fn test_zones() {
+        // a zone is kept in readable formats and refused in binary ones.
+        let zoned: ipv6::Addr = ipv6::ParseOptions::permissive()
+            .parse("2001:db8::1%eth0")
+            .unwrap();
+        let readable = [Token::Str("2001:db8::1%eth0")];
+        assert_tokens(&zoned.readable(), &readable);
+        assert_tokens(&IpAddr::V6(zoned).readable(), &readable);
+        assert_ser_tokens_error(
+            &zoned.compact(),
+            &[],
+            "ipv6 address 2001:db8::1%eth0 has a zone, which binary formats cannot hold",
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // a string that does not parse fails with the parse error.
+        fn check<T, E>(s: &'static str, err: E)
+        where
+            Readable<T>: DeserializeOwned,
+            E: Display,
+        {
+            assert_de_tokens_error::<Readable<T>>(&[Token::Str(s)], &err.to_string());
+        }
+
+        check::<ipv4::Addr, _>("010.0.0.1", ipv4::parse("010.0.0.1").unwrap_err());
+        check::<ipv6::Addr, _>("2001:db8:::1", ipv6::parse("2001:db8:::1").unwrap_err());
+        check::<ipv4::Prefix, _>("10.0.0.1/8", ipv4::parse_prefix("10.0.0.1/8").unwrap_err());
+        check::<ipv6::Prefix, _>("::/129", ipv6::parse_prefix("::/129").unwrap_err());
+        check::<IpAddr, _>("1.2.3", crate::parse_addr("1.2.3").unwrap_err());
+        check::<IpNet, _>("::1/x", crate::parse_net("::1/x").unwrap_err());
+    }
+
+    #[test]
+    fn test_binary_errors() {
+        let cases = Vec::from([
+            (
+                prefix(&[10, 0, 0, 1], 8),
+                "host bits set in prefix 10.0.0.1/8",
+            ),
+            (
+                prefix(&[10, 0, 0, 0], 33),
+                "invalid value: integer `33`, expected a prefix length in range",
+            ),
+        ]);
+
+        for (tokens, want) in cases {
+            assert_de_tokens_error::<Compact<ipv4::Prefix>>(&tokens, want);
+        }
+
+        assert_de_tokens_error::<Compact<IpAddr>>(
+            &[Token::NewtypeVariant {
+                name: "IpAddr",
+                variant: "V5",
+            }],
+            "unknown variant `V5`, expected `V4` or `V6`",
+        );
+    }
+}
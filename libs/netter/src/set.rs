@@ -0,0 +1,586 @@
+use alloc::vec::Vec;
+use core::ops::{BitAnd, BitOr, Not, Sub};
+
+use crate::bits::Bits;
+use crate::{AddrRange, IpAddr, IpNet, Nets, ipv4, ipv6};
+
+// IpSet is a set of addresses of both families. It is stored as the sorted
+// list of disjoint ranges it covers, one list per family, with ranges that
+// touch merged, so its size depends on how fragmented the set is rather
+// than on how many addresses or prefixes went into it. Lookups are a binary
+// search, and the set operations walk the two lists side by side in time
+// linear in their lengths.
+//
+// Building a set from an iterator sorts and merges everything in one go,
+// which is the fast way to load millions of entries:
+//
+//   let allowed: IpSet = feeds.iter().flat_map(|feed| feed.nets()).collect();
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IpSet {
+    v4: Vec<(u32, u32)>,
+    v6: Vec<(u128, u128)>,
+}
+
+impl IpSet {
+    // new returns the empty set.
+    pub const fn new() -> IpSet {
+        IpSet {
+            v4: Vec::new(),
+            v6: Vec::new(),
+        }
+    }
+
+    // is_empty returns true if the set has no addresses.
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    // insert adds an address, a prefix or a range to the set.
+    pub fn insert(&mut self, r: impl Into<AddrRange>) {
+        match r.into() {
+            AddrRange::V4(r) => insert(&mut self.v4, (r.start().to_bits(), r.end().to_bits())),
+            AddrRange::V6(r) => insert(&mut self.v6, (r.start().to_bits(), r.end().to_bits())),
+        }
+    }
+
+    // remove takes an address, a prefix or a range out of the set.
+    pub fn remove(&mut self, r: impl Into<AddrRange>) {
+        match r.into() {
+            AddrRange::V4(r) => remove(&mut self.v4, (r.start().to_bits(), r.end().to_bits())),
+            AddrRange::V6(r) => remove(&mut self.v6, (r.start().to_bits(), r.end().to_bits())),
+        }
+    }
+
+    // contains returns true if addr is in the set. The zone of an IPv6
+    // address is ignored.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match addr {
+            IpAddr::V4(a) => covers(&self.v4, (a.to_bits(), a.to_bits())),
+            IpAddr::V6(a) => covers(&self.v6, (a.to_bits(), a.to_bits())),
+        }
+    }
+
+    // contains_range returns true if every address of an address, a prefix
+    // or a range is in the set.
+    pub fn contains_range(&self, r: impl Into<AddrRange>) -> bool {
+        match r.into() {
+            AddrRange::V4(r) => covers(&self.v4, (r.start().to_bits(), r.end().to_bits())),
+            AddrRange::V6(r) => covers(&self.v6, (r.start().to_bits(), r.end().to_bits())),
+        }
+    }
+
+    // union returns the addresses in either set.
+    pub fn union(&self, other: &IpSet) -> IpSet {
+        IpSet {
+            v4: union(&self.v4, &other.v4),
+            v6: union(&self.v6, &other.v6),
+        }
+    }
+
+    // intersection returns the addresses in both sets.
+    pub fn intersection(&self, other: &IpSet) -> IpSet {
+        IpSet {
+            v4: intersection(&self.v4, &other.v4),
+            v6: intersection(&self.v6, &other.v6),
+        }
+    }
+
+    // difference returns the addresses in this set and not in other.
+    pub fn difference(&self, other: &IpSet) -> IpSet {
+        IpSet {
+            v4: difference(&self.v4, &other.v4),
+            v6: difference(&self.v6, &other.v6),
+        }
+    }
+
+    // complement returns the addresses not in the set, each family within
+    // its own address space: the complement of a set of IPv4 addresses has
+    // every other IPv4 address and every IPv6 address.
+    pub fn complement(&self) -> IpSet {
+        IpSet {
+            v4: complement(&self.v4),
+            v6: complement(&self.v6),
+        }
+    }
+
+    // ranges returns an iterator over the disjoint ranges that make up the
+    // set, in order: IPv4 first, then IPv6. Ranges that touch are always
+    // merged, so no two ranges are next to each other.
+    pub fn ranges(&self) -> Ranges<'_> {
+        Ranges {
+            v4: self.v4.iter(),
+            v6: self.v6.iter(),
+        }
+    }
+
+    // aggregate returns an iterator over the fewest prefixes that cover
+    // exactly the set, in order.
+    pub fn aggregate(&self) -> Aggregate<'_> {
+        Aggregate {
+            ranges: self.ranges(),
+            nets: None,
+        }
+    }
+}
+
+impl<R: Into<AddrRange>> FromIterator<R> for IpSet {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> IpSet {
+        let mut set = IpSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<R: Into<AddrRange>> Extend<R> for IpSet {
+    // extend adds everything at once: the new ranges are appended, and the
+    // whole list is sorted and merged again only at the end.
+    fn extend<I: IntoIterator<Item = R>>(&mut self, iter: I) {
+        for r in iter {
+            match r.into() {
+                AddrRange::V4(r) => self.v4.push((r.start().to_bits(), r.end().to_bits())),
+                AddrRange::V6(r) => self.v6.push((r.start().to_bits(), r.end().to_bits())),
+            }
+        }
+        normalize(&mut self.v4);
+        normalize(&mut self.v6);
+    }
+}
+
+impl BitOr for &IpSet {
+    type Output = IpSet;
+
+    fn bitor(self, other: &IpSet) -> IpSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for &IpSet {
+    type Output = IpSet;
+
+    fn bitand(self, other: &IpSet) -> IpSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for &IpSet {
+    type Output = IpSet;
+
+    fn sub(self, other: &IpSet) -> IpSet {
+        self.difference(other)
+    }
+}
+
+impl Not for &IpSet {
+    type Output = IpSet;
+
+    fn not(self) -> IpSet {
+        self.complement()
+    }
+}
+
+// Ranges iterates over the ranges of a set. See IpSet::ranges.
+#[derive(Debug, Clone)]
+pub struct Ranges<'a> {
+    v4: core::slice::Iter<'a, (u32, u32)>,
+    v6: core::slice::Iter<'a, (u128, u128)>,
+}
+
+impl Iterator for Ranges<'_> {
+    type Item = AddrRange;
+
+    fn next(&mut self) -> Option<AddrRange> {
+        if let Some(&(start, end)) = self.v4.next() {
+            let r = ipv4::AddrRange::new(ipv4::Addr::from_bits(start), ipv4::Addr::from_bits(end));
+            return r.map(AddrRange::V4);
+        }
+        let &(start, end) = self.v6.next()?;
+        let r = ipv6::AddrRange::new(ipv6::Addr::from_bits(start), ipv6::Addr::from_bits(end));
+        r.map(AddrRange::V6)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.v4.len() + self.v6.len();
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Ranges<'_> {}
+
+// Aggregate iterates over the prefixes that make up a set. See
+// IpSet::aggregate.
+#[derive(Debug, Clone)]
+pub struct Aggregate<'a> {
+    ranges: Ranges<'a>,
+    nets: Option<Nets>,
+}
+
+impl Iterator for Aggregate<'_> {
+    type Item = IpNet;
+
+    fn next(&mut self) -> Option<IpNet> {
+        loop {
+            if let Some(net) = self.nets.as_mut().and_then(Iterator::next) {
+                return Some(net);
+            }
+            self.nets = Some(self.ranges.next()?.nets());
+        }
+    }
+}
+
+// The functions below work on lists of inclusive (start, end) ranges that
+// are sorted, disjoint and never touch, except that normalize takes any list
+// and makes it so.
+
+// touches reports whether a range ending at end is followed without a gap
+// by, or overlaps, one starting at start.
+fn touches<T: Bits>(end: T, start: T) -> bool {
+    end.succ().is_none_or(|next| start <= next)
+}
+
+// normalize sorts ranges and merges the ones that overlap or touch.
+fn normalize<T: Bits>(ranges: &mut Vec<(T, T)>) {
+    ranges.sort_unstable();
+    let mut n = 0;
+    for i in 0..ranges.len() {
+        let r = ranges[i];
+        if n > 0 && touches(ranges[n - 1].1, r.0) {
+            ranges[n - 1].1 = ranges[n - 1].1.max(r.1);
+        } else {
+            ranges[n] = r;
+            n += 1;
+        }
+    }
+    ranges.truncate(n);
+}
+
+// covers reports whether every address of r is in ranges.
+fn covers<T: Bits>(ranges: &[(T, T)], r: (T, T)) -> bool {
+    let i = ranges.partition_point(|&(_, end)| end < r.0);
+    ranges
+        .get(i)
+        .is_some_and(|&(start, end)| start <= r.0 && r.1 <= end)
+}
+
+// insert adds r to ranges, merging it with the ranges it touches.
+fn insert<T: Bits>(ranges: &mut Vec<(T, T)>, r: (T, T)) {
+    // ranges[i..j] are the ones r overlaps or touches.
+    let i = ranges.partition_point(|&(_, end)| !touches(end, r.0));
+    let j = ranges.partition_point(|&(start, _)| touches(r.1, start));
+    if i == j {
+        ranges.insert(i, r);
+        return;
+    }
+    let merged = (r.0.min(ranges[i].0), r.1.max(ranges[j - 1].1));
+    ranges.splice(i..j, [merged]);
+}
+
+// remove takes r out of ranges, keeping what is left of the ranges it cuts.
+fn remove<T: Bits>(ranges: &mut Vec<(T, T)>, r: (T, T)) {
+    // ranges[i..j] are the ones r overlaps.
+    let i = ranges.partition_point(|&(_, end)| end < r.0);
+    let j = ranges.partition_point(|&(start, _)| start <= r.1);
+    if i == j {
+        return;
+    }
+    let mut rest = Vec::with_capacity(2);
+    if let Some(before) = r.0.pred().filter(|&before| ranges[i].0 <= before) {
+        rest.push((ranges[i].0, before));
+    }
+    if let Some(after) = r.1.succ().filter(|&after| after <= ranges[j - 1].1) {
+        rest.push((after, ranges[j - 1].1));
+    }
+    ranges.splice(i..j, rest);
+}
+
+// union returns the ranges of the addresses in a or b.
+fn union<T: Bits>(a: &[(T, T)], b: &[(T, T)]) -> Vec<(T, T)> {
+    let mut out: Vec<(T, T)> = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let r = if j == b.len() || (i < a.len() && a[i] <= b[j]) {
+            i += 1;
+            a[i - 1]
+        } else {
+            j += 1;
+            b[j - 1]
+        };
+        match out.last_mut() {
+            Some(last) if touches(last.1, r.0) => last.1 = last.1.max(r.1),
+            _ => out.push(r),
+        }
+    }
+    out
+}
+
+// intersection returns the ranges of the addresses in both a and b.
+fn intersection<T: Bits>(a: &[(T, T)], b: &[(T, T)]) -> Vec<(T, T)> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].0.max(b[j].0);
+        let end = a[i].1.min(b[j].1);
+        if start <= end {
+            out.push((start, end));
+        }
+        // the range that ends first cannot overlap anything after the other.
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+// difference returns the ranges of the addresses in a and not in b.
+fn difference<T: Bits>(a: &[(T, T)], b: &[(T, T)]) -> Vec<(T, T)> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut j = 0;
+    for &(start, end) in a {
+        // skip the ranges of b before this one; they are before every later
+        // one too.
+        while j < b.len() && b[j].1 < start {
+            j += 1;
+        }
+        // cut out the ranges of b that overlap this one, left to right.
+        let mut next = Some(start);
+        let mut k = j;
+        while let Some(from) = next {
+            if k == b.len() || b[k].0 > end {
+                out.push((from, end));
+                break;
+            }
+            if let Some(before) = b[k].0.pred().filter(|&before| from <= before) {
+                out.push((from, before));
+            }
+            next = b[k].1.succ().filter(|&after| after <= end);
+            k += 1;
+        }
+    }
+    out
+}
+
+// complement returns the ranges of the addresses not in ranges.
+fn complement<T: Bits>(ranges: &[(T, T)]) -> Vec<(T, T)> {
+    let mut out = Vec::with_capacity(ranges.len() + 1);
+    let mut next = Some(T::MIN);
+    for &(start, end) in ranges {
+        if let (Some(from), Some(before)) = (next, start.pred())
+            && from <= before
+        {
+            out.push((from, before));
+        }
+        next = end.succ();
+    }
+    if let Some(from) = next {
+        out.push((from, T::MAX));
+    }
+    out
+}
+
+#[cfg(test)]
+mod set_tests {
+    use super::IpSet;
+    use crate::rng::Rng;
+    use crate::testing::{addr, net, range};
+    use crate::{AddrRange, IpNet, ipv4};
+
+    fn ranges(set: &IpSet) -> Vec<String> {
+        set.ranges().map(|r| r.to_string()).collect()
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = IpSet::new();
+        assert!(set.is_empty());
+        set.insert(net("10.0.0.0/24"));
+        set.insert(net("10.0.2.0/24"));
+        set.insert(addr("2001:db8::1"));
+        assert_eq!(
+            ranges(&set),
+            [
+                "10.0.0.0-10.0.0.255",
+                "10.0.2.0-10.0.2.255",
+                "2001:db8::1-2001:db8::1"
+            ]
+        );
+
+        // a range that fills the gap merges all three.
+        set.insert(net("10.0.1.0/24"));
+        assert_eq!(ranges(&set)[0], "10.0.0.0-10.0.2.255");
+        assert!(set.contains(addr("10.0.1.7")));
+        assert!(!set.contains(addr("10.0.3.0")));
+        assert!(set.contains_range(range("10.0.0.5", "10.0.2.17")));
+        assert!(!set.contains_range(net("10.0.0.0/16")));
+        assert!(!set.contains(addr("::ffff:10.0.1.7")));
+
+        // removing from the middle splits a range.
+        set.remove(range("10.0.1.0", "10.0.1.9"));
+        set.remove(addr("2001:db8::1"));
+        assert_eq!(
+            ranges(&set),
+            ["10.0.0.0-10.0.0.255", "10.0.1.10-10.0.2.255"]
+        );
+
+        // the ends of the address space.
+        let mut set = IpSet::new();
+        set.insert(range("255.255.255.0", "255.255.255.255"));
+        set.insert(range("0.0.0.0", "0.0.0.0"));
+        set.insert(range("0.0.0.1", "255.255.254.255"));
+        assert_eq!(ranges(&set), ["0.0.0.0-255.255.255.255"]);
+        set.remove(range("0.0.0.0", "0.0.0.0"));
+        set.remove(range("255.255.255.255", "255.255.255.255"));
+        assert_eq!(ranges(&set), ["0.0.0.1-255.255.255.254"]);
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let a: IpSet = ["10.0.0.0/8", "192.168.0.0/16", "2001:db8::/32"]
+            .map(net)
+            .into_iter()
+            .collect();
+        let b: IpSet = ["10.128.0.0/9", "172.16.0.0/12", "2001:db8:8000::/33"]
+            .map(net)
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            ranges(&(&a | &b)),
+            [
+                "10.0.0.0-10.255.255.255",
+                "172.16.0.0-172.31.255.255",
+                "192.168.0.0-192.168.255.255",
+                "2001:db8::-2001:db8:ffff:ffff:ffff:ffff:ffff:ffff",
+            ]
+        );
+        assert_eq!(
+            ranges(&(&a & &b)),
+            [
+                "10.128.0.0-10.255.255.255",
+                "2001:db8:8000::-2001:db8:ffff:ffff:ffff:ffff:ffff:ffff",
+            ]
+        );
+        assert_eq!(
+            ranges(&(&a - &b)),
+            [
+                "10.0.0.0-10.127.255.255",
+                "192.168.0.0-192.168.255.255",
+                "2001:db8::-2001:db8:7fff:ffff:ffff:ffff:ffff:ffff",
+            ]
+        );
+
+        let none = IpSet::new();
+        let all = !&none;
+        assert_eq!(
+            ranges(&all),
+            [
+                "0.0.0.0-255.255.255.255",
+                "::-ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"
+            ]
+        );
+        assert_eq!(!&all, none);
+        assert_eq!(&(!&a) | &a, all);
+        assert_eq!(&(!&a) & &a, none);
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let set: IpSet = [
+            "10.0.0.0/25",
+            "10.0.0.128/25",
+            "10.0.1.0/24",
+            "10.0.2.1/32",
+            "::/1",
+        ]
+        .map(net)
+        .into_iter()
+        .collect();
+        let got: Vec<IpNet> = set.aggregate().collect();
+        assert_eq!(got, ["10.0.0.0/23", "10.0.2.1/32", "::/1"].map(net));
+        assert_eq!(IpSet::new().aggregate().count(), 0);
+    }
+
+    #[test]
+    fn test_against_model() {
+        // every operation on random sets of the addresses 10.0.0.0 to
+        // 10.0.0.255 agrees with the same operation on a bitmap.
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        let base = ipv4::Addr::new(10, 0, 0, 0).to_bits();
+        let mut random_range = || {
+            let (a, b) = ((rng.next() % 256) as u32, (rng.next() % 256) as u32);
+            let r = ipv4::AddrRange::new(
+                ipv4::Addr::from_bits(base + a.min(b)),
+                ipv4::Addr::from_bits(base + a.max(b)),
+            );
+            r.unwrap()
+        };
+        let bitmap = |set: &IpSet| -> [bool; 256] {
+            core::array::from_fn(|i| set.contains(ipv4::Addr::from_bits(base + i as u32).into()))
+        };
+
+        for _ in 0..500 {
+            let mut a = IpSet::new();
+            let mut b = IpSet::new();
+            let mut want_a = [false; 256];
+            let mut want_b = [false; 256];
+            for _ in 0..4 {
+                for (set, want, remove) in
+                    [(&mut a, &mut want_a, false), (&mut b, &mut want_b, true)]
+                {
+                    let r = random_range();
+                    let (start, end) = (r.start().to_bits() - base, r.end().to_bits() - base);
+                    if remove && start % 3 == 0 {
+                        set.remove(r);
+                        want[start as usize..=end as usize].fill(false);
+                    } else {
+                        set.insert(r);
+                        want[start as usize..=end as usize].fill(true);
+                    }
+                }
+            }
+            assert_eq!(bitmap(&a), want_a, "{:?}", a);
+            assert_eq!(bitmap(&b), want_b, "{:?}", b);
+
+            let each = |f: fn(bool, bool) -> bool| -> [bool; 256] {
+                core::array::from_fn(|i| f(want_a[i], want_b[i]))
+            };
+            assert_eq!(bitmap(&(&a | &b)), each(|x, y| x || y), "{:?} | {:?}", a, b);
+            assert_eq!(bitmap(&(&a & &b)), each(|x, y| x && y), "{:?} & {:?}", a, b);
+            assert_eq!(
+                bitmap(&(&a - &b)),
+                each(|x, y| x && !y),
+                "{:?} - {:?}",
+                a,
+                b
+            );
+            assert_eq!(bitmap(&!&a), each(|x, _| !x), "!{:?}", a);
+
+            // the ranges are sorted and never touch, which is what keeps the
+            // representation unique.
+            let bounds: Vec<(u32, u32)> = a
+                .ranges()
+                .map(|r| match r {
+                    AddrRange::V4(r) => (r.start().to_bits(), r.end().to_bits()),
+                    AddrRange::V6(_) => unreachable!(),
+                })
+                .collect();
+            for pair in bounds.windows(2) {
+                assert!(pair[0].1 + 1 < pair[1].0, "{:?} touch in {:?}", pair, a);
+            }
+            let rebuilt: IpSet = a.aggregate().collect();
+            assert_eq!(rebuilt, a);
+        }
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        // every address of 10.0.0.0/12, a million of them, in scrambled
+        // order: multiplying by an odd number permutes the offsets.
+        let base = ipv4::Addr::new(10, 0, 0, 0).to_bits();
+        let addrs =
+            (0..1u32 << 20).map(|i| ipv4::Addr::from_bits(base + i.wrapping_mul(7919) % (1 << 20)));
+        let set: IpSet = addrs.collect();
+        assert_eq!(ranges(&set), ["10.0.0.0-10.15.255.255"]);
+        assert_eq!(set.aggregate().collect::<Vec<_>>(), [net("10.0.0.0/12")]);
+    }
+}
@@ -0,0 +1,82 @@
+// testing has the fixtures and checks that the test modules share. The
+// helpers parse a value the test knows to be valid, so that case tables can
+// be written as strings.
+use crate::{AddrRange, IpAddr, IpNet};
+
+pub(crate) fn addr(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+pub(crate) fn net(s: &str) -> IpNet {
+    s.parse().unwrap()
+}
+
+// range returns the range from start to end, which must be in order.
+pub(crate) fn range(start: &str, end: &str) -> AddrRange {
+    AddrRange::new(addr(start), addr(end)).unwrap()
+}
+
+// matches_bits checks that the arithmetic and bitwise operators of the
+// address type $addr agree with the same ones on its integer, for pairs of
+// integers drawn by $random. The step n is small half the time, so that the
+// edges of the address space are hit as well as the wraps.
+macro_rules! matches_bits {
+    ($addr:ty, $random:expr) => {{
+        let mut rng = $crate::rng::Rng::new(0x9e3779b97f4a7c15);
+        for _ in 0..10_000 {
+            let x = ($random)(&mut rng);
+            let y = ($random)(&mut rng);
+            let n = if x.is_multiple_of(2) { y } else { y % 4 };
+            let (a, b) = (<$addr>::from_bits(x), <$addr>::from_bits(y));
+
+            assert_eq!(a.checked_add(n), x.checked_add(n).map(<$addr>::from_bits));
+            assert_eq!(a.checked_sub(n), x.checked_sub(n).map(<$addr>::from_bits));
+            assert_eq!(a.saturating_add(n).to_bits(), x.saturating_add(n));
+            assert_eq!(a.saturating_sub(n).to_bits(), x.saturating_sub(n));
+            assert_eq!(a.wrapping_add(n).to_bits(), x.wrapping_add(n));
+            assert_eq!(a.wrapping_sub(n).to_bits(), x.wrapping_sub(n));
+            assert_eq!(a.distance(b), x.abs_diff(y));
+            assert_eq!((a & b).to_bits(), x & y);
+            assert_eq!((a | b).to_bits(), x | y);
+            assert_eq!((a ^ b).to_bits(), x ^ y);
+            assert_eq!((!a).to_bits(), !x);
+        }
+    }};
+}
+
+pub(crate) use matches_bits;
+
+// ipv4 has the same helpers for the ipv4 types.
+pub(crate) mod ipv4 {
+    use crate::ipv4::{Addr, AddrRange, Prefix};
+
+    pub(crate) fn addr(s: &str) -> Addr {
+        s.parse().unwrap()
+    }
+
+    pub(crate) fn prefix(s: &str) -> Prefix {
+        s.parse().unwrap()
+    }
+
+    pub(crate) fn range(start: &str, end: &str) -> AddrRange {
+        AddrRange::new(addr(start), addr(end)).unwrap()
+    }
+}
+
+// ipv6 has the same helpers for the ipv6 types. addr parses with the
+// permissive options, so that an address may have a zone.
+pub(crate) mod ipv6 {
+    use crate::ipv6::{Addr, AddrRange, ParseOptions, Prefix};
+
+    pub(crate) fn addr(s: &str) -> Addr {
+        ParseOptions::permissive().parse(s).unwrap()
+    }
+
+    pub(crate) fn prefix(s: &str) -> Prefix {
+        s.parse().unwrap()
+    }
+
+    pub(crate) fn range(start: &str, end: &str) -> AddrRange {
+        AddrRange::new(addr(start), addr(end)).unwrap()
+    }
+}